@@ -1,9 +1,30 @@
 // agent.rs
 
+use crate::backend::ModelBackend;
+use crate::cache::ResponseCache;
+use crate::config::Verbosity;
+use crate::error::Result;
+use crate::memory::{GlobalMemory, MAX_FACTS};
 use crate::personality::Personality;
+use crate::prompt::{self, PromptTemplate};
+use crate::repetition::similarity;
+use crate::sentiment::Sentiment;
 use crate::state::AgentState;
-use ollama_rs::generation::completion::request::GenerationRequest;
-use ollama_rs::Ollama;
+use crate::tool::Tool;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Maximum number of entries kept in an agent's conversation history.
+pub const MAX_HISTORY: usize = 10;
+
+/// Added to an agent's talkativeness when the topic matches its expertise.
+pub const EXPERTISE_BOOST: f32 = 0.3;
+
+/// Weight of the newest message in an agent's rolling mood.
+pub const MOOD_SMOOTHING: f32 = 0.3;
+
+/// Number of an agent's own latest responses a new one is checked against for repetition.
+pub const RECENT_RESPONSES: usize = 5;
 
 /// Represents an autonomous agent in the simulation.
 #[derive(Debug, Clone)]
@@ -17,6 +38,12 @@ pub struct Agent {
     /// Current energy level of the agent.
     pub energy: f32,
 
+    /// Rolling tone of the agent's recent exchanges, from -1 (tense) to 1 (happy).
+    pub mood: f32,
+
+    /// Current (x, y) position of the agent in the world.
+    pub position: (i32, i32),
+
     /// Agent's personality traits influencing its behavior.
     pub personality: Personality,
 
@@ -28,6 +55,70 @@ pub struct Agent {
 
     /// Stores messages heard during the current tick.
     pub next_prompt: String,
+
+    /// Backend used to generate responses.
+    pub backend: Arc<dyn ModelBackend>,
+
+    /// Template of the prompt sent to the model.
+    pub prompt_template: PromptTemplate,
+
+    /// Template of the prompt sent for the turn opening a discussion.
+    pub opening_prompt_template: PromptTemplate,
+
+    /// Text put before every prompt, separated by a blank line (nothing when empty).
+    pub prompt_prefix: String,
+
+    /// Text put after every prompt, separated by a blank line (nothing when empty).
+    pub prompt_suffix: String,
+
+    /// Whether the agent's next turn opens a discussion; cleared once it has spoken.
+    pub opening: bool,
+
+    /// Responses already generated for identical prompts, if caching is enabled.
+    pub response_cache: Option<ResponseCache>,
+
+    /// Probability (0-1) of responding on a tick when the agent has heard something.
+    pub talkativeness: f32,
+
+    /// Maximum number of tokens per response, if limited.
+    pub max_tokens: Option<u32>,
+
+    /// Estimated tokens of prompt prefix, suffix, history and recent messages the prompt may
+    /// hold, if limited.
+    pub context_tokens: Option<usize>,
+
+    /// How long the agent's responses should be.
+    pub verbosity: Verbosity,
+
+    /// Facts shared by every agent in the simulation.
+    pub memory: GlobalMemory,
+
+    /// Past messages recalled as relevant to what the agent is about to answer.
+    pub recalled: Vec<String>,
+
+    /// Subjects the agent cares about besides the discussion topic.
+    pub interests: Vec<String>,
+
+    /// Objective the agent pursues in the discussion, if any.
+    pub goal: Option<String>,
+
+    /// Keywords of the topics the agent is an expert on.
+    pub expertise: Vec<String>,
+
+    /// Language the agent responds in, if constrained.
+    pub language: Option<String>,
+
+    /// Tool answering in place of the model, for tool agents.
+    pub tool: Option<Arc<dyn Tool>>,
+
+    /// Whether the user silenced the agent; it keeps hearing but does not speak.
+    pub muted: bool,
+
+    /// Room of the last room message the agent heard, where it answers.
+    pub room: Option<String>,
+
+    /// The agent's latest responses, oldest first, up to `RECENT_RESPONSES`.
+    pub recent_responses: VecDeque<String>,
 }
 
 impl Agent {
@@ -40,18 +131,49 @@ impl Agent {
     /// * `initial_energy` - Starting energy level.
     /// * `initial_position` - Initial (x, y) coordinates.
     /// * `ollama_model` - The Ollama model to be used by the agent.
+    /// * `backend` - The backend generating the agent's responses.
     ///
     /// # Returns
     /// * A new `Agent` instance.
-    pub fn new(name: String, personality: Personality, initial_energy: f32, ollama_model: String) -> Self {
+    pub fn new(
+        name: String,
+        personality: Personality,
+        initial_energy: f32,
+        initial_position: (i32, i32),
+        ollama_model: String,
+        backend: Arc<dyn ModelBackend>,
+    ) -> Self {
         Self {
             name,
+            talkativeness: personality.extraversion,
             state: AgentState::Idle,
             energy: initial_energy,
+            mood: 0.0,
+            position: initial_position,
             personality,
             conversation_history: Vec::new(),
             ollama_model, // Use the provided model
             next_prompt: String::new(),
+            backend,
+            prompt_template: PromptTemplate::default(),
+            opening_prompt_template: PromptTemplate::opening(),
+            prompt_prefix: String::new(),
+            prompt_suffix: String::new(),
+            opening: false,
+            response_cache: None,
+            max_tokens: None,
+            context_tokens: None,
+            verbosity: Verbosity::default(),
+            memory: GlobalMemory::default(),
+            recalled: Vec::new(),
+            interests: Vec::new(),
+            goal: None,
+            expertise: Vec::new(),
+            language: None,
+            tool: None,
+            muted: false,
+            room: None,
+            recent_responses: VecDeque::new(),
         }
     }
 
@@ -60,28 +182,128 @@ impl Agent {
         self.ollama_model = model;
     }
 
+    /// Appends an entry to the conversation history, dropping the oldest past `MAX_HISTORY`.
+    pub fn remember(&mut self, entry: String) {
+        self.conversation_history.push(entry);
+        if self.conversation_history.len() > MAX_HISTORY {
+            let excess = self.conversation_history.len() - MAX_HISTORY;
+            self.conversation_history.drain(..excess);
+        }
+    }
+
+    /// Keeps a response among the agent's latest ones, dropping the oldest past
+    /// `RECENT_RESPONSES`.
+    pub fn record_response(&mut self, response: &str) {
+        self.recent_responses.push_back(response.to_string());
+        if self.recent_responses.len() > RECENT_RESPONSES {
+            self.recent_responses.pop_front();
+        }
+    }
+
+    /// Whether a response is at least `threshold` similar to one of the agent's latest ones.
+    pub fn repeats(&self, response: &str, threshold: f32) -> bool {
+        self.recent_responses
+            .iter()
+            .any(|recent| similarity(recent, response) >= threshold)
+    }
+
+    /// Folds the tone of a message the agent sent or heard into its mood.
+    ///
+    /// The mood is an exponential moving average of message sentiment scores, so each message
+    /// moves it `MOOD_SMOOTHING` of the way towards its own tone and older ones fade out.
+    pub fn update_mood(&mut self, sentiment: Sentiment) {
+        self.mood += MOOD_SMOOTHING * (sentiment.score() - self.mood);
+    }
+
+    /// Moves the messages heard this tick into the conversation history and clears them.
+    pub fn archive_prompt(&mut self) {
+        let heard = std::mem::take(&mut self.next_prompt);
+        for line in heard.lines() {
+            self.remember(line.to_string());
+        }
+    }
+
+    /// Sets the template of the prompt sent to the model.
+    pub fn set_prompt_template(&mut self, template: PromptTemplate) {
+        self.prompt_template = template;
+    }
+
     /// Generates a response based on the agent's stored prompt.
     ///
+    /// # Arguments
+    /// * `topic` - The current discussion topic, if any.
+    ///
     /// # Returns
     /// * `Ok(String)` containing the response text.
-    /// * `Err(String)` if the response could not be generated.
+    /// * `Err(Error)` if the response could not be generated.
     ///
     /// # TODO:
     /// - Improve contextual awareness by prioritizing recent inputs.
     /// - Introduce energy-based behavior (e.g., tired agents respond differently).
-    pub(crate) async fn generate_response_from_prompt(&self) -> Result<String, String> {
-        let ollama = Ollama::default();
+    pub(crate) async fn generate_response_from_prompt(
+        &self,
+        topic: Option<&str>,
+    ) -> Result<String> {
+        let prompt = self.full_prompt(topic);
+
+        // An identical prompt already answered is not sent again
+        if let Some(response) = self
+            .response_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&self.ollama_model, &prompt))
+        {
+            return Ok(enforce_budget(response, self.max_tokens));
+        }
+
+        // Send request to the AI model
+        let response = self
+            .backend
+            .generate(&self.ollama_model, &prompt, self.max_tokens)
+            .await?;
+
+        // Empty responses are retried, so they are never cached
+        if let Some(cache) = &self.response_cache {
+            if !response.trim().is_empty() {
+                cache.insert(&self.ollama_model, &prompt, &response);
+            }
+        }
+        Ok(enforce_budget(response, self.max_tokens))
+    }
 
+    /// Renders the prompt the agent sends to the model, wrapped in the global prefix and
+    /// suffix.
+    ///
+    /// # Arguments
+    /// * `topic` - The current discussion topic, if any.
+    ///
+    /// # Returns
+    /// * The prompt as sent to the model.
+    pub fn full_prompt(&self, topic: Option<&str>) -> String {
+        let mut prompt = self.build_prompt(topic);
+        if !self.prompt_prefix.is_empty() {
+            prompt = format!("{}\n\n{}", self.prompt_prefix, prompt);
+        }
+        if !self.prompt_suffix.is_empty() {
+            prompt = format!("{}\n\n{}", prompt, self.prompt_suffix);
+        }
+        prompt
+    }
+
+    /// Renders the prompt the agent sends to the model to respond.
+    ///
+    /// # Arguments
+    /// * `topic` - The current discussion topic, if any.
+    ///
+    /// # Returns
+    /// * The prompt, built from the agent's template.
+    pub fn build_prompt(&self, topic: Option<&str>) -> String {
         // Construct personality description
-        let personality_desc = format!(
-            "You are {}, an AI agent with the following personality traits:\n\
-            - Openness: {}/10\n\
+        let personality = format!(
+            "- Openness: {}/10\n\
             - Conscientiousness: {}/10\n\
             - Extraversion: {}/10\n\
             - Agreeableness: {}/10\n\
-            - Neuroticism: {}/10\n\
-            Respond concisely (max 2-3 sentences) while staying in character.",
-            self.name,
+            - Neuroticism: {}/10",
             (self.personality.openness * 10.0) as i32,
             (self.personality.conscientiousness * 10.0) as i32,
             (self.personality.extraversion * 10.0) as i32,
@@ -89,20 +311,574 @@ impl Agent {
             (self.personality.neuroticism * 10.0) as i32
         );
 
-        // Conversation history
-        let history = self.conversation_history.join("\n");
+        // Conversation history and recent messages, trimmed to the context budget
+        let (history, recent, _) = self.context();
+
+        // The goal line disappears entirely for agents without one
+        let goal = self
+            .goal
+            .as_ref()
+            .map_or_else(String::new, |goal| format!("Your goal: {}\n", goal));
+
+        // Agents without expertise neither claim nor disclaim any
+        let expertise = match topic {
+            Some(topic) if !self.expertise.is_empty() => match self.expertise_in(topic) {
+                Some(keyword) => format!(
+                    "You are an expert on {}, so speak with authority.\n",
+                    keyword
+                ),
+                None => format!(
+                    "You are uncertain about {}, so defer to those who know more.\n",
+                    topic
+                ),
+            },
+            _ => String::new(),
+        };
+
+        // Without a language, the model answers in whatever language it picks
+        let language = self.language.as_ref().map_or_else(String::new, |language| {
+            format!("Respond in {}.\n", language)
+        });
+
+        let memories = self.recalled.join("\n");
+
+        // The agent opening a discussion frames it instead of reacting to it
+        let mut template = if self.opening {
+            self.opening_prompt_template.clone()
+        } else {
+            self.prompt_template.clone()
+        };
+
+        // Empty sections are left out rather than shown with a bare heading, which models
+        // handle awkwardly on the first turn
+        if history.is_empty() {
+            template = template.without_section("history");
+        }
+        if recent.trim().is_empty() {
+            template = template.without_section("recent");
+        }
+        if self.memory.facts().is_empty() {
+            template = template.without_section("facts");
+        }
+        if memories.is_empty() {
+            template = template.without_section("memories");
+        }
+        template.render(&[
+            ("name", &self.name),
+            ("personality", &personality),
+            ("verbosity", self.verbosity.instruction()),
+            ("goal", &goal),
+            ("expertise", &expertise),
+            ("language", &language),
+            ("history", &history),
+            ("recent", &recent),
+            ("topic", topic.unwrap_or("none")),
+            ("facts", &self.memory.describe()),
+            ("memories", &memories),
+        ])
+    }
+
+    /// Finds the expertise keyword a topic matches, case-insensitively.
+    ///
+    /// # Arguments
+    /// * `topic` - The discussion topic.
+    ///
+    /// # Returns
+    /// * The first keyword contained in the topic, or `None` if the agent is no expert on it.
+    pub fn expertise_in(&self, topic: &str) -> Option<&str> {
+        let topic = topic.to_lowercase();
+        self.expertise
+            .iter()
+            .map(|keyword| keyword.trim())
+            .find(|keyword| !keyword.is_empty() && topic.contains(&keyword.to_lowercase()))
+    }
+
+    /// Returns the probability that the agent responds on a tick, raised on topics it is an
+    /// expert on.
+    ///
+    /// # Arguments
+    /// * `topic` - The discussion topic, if any.
+    pub fn speaking_probability(&self, topic: Option<&str>) -> f32 {
+        match topic.and_then(|topic| self.expertise_in(topic)) {
+            Some(_) => (self.talkativeness + EXPERTISE_BOOST).min(1.0),
+            None => self.talkativeness,
+        }
+    }
+
+    /// Returns the conversation history and recent messages to include in the prompt.
+    ///
+    /// # Returns
+    /// * The history, the recent messages, and whether older content was dropped to fit
+    ///   `context_tokens`.
+    pub fn context(&self) -> (String, String, bool) {
+        match self.context_tokens {
+            Some(budget) => {
+                let (history, recent, trimmed) = fit_context(
+                    &self.conversation_history,
+                    &self.next_prompt,
+                    self.context_budget(budget),
+                );
+                (history.join("\n"), recent, trimmed)
+            }
+            None => (
+                self.conversation_history.join("\n"),
+                self.next_prompt.clone(),
+                false,
+            ),
+        }
+    }
+
+    /// Drops the history and recent messages that do not fit `context_tokens`, so the prompts
+    /// rendered for this turn, retries included, all reuse the same context.
+    ///
+    /// Meant for the copy of the agent a turn is generated with, since the dropped entries
+    /// are lost.
+    ///
+    /// # Returns
+    /// * Whether older content was dropped to fit.
+    pub fn trim_context(&mut self) -> bool {
+        let Some(budget) = self.context_tokens.take() else {
+            return false;
+        };
+        let (history, recent, trimmed) = fit_context(
+            &self.conversation_history,
+            &self.next_prompt,
+            self.context_budget(budget),
+        );
+        self.conversation_history = history;
+        self.next_prompt = recent;
+        trimmed
+    }
+
+    /// Returns the part of a context budget left for history and recent messages once the
+    /// prompt prefix and suffix, sent with every prompt, are counted.
+    ///
+    /// # Arguments
+    /// * `budget` - The agent's `context_tokens`.
+    fn context_budget(&self, budget: usize) -> usize {
+        budget.saturating_sub(
+            estimate_tokens(&self.prompt_prefix) + estimate_tokens(&self.prompt_suffix),
+        )
+    }
+
+    /// Generates an updated list of the facts established in the discussion.
+    ///
+    /// # Arguments
+    /// * `topic` - The current discussion topic, if any.
+    /// * `recent` - The latest messages of the discussion, one per line.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the facts, one per line.
+    /// * `Err(Error)` if the facts could not be generated.
+    pub(crate) async fn generate_memory_update(
+        &self,
+        topic: Option<&str>,
+        recent: &str,
+    ) -> Result<String> {
+        let prompt = format!(
+            "You keep the shared memory of a discussion between AI agents about {}.\n\n\
+            Known facts:\n{}\n\n\
+            Recent messages:\n{}\n\n\
+            Update the known facts: keep those still true and add what the recent messages \
+            established. Reply with at most {} short facts, one per line starting with \"- \".",
+            topic.unwrap_or("any subject"),
+            self.memory.describe(),
+            recent,
+            MAX_FACTS
+        );
+
+        let response = self
+            .backend
+            .generate(&self.ollama_model, &prompt, self.max_tokens)
+            .await?;
+        Ok(response)
+    }
 
-        // Final prompt including recent messages
+    /// Generates an assessment of how close the agent is to its goal.
+    ///
+    /// # Arguments
+    /// * `goal` - The agent's goal.
+    /// * `recent` - The latest messages of the discussion, one per line.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing a score out of 10 followed by a short reason.
+    /// * `Err(Error)` if the assessment could not be generated.
+    pub(crate) async fn generate_goal_assessment(
+        &self,
+        goal: &str,
+        recent: &str,
+    ) -> Result<String> {
         let prompt = format!(
-            "{}\n\nConversation history:\n{}\n\nRecent messages:\n{}\n\nHow would you respond?",
-            personality_desc, history, self.next_prompt
+            "Recent messages of a discussion between AI agents:\n{}\n\n\
+            {}'s goal is: {}\n\n\
+            How close is {} to achieving this goal? Reply with a score from 0 (no progress) to \
+            10 (achieved), followed by a short reason.",
+            recent, self.name, goal, self.name
         );
 
-        // Send request to the AI model
-        let request = GenerationRequest::new(self.ollama_model.clone(), prompt);
-        match ollama.generate(request).await {
-            Ok(response) => Ok(response.response),
-            Err(e) => Err(format!("Generation error: {}", e)),
+        let response = self
+            .backend
+            .generate(&self.ollama_model, &prompt, self.max_tokens)
+            .await?;
+        Ok(response)
+    }
+
+    /// Generates a moderator intervention steering the discussion.
+    ///
+    /// # Arguments
+    /// * `topic` - The current discussion topic, if any.
+    /// * `recent` - The latest messages of the discussion, one per line.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the intervention text.
+    /// * `Err(Error)` if the intervention could not be generated.
+    pub(crate) async fn generate_moderation(
+        &self,
+        topic: Option<&str>,
+        recent: &str,
+    ) -> Result<String> {
+        let prompt = format!(
+            "You are {}, the moderator of a discussion between AI agents about {}.\n\n\
+            Recent messages:\n{}\n\n\
+            Steer the discussion in 1-2 sentences: summarize the main points, ask a follow-up \
+            question, or move on to a related subtopic. Do not give your own opinion.",
+            self.name,
+            topic.unwrap_or("any subject they like"),
+            recent
+        );
+
+        let response = self
+            .backend
+            .generate(&self.ollama_model, &prompt, self.max_tokens)
+            .await?;
+        Ok(enforce_budget(response, self.max_tokens))
+    }
+
+    /// Generates a summary of the discussion, for the user or for its participants.
+    ///
+    /// # Arguments
+    /// * `template` - The summary prompt, with `{name}`, `{topic}`, and `{recent}` placeholders.
+    /// * `topic` - The current discussion topic, if any.
+    /// * `recent` - The latest messages of the discussion, one per line.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the summary.
+    /// * `Err(Error)` if the summary could not be generated.
+    pub(crate) async fn generate_observation(
+        &self,
+        template: &str,
+        topic: Option<&str>,
+        recent: &str,
+    ) -> Result<String> {
+        let prompt = prompt::render(
+            template,
+            &[
+                ("name", &self.name),
+                ("topic", topic.unwrap_or("any subject they like")),
+                ("recent", recent),
+            ],
+        );
+
+        let response = self
+            .backend
+            .generate(&self.ollama_model, &prompt, self.max_tokens)
+            .await?;
+        Ok(enforce_budget(response, self.max_tokens))
+    }
+}
+
+/// Marker appended to responses cut at the token budget.
+pub const TRUNCATION_MARKER: &str = " […]";
+
+/// Cuts a response exceeding the token budget and flags it with `TRUNCATION_MARKER`.
+///
+/// Tokens are approximated by whitespace-separated words, which backends that ignore the
+/// limit may overrun.
+///
+/// # Arguments
+/// * `response` - The generated text.
+/// * `max_tokens` - The token budget, if any.
+///
+/// # Returns
+/// * The response, truncated and flagged when over budget.
+fn enforce_budget(response: String, max_tokens: Option<u32>) -> String {
+    let Some(max_tokens) = max_tokens else {
+        return response;
+    };
+    let words: Vec<&str> = response.split_whitespace().collect();
+    if words.len() <= max_tokens as usize {
+        return response;
+    }
+    format!(
+        "{}{}",
+        words[..max_tokens as usize].join(" "),
+        TRUNCATION_MARKER
+    )
+}
+
+/// Reads the score out of 10 at the start of a goal assessment.
+///
+/// # Returns
+/// * The first number in the reply, capped at 10, or `None` if there is none.
+pub fn parse_goal_score(reply: &str) -> Option<u8> {
+    let digits: String = reply
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse::<u32>().ok().map(|score| score.min(10) as u8)
+}
+
+/// Estimates the number of tokens in a text, at about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Drops the oldest history entries, then the oldest recent lines, until both fit a budget.
+///
+/// A single recent line that is still too long keeps only its end.
+///
+/// # Arguments
+/// * `history` - The agent's conversation history, oldest first.
+/// * `recent` - The messages heard since the agent last spoke, one per line.
+/// * `budget` - The estimated number of tokens both may take together.
+///
+/// # Returns
+/// * The history entries kept, the recent messages, and whether anything was dropped.
+fn fit_context(history: &[String], recent: &str, budget: usize) -> (Vec<String>, String, bool) {
+    if estimate_tokens(&history.join("\n")) + estimate_tokens(recent) <= budget {
+        return (history.to_vec(), recent.to_string(), false);
+    }
+
+    let mut history: VecDeque<&str> = history.iter().map(String::as_str).collect();
+    let mut recent: VecDeque<&str> = recent.lines().collect();
+    let size = |history: &VecDeque<&str>, recent: &VecDeque<&str>| {
+        let chars: usize = history
+            .iter()
+            .chain(recent.iter())
+            .map(|line| line.chars().count() + 1)
+            .sum();
+        chars.div_ceil(4)
+    };
+
+    let mut dropped = false;
+    while size(&history, &recent) > budget {
+        if history.pop_front().is_none() && (recent.len() <= 1 || recent.pop_front().is_none()) {
+            break;
+        }
+        dropped = true;
+    }
+
+    let mut recent_text: String = recent.iter().map(|line| format!("{}\n", line)).collect();
+    let max_chars = budget * 4;
+    let chars = recent_text.chars().count();
+    if history.is_empty() && chars > max_chars {
+        recent_text = recent_text.chars().skip(chars - max_chars).collect();
+        dropped = true;
+    }
+
+    let history = history.into_iter().map(str::to_string).collect();
+    (history, recent_text, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    /// Returns a balanced agent named Alice on the mock backend.
+    fn test_agent() -> Agent {
+        Agent::new(
+            "Alice".to_string(),
+            Personality::new(0.5, 0.5, 0.5, 0.5, 0.5),
+            100.0,
+            (0, 0),
+            "mock".to_string(),
+            Arc::new(MockBackend::new(Vec::new())),
+        )
+    }
+
+    #[test]
+    fn test_history_stays_bounded() {
+        let mut agent = test_agent();
+
+        for i in 0..(MAX_HISTORY + 5) {
+            agent
+                .next_prompt
+                .push_str(&format!("[Bob→Alice]: heard {}\n", i));
+            agent.archive_prompt();
+            agent.remember(format!("[Alice→Bob]: reply {}", i));
         }
+
+        assert_eq!(agent.conversation_history.len(), MAX_HISTORY);
+        assert_eq!(
+            agent.conversation_history.last().map(String::as_str),
+            Some(format!("[Alice→Bob]: reply {}", MAX_HISTORY + 4).as_str())
+        );
+        assert!(agent.next_prompt.is_empty());
+    }
+
+    #[test]
+    fn test_over_budget_response_is_truncated_and_flagged() {
+        let response = "one two three four five".to_string();
+        assert_eq!(enforce_budget(response.clone(), None), response);
+        assert_eq!(enforce_budget(response.clone(), Some(5)), response);
+        assert_eq!(
+            enforce_budget(response, Some(2)),
+            format!("one two{}", TRUNCATION_MARKER)
+        );
+    }
+
+    #[test]
+    fn test_prompt_follows_verbosity() {
+        let mut agent = test_agent();
+        assert!(agent
+            .build_prompt(None)
+            .contains("Respond concisely (max 2-3 sentences) while staying in character."));
+
+        agent.verbosity = Verbosity::Terse;
+        assert!(agent
+            .build_prompt(None)
+            .contains("Respond in a single short sentence while staying in character."));
+    }
+
+    #[test]
+    fn test_goal_shapes_prompt() {
+        let mut agent = test_agent();
+        assert!(!agent.build_prompt(None).contains("Your goal:"));
+
+        agent.goal = Some("convince others to adopt solar power".to_string());
+        assert!(agent
+            .build_prompt(None)
+            .contains("in character.\nYour goal: convince others to adopt solar power\n\n"));
+
+        assert_eq!(parse_goal_score("7/10 - good arguments"), Some(7));
+        assert_eq!(parse_goal_score("Score: 12"), Some(10));
+        assert_eq!(parse_goal_score("no idea"), None);
+    }
+
+    #[test]
+    fn test_language_constrains_prompt() {
+        let mut agent = test_agent();
+        assert!(!agent.build_prompt(None).contains("Respond in"));
+
+        agent.language = Some("French".to_string());
+        assert!(agent
+            .build_prompt(None)
+            .contains("in character.\nRespond in French.\n\n"));
+    }
+
+    #[test]
+    fn test_empty_history_is_left_out_of_prompt() {
+        let mut agent = test_agent();
+        let prompt = agent.build_prompt(None);
+        assert!(!prompt.contains("Conversation history:"));
+        assert!(!prompt.contains("Recent messages:"));
+        assert!(!prompt.contains("Known facts:"));
+        assert!(!prompt.contains("Relevant memories:"));
+        assert!(prompt.ends_with("Discussion topic: none\n\nHow would you respond?"));
+
+        agent.next_prompt.push_str("[Bob→Alice]: hello\n");
+        let prompt = agent.build_prompt(None);
+        assert!(!prompt.contains("Conversation history:"));
+        assert!(prompt.contains("Recent messages:\n[Bob→Alice]: hello\n"));
+
+        agent.archive_prompt();
+        assert!(agent
+            .build_prompt(None)
+            .contains("Conversation history:\n[Bob→Alice]: hello"));
+    }
+
+    #[test]
+    fn test_global_prefix_and_suffix_wrap_prompt() {
+        let mut agent = test_agent();
+        assert_eq!(agent.full_prompt(None), agent.build_prompt(None));
+
+        agent.prompt_prefix = "The year is 1750.".to_string();
+        agent.prompt_suffix = "Stay in 18th-century character.".to_string();
+        let prompt = agent.full_prompt(None);
+        assert!(prompt.starts_with("The year is 1750.\n\nYou are Alice"));
+        assert!(prompt.ends_with("How would you respond?\n\nStay in 18th-century character."));
+    }
+
+    #[test]
+    fn test_expertise_shapes_prompt_and_talkativeness() {
+        let mut agent = test_agent();
+        agent.talkativeness = 0.5;
+        assert!(!agent
+            .build_prompt(Some("Urban transport"))
+            .contains("expert"));
+
+        agent.expertise = vec!["Transport".to_string(), "energy".to_string()];
+        assert!(agent
+            .build_prompt(Some("Urban transport"))
+            .contains("You are an expert on Transport"));
+        assert_eq!(
+            agent.speaking_probability(Some("Urban transport")),
+            0.5 + EXPERTISE_BOOST
+        );
+
+        assert!(agent
+            .build_prompt(Some("Gardening"))
+            .contains("You are uncertain about Gardening"));
+        assert_eq!(agent.speaking_probability(Some("Gardening")), 0.5);
+        assert_eq!(agent.speaking_probability(None), 0.5);
+    }
+
+    #[test]
+    fn test_fit_context_drops_oldest_first() {
+        let history = vec!["a".repeat(40), "b".repeat(40)];
+        let recent = format!("{}\n{}\n", "c".repeat(20), "d".repeat(20));
+
+        let (_, kept_recent, trimmed) = fit_context(&history, &recent, 100);
+        assert!(!trimmed);
+        assert_eq!(kept_recent, recent);
+
+        let (kept_history, kept_recent, trimmed) = fit_context(&history, &recent, 22);
+        assert!(trimmed);
+        assert_eq!(kept_history, vec!["b".repeat(40)]);
+        assert_eq!(kept_recent, recent);
+
+        let (kept_history, kept_recent, _) = fit_context(&history, &recent, 6);
+        assert!(kept_history.is_empty());
+        assert_eq!(kept_recent, format!("{}\n", "d".repeat(20)));
+
+        let (_, kept_recent, _) = fit_context(&history, &recent, 2);
+        assert_eq!(kept_recent.chars().count(), 8);
+    }
+
+    #[test]
+    fn test_trim_context_reports_only_dropped_content() {
+        let mut agent = test_agent();
+        agent.conversation_history = vec!["a".repeat(40), "b".repeat(40)];
+        agent.next_prompt = "c".repeat(20);
+
+        let mut roomy = agent.clone();
+        roomy.context_tokens = Some(100);
+        assert!(!roomy.trim_context());
+        assert_eq!(roomy.conversation_history.len(), 2);
+
+        agent.context_tokens = Some(22);
+        assert!(agent.trim_context());
+        assert_eq!(agent.conversation_history, vec!["b".repeat(40)]);
+        assert_eq!(
+            agent.context(),
+            ("b".repeat(40), "c".repeat(20) + "\n", false)
+        );
+    }
+
+    #[test]
+    fn test_context_budget_counts_prompt_prefix_and_suffix() {
+        let mut agent = test_agent();
+        agent.conversation_history = vec!["a".repeat(40), "b".repeat(40)];
+        agent.next_prompt = "c".repeat(20);
+        agent.context_tokens = Some(32);
+        assert!(!agent.context().2);
+
+        // The shared constraints take 10 of the 32 tokens, leaving room for one entry
+        agent.prompt_prefix = "p".repeat(20);
+        agent.prompt_suffix = "s".repeat(20);
+        assert!(agent.trim_context());
+        assert_eq!(agent.conversation_history, vec!["b".repeat(40)]);
     }
 }
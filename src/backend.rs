@@ -0,0 +1,215 @@
+// backend.rs
+
+use crate::error::{Error, Result};
+use ollama_rs::error::OllamaError;
+use ollama_rs::generation::completion::request::GenerationRequest;
+use ollama_rs::generation::embeddings::request::GenerateEmbeddingsRequest;
+use ollama_rs::models::ModelOptions;
+use ollama_rs::Ollama;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Future returned by a model backend, resolving to the generated text.
+pub type GenerationFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+/// Future returned by a model backend, resolving to an embedding vector.
+pub type EmbeddingFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<f32>>> + Send + 'a>>;
+
+/// Future returned by a model backend's health check.
+pub type HealthFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Number of dimensions of the embeddings computed by `MockBackend`.
+const MOCK_EMBEDDING_DIMENSIONS: usize = 64;
+
+/// A source of text completions used by agents to respond.
+pub trait ModelBackend: Debug + Send + Sync {
+    /// Generates a completion for the given prompt.
+    ///
+    /// # Arguments
+    /// * `model` - Name of the model to use.
+    /// * `prompt` - The fully rendered prompt.
+    /// * `max_tokens` - Upper bound on the number of generated tokens, if any.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the response text.
+    /// * `Err(Error)` if the response could not be generated.
+    fn generate<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+        max_tokens: Option<u32>,
+    ) -> GenerationFuture<'a>;
+
+    /// Computes the embedding of a text.
+    ///
+    /// # Arguments
+    /// * `model` - Name of the embedding model to use.
+    /// * `text` - The text to embed.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<f32>)` containing the embedding.
+    /// * `Err(Error)` if the embedding could not be computed.
+    fn embed<'a>(&'a self, model: &'a str, text: &'a str) -> EmbeddingFuture<'a>;
+
+    /// Checks that the backend can currently serve generations.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the backend is reachable.
+    /// * `Err(Error)` describing why it is not.
+    fn health_check(&self) -> HealthFuture<'_>;
+}
+
+/// Backend that sends prompts to a local Ollama server.
+#[derive(Debug, Default)]
+pub struct OllamaBackend {
+    ollama: Ollama,
+}
+
+impl ModelBackend for OllamaBackend {
+    fn generate<'a>(
+        &'a self,
+        model: &'a str,
+        prompt: &'a str,
+        max_tokens: Option<u32>,
+    ) -> GenerationFuture<'a> {
+        Box::pin(async move {
+            let mut request = GenerationRequest::new(model.to_string(), prompt);
+            if let Some(max_tokens) = max_tokens {
+                let limit = i32::try_from(max_tokens).unwrap_or(i32::MAX);
+                request = request.options(ModelOptions::default().num_predict(limit));
+            }
+            match self.ollama.generate(request).await {
+                Ok(response) => Ok(response.response),
+                Err(e) => Err(classify_ollama_error(model, e)),
+            }
+        })
+    }
+
+    fn embed<'a>(&'a self, model: &'a str, text: &'a str) -> EmbeddingFuture<'a> {
+        Box::pin(async move {
+            let request = GenerateEmbeddingsRequest::new(model.to_string(), text.into());
+            match self.ollama.generate_embeddings(request).await {
+                Ok(response) => response
+                    .embeddings
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| Error::Generation("no embedding returned".to_string())),
+                Err(e) => Err(classify_ollama_error(model, e)),
+            }
+        })
+    }
+
+    /// Lists the installed models, the cheapest request the server answers.
+    fn health_check(&self) -> HealthFuture<'_> {
+        Box::pin(async move {
+            self.ollama
+                .list_local_models()
+                .await
+                .map(|_| ())
+                .map_err(|e| Error::OllamaConnection(e.to_string()))
+        })
+    }
+}
+
+/// Maps an Ollama client error to the matching crate error.
+fn classify_ollama_error(model: &str, error: OllamaError) -> Error {
+    match error {
+        OllamaError::ReqwestError(e) if e.is_connect() => Error::OllamaConnection(e.to_string()),
+        OllamaError::InternalError(e) if e.message.contains("not found") => {
+            Error::ModelNotFound(model.to_string())
+        }
+        e => Error::Generation(e.to_string()),
+    }
+}
+
+/// Backend returning canned responses, for tests and demos without a model server.
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    /// Responses returned in turn; an empty list echoes the end of the prompt instead.
+    responses: Vec<String>,
+
+    /// Index of the next canned response.
+    next: AtomicUsize,
+
+    /// Whether health checks fail, simulating a server that went away.
+    unreachable: AtomicBool,
+
+    /// Whether health checks never answer, simulating a server that hangs.
+    stalled: AtomicBool,
+}
+
+impl MockBackend {
+    /// Creates a mock backend cycling through the given responses.
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses,
+            next: AtomicUsize::new(0),
+            unreachable: AtomicBool::new(false),
+            stalled: AtomicBool::new(false),
+        }
+    }
+
+    /// Makes health checks succeed or fail.
+    #[cfg(test)]
+    pub fn set_reachable(&self, reachable: bool) {
+        self.unreachable.store(!reachable, Ordering::Relaxed);
+    }
+
+    /// Makes health checks hang instead of answering.
+    #[cfg(test)]
+    pub fn set_stalled(&self, stalled: bool) {
+        self.stalled.store(stalled, Ordering::Relaxed);
+    }
+}
+
+impl ModelBackend for MockBackend {
+    fn generate<'a>(
+        &'a self,
+        _model: &'a str,
+        prompt: &'a str,
+        _max_tokens: Option<u32>,
+    ) -> GenerationFuture<'a> {
+        let response = if self.responses.is_empty() {
+            let last_line = prompt.lines().rev().find(|l| !l.trim().is_empty());
+            format!("(mock) {}", last_line.unwrap_or_default().trim())
+        } else {
+            let index = self.next.fetch_add(1, Ordering::Relaxed) % self.responses.len();
+            self.responses[index].clone()
+        };
+        Box::pin(async move { Ok(response) })
+    }
+
+    /// Hashes the words of the text into a fixed-size bag-of-words vector.
+    fn embed<'a>(&'a self, _model: &'a str, text: &'a str) -> EmbeddingFuture<'a> {
+        let mut vector = vec![0.0; MOCK_EMBEDDING_DIMENSIONS];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            let hash = word.to_lowercase().bytes().fold(0usize, |hash, b| {
+                hash.wrapping_mul(31).wrapping_add(b as usize)
+            });
+            vector[hash % MOCK_EMBEDDING_DIMENSIONS] += 1.0;
+        }
+        Box::pin(async move { Ok(vector) })
+    }
+
+    fn health_check(&self) -> HealthFuture<'_> {
+        let unreachable = self.unreachable.load(Ordering::Relaxed);
+        let stalled = self.stalled.load(Ordering::Relaxed);
+        Box::pin(async move {
+            if stalled {
+                std::future::pending::<()>().await;
+            }
+            if unreachable {
+                Err(Error::OllamaConnection(
+                    "mock backend set unreachable".to_string(),
+                ))
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
@@ -0,0 +1,212 @@
+// cache.rs
+
+use crate::config::CacheConfig;
+use crate::error::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A generated response kept for reuse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    response: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Responses already generated, keyed by a hash of the model and the rendered prompt.
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CachedResponse>,
+    path: Option<PathBuf>,
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    // Whether entries changed since the cache was last written to its file
+    dirty: bool,
+}
+
+/// Cache of model responses, so identical prompts are not generated twice.
+///
+/// Clones share the same entries, so every agent reads and fills a single cache.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache(Arc<Mutex<CacheState>>);
+
+impl ResponseCache {
+    /// Opens a cache, loading the entries saved in its file if there is one.
+    ///
+    /// # Arguments
+    /// * `config` - The cache file and entry lifetime.
+    ///
+    /// # Returns
+    /// * `Ok(ResponseCache)` with the unexpired saved entries.
+    /// * `Err(Error)` if the file exists but could not be read.
+    pub fn open(config: &CacheConfig) -> Result<Self> {
+        let path = config.path.as_ref().map(PathBuf::from);
+        let ttl = config
+            .ttl_secs
+            .and_then(|secs| Duration::try_seconds(i64::try_from(secs).ok()?));
+
+        let entries = match &path {
+            Some(path) if path.exists() => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            _ => HashMap::new(),
+        };
+
+        let mut state = CacheState {
+            entries,
+            path,
+            ttl,
+            max_entries: config.max_entries,
+            dirty: false,
+        };
+        state.entries.retain(|_, entry| !is_expired(entry, ttl));
+        Ok(Self(Arc::new(Mutex::new(state))))
+    }
+
+    /// Looks up the response previously generated for a prompt.
+    ///
+    /// # Arguments
+    /// * `model` - Name of the model the prompt is sent to.
+    /// * `prompt` - The fully rendered prompt.
+    ///
+    /// # Returns
+    /// * The cached response, unless there is none or it expired.
+    pub fn get(&self, model: &str, prompt: &str) -> Option<String> {
+        let mut state = self.0.lock().ok()?;
+        let key = cache_key(model, prompt);
+        let entry = state.entries.get(&key)?;
+        if is_expired(entry, state.ttl) {
+            state.entries.remove(&key);
+            return None;
+        }
+        Some(entry.response.clone())
+    }
+
+    /// Stores a generated response, dropping expired entries and, past `max_entries`, the
+    /// oldest ones.
+    ///
+    /// The file is only written by `flush`.
+    ///
+    /// # Arguments
+    /// * `model` - Name of the model the prompt was sent to.
+    /// * `prompt` - The fully rendered prompt.
+    /// * `response` - The generated response.
+    pub fn insert(&self, model: &str, prompt: &str, response: &str) {
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        let ttl = state.ttl;
+        state.entries.retain(|_, entry| !is_expired(entry, ttl));
+        state.entries.insert(
+            cache_key(model, prompt),
+            CachedResponse {
+                response: response.to_string(),
+                created_at: Utc::now(),
+            },
+        );
+        if let Some(max_entries) = state.max_entries {
+            while state.entries.len() > max_entries {
+                let Some(oldest) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.created_at)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                state.entries.remove(&oldest);
+            }
+        }
+        state.dirty = true;
+    }
+
+    /// Writes the cache to its file if entries changed since the last write.
+    ///
+    /// # Returns
+    /// * `Ok(())` once written, or when there was nothing to write.
+    /// * `Err(Error)` if the cache file could not be written.
+    pub fn flush(&self) -> Result<()> {
+        let Ok(mut state) = self.0.lock() else {
+            return Ok(());
+        };
+        if !state.dirty {
+            return Ok(());
+        }
+        if let Some(path) = &state.path {
+            std::fs::write(path, serde_json::to_string(&state.entries)?)?;
+        }
+        state.dirty = false;
+        Ok(())
+    }
+}
+
+/// Whether an entry is older than the cache's time-to-live.
+fn is_expired(entry: &CachedResponse, ttl: Option<Duration>) -> bool {
+    ttl.is_some_and(|ttl| Utc::now() - entry.created_at > ttl)
+}
+
+/// Hashes a model and prompt into a cache key.
+///
+/// FNV-1a is used, since the standard hasher is not guaranteed to be stable across releases
+/// and keys are saved to disk.
+fn cache_key(model: &str, prompt: &str) -> String {
+    let hash = model
+        .bytes()
+        .chain(std::iter::once(0))
+        .chain(prompt.bytes())
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_reuses_responses_per_model_and_prompt() {
+        let cache = ResponseCache::default();
+        cache.insert("llama3.2", "Hello", "Hi there");
+
+        assert_eq!(cache.get("llama3.2", "Hello").as_deref(), Some("Hi there"));
+        assert_eq!(cache.get("mistral", "Hello"), None);
+        assert_eq!(cache.get("llama3.2", "Hello!"), None);
+
+        let expiring = ResponseCache::open(&CacheConfig {
+            path: None,
+            ttl_secs: Some(0),
+            max_entries: None,
+        })
+        .unwrap();
+        expiring.insert("llama3.2", "Hello", "Hi there");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(expiring.get("llama3.2", "Hello"), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest_and_writes_on_flush() {
+        let path =
+            std::env::temp_dir().join(format!("protopolis-cache-{}.json", uuid::Uuid::new_v4()));
+        let cache = ResponseCache::open(&CacheConfig {
+            path: Some(path.to_string_lossy().into_owned()),
+            ttl_secs: None,
+            max_entries: Some(2),
+        })
+        .unwrap();
+        for prompt in ["one", "two", "three"] {
+            cache.insert("llama3.2", prompt, prompt);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        assert_eq!(cache.get("llama3.2", "one"), None);
+        assert_eq!(cache.get("llama3.2", "three").as_deref(), Some("three"));
+
+        // Inserting only marks the cache; the file is written once, on flush
+        assert!(!path.exists());
+        cache.flush().unwrap();
+        let saved: HashMap<String, CachedResponse> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.len(), 2);
+    }
+}
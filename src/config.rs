@@ -1,5 +1,12 @@
 // config.rs
 
+use crate::error::{Error, Result};
+use crate::personality::{Personality, TEMPLATE_NAMES};
+use crate::prompt::{PromptTemplate, DEFAULT_OBSERVER_PROMPT, DEFAULT_SUMMARY_PROMPT};
+use crate::strings::DEFAULT_LANGUAGE;
+use crate::tool::TOOL_NAMES;
+use rand::Rng;
+use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{Read, Write};
@@ -14,13 +21,160 @@ pub struct Config {
     /// List of agent configurations.
     pub agents: Vec<AgentConfig>,
 
+    /// Named sub-discussions, each with its own agents and topic (a single discussion when
+    /// empty).
+    #[serde(default)]
+    pub rooms: Vec<RoomConfig>,
+
+    /// Extra agents generated at startup, in addition to `agents`.
+    #[serde(default)]
+    pub procedural_agents: Option<ProceduralAgents>,
+
     /// Debug mode flag (enables additional logging and debugging features).
     pub debug: bool,
 
     /// The Ollama model to use.
     pub ollama_model: Option<String>,
+
+    /// Which backend generates agent responses.
+    #[serde(default)]
+    pub backend: BackendKind,
+
+    /// Canned responses returned by the mock backend (echoes the prompt when empty).
+    #[serde(default)]
+    pub mock_responses: Vec<String>,
+
+    /// Appearance settings for the terminal interface.
+    #[serde(default)]
+    pub ui: UiConfig,
+
+    /// Directory where sessions are saved.
+    #[serde(default = "default_save_dir")]
+    pub save_dir: String,
+
+    /// Whether the session and transcript are saved automatically when the simulation stops,
+    /// and the snapshot kept up to date while it runs.
+    #[serde(default = "default_autosave")]
+    pub autosave: bool,
+
+    /// Snapshot of the running session, offered for restoring on the next launch.
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+
+    /// How agent messages are tagged with a sentiment for UI coloring.
+    #[serde(default)]
+    pub sentiment: SentimentMode,
+
+    /// Seed for the random number generator, making runs reproducible when set.
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Local port of the server streaming events over WebSocket and accepting HTTP commands
+    /// (disabled when unset).
+    #[serde(default)]
+    pub ws_port: Option<u16>,
+
+    /// File every simulation event is appended to as one JSON object per line (disabled when
+    /// unset).
+    #[serde(default)]
+    pub event_log: Option<String>,
+
+    /// Recall of relevant past messages by embedding similarity (disabled when unset).
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingsConfig>,
+
+    /// Reuse of responses to identical prompts across ticks and runs (disabled when unset).
+    #[serde(default)]
+    pub cache: Option<CacheConfig>,
+
+    /// Optional moderator that periodically steers the discussion.
+    #[serde(default)]
+    pub moderator: Option<ModeratorConfig>,
+
+    /// Optional observer that periodically summarizes the discussion for the user.
+    #[serde(default)]
+    pub observer: Option<ObserverConfig>,
+
+    /// Clean-up applied to every raw model response.
+    #[serde(default)]
+    pub postprocess: PostProcessConfig,
+
+    /// Template of the prompt sent to agents, with `{name}`, `{personality}`, `{verbosity}`,
+    /// `{goal}`, `{expertise}`, `{history}`, `{recent}`, `{topic}`, `{facts}`, and `{memories}`
+    /// placeholders.
+    #[serde(default)]
+    pub prompt_template: PromptTemplate,
+
+    /// Template of the prompt for the first turn of the agent opening a discussion, asking it
+    /// to set the stage; accepts the same placeholders as `prompt_template`.
+    #[serde(default = "PromptTemplate::opening")]
+    pub opening_prompt_template: PromptTemplate,
+
+    /// Text put before every agent prompt, e.g. constraints shared by the whole cast.
+    #[serde(default)]
+    pub global_prompt_prefix: String,
+
+    /// Text put after every agent prompt.
+    #[serde(default)]
+    pub global_prompt_suffix: String,
+
+    /// Maximum number of tokens an agent may generate per response (unlimited when unset).
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// Estimated tokens of prompt prefix, suffix, history and recent messages an agent's prompt
+    /// may hold; the oldest content is dropped beyond it (unlimited when unset).
+    #[serde(default)]
+    pub context_tokens: Option<usize>,
+
+    /// Whether to go straight to the main screen without showing the splash screen.
+    #[serde(default)]
+    pub skip_splash: bool,
+
+    /// Language of the UI text, read from `lang/<language>.json` ("en" is built in).
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Duration of a tick, in milliseconds, when replaying a transcript.
+    #[serde(default = "default_replay_tick_ms")]
+    pub replay_tick_ms: u64,
+
+    /// Seconds between checks that the model server is reachable; ticks wait while it is not.
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+
+    /// Most generations sent to the model server at once; the others wait for a free slot.
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+}
+
+fn default_save_dir() -> String {
+    "saves".to_string()
+}
+
+fn default_autosave() -> bool {
+    true
 }
 
+fn default_replay_tick_ms() -> u64 {
+    500
+}
+
+fn default_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_concurrent_requests() -> usize {
+    2
+}
+
+/// Names used for participants other than agents, which agents may not take.
+const RESERVED_NAMES: [&str; 4] = ["User", "System", "World", "everyone"];
+
 /// Defines the world parameters for the simulation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorldConfig {
@@ -35,6 +189,294 @@ pub struct WorldConfig {
 
     /// Number of hours in an in-game day.
     pub hours_per_day: u32,
+
+    /// Which conversation partners agents prefer to address.
+    #[serde(default)]
+    pub recipient_preference: RecipientPreference,
+
+    /// Ends the simulation after this many ticks.
+    #[serde(default)]
+    pub max_ticks: Option<u64>,
+
+    /// Ends the simulation once this many messages have been exchanged.
+    #[serde(default)]
+    pub max_messages: Option<usize>,
+
+    /// Ends the simulation when no agent has spoken for this many consecutive ticks.
+    #[serde(default)]
+    pub max_idle_ticks: Option<u64>,
+
+    /// Range (min, max) of ticks an agent waits before its reply is delivered.
+    #[serde(default)]
+    pub reply_delay_ticks: Option<(u64, u64)>,
+
+    /// Energy regained by each active agent per tick.
+    #[serde(default = "default_energy_regen")]
+    pub energy_regen: f32,
+
+    /// Maximum energy of an agent.
+    #[serde(default = "default_energy_cap")]
+    pub energy_cap: f32,
+
+    /// Energy lost per tick by idle agents instead of regaining it (disabled when unset).
+    #[serde(default)]
+    pub idle_decay: Option<f32>,
+
+    /// How the agent opening a conversation is chosen.
+    #[serde(default)]
+    pub starter: StarterPolicy,
+
+    /// Agent opening every conversation; takes precedence over `starter` when set.
+    #[serde(default)]
+    pub starting_agent: Option<String>,
+
+    /// Minimum relevance (0-1) of what an agent heard to the topic and its interests for it
+    /// to respond; agents respond to everything when unset.
+    #[serde(default)]
+    pub relevance_threshold: Option<f32>,
+
+    /// Similarity (0-1) to one of an agent's recent responses above which a response counts
+    /// as a repetition, asked again and then skipped; repetitions are allowed when unset.
+    #[serde(default)]
+    pub repetition_threshold: Option<f32>,
+
+    /// Seconds after which a generation still in progress is abandoned (no limit when unset).
+    #[serde(default)]
+    pub generation_timeout_secs: Option<u64>,
+
+    /// Number of ticks between two interventions of the moderator.
+    #[serde(default = "default_moderator_interval")]
+    pub moderator_interval: u64,
+
+    /// Whether a reply opening with "@Name" or "Name," is addressed to that agent.
+    #[serde(default)]
+    pub vocative_addressing: bool,
+
+    /// Ticks an agent stays silent after speaking, letting others get a word in (disabled when
+    /// unset).
+    #[serde(default)]
+    pub speak_cooldown_ticks: Option<u64>,
+
+    /// Number of ticks between two updates of the shared memory from the recent messages
+    /// (disabled when unset).
+    #[serde(default)]
+    pub memory_interval: Option<u64>,
+
+    /// Number of ticks between model evaluations of how close agents are to their goals
+    /// (disabled when unset).
+    #[serde(default)]
+    pub goal_check_interval: Option<u64>,
+
+    /// Gradual change of agents' traits with the tone of what they hear.
+    #[serde(default)]
+    pub personality_drift: DriftConfig,
+
+    /// Automatic pause when agents keep flooding the discussion with messages.
+    #[serde(default)]
+    pub flood_guard: FloodGuardConfig,
+
+    /// Summaries of the discussion shared with the agents every few turns.
+    #[serde(default)]
+    pub summary: SummaryConfig,
+
+    /// Shorter and rarer replies from an agent that keeps the floor to itself.
+    #[serde(default)]
+    pub monologue: MonologueConfig,
+
+    /// Messages delivered in order to their recipients whenever a conversation starts.
+    #[serde(default)]
+    pub opening_messages: Vec<OpeningMessage>,
+}
+
+/// A message setting up the scenario, such as a position to defend in a debate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningMessage {
+    /// Agent receiving the message, or "everyone".
+    pub recipient: String,
+
+    /// Message text; `{topic}` is replaced by the discussion topic.
+    pub content: String,
+}
+
+fn default_moderator_interval() -> u64 {
+    20
+}
+
+fn default_energy_regen() -> f32 {
+    0.1
+}
+
+fn default_energy_cap() -> f32 {
+    100.0
+}
+
+/// What produces an agent's messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentKind {
+    /// A personality voiced by the model.
+    #[default]
+    Persona,
+
+    /// A deterministic tool answering the queries addressed to it, without the model.
+    Tool,
+}
+
+/// How long an agent's responses should be.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// A single short sentence.
+    Terse,
+
+    /// Two or three sentences.
+    #[default]
+    Normal,
+
+    /// A full paragraph.
+    Verbose,
+}
+
+impl Verbosity {
+    /// Returns the length instruction given to the model.
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Verbosity::Terse => "Respond in a single short sentence",
+            Verbosity::Normal => "Respond concisely (max 2-3 sentences)",
+            Verbosity::Verbose => "Respond at length (a full paragraph)",
+        }
+    }
+
+    /// Returns the response budget used when no `max_tokens` is configured.
+    pub fn default_max_tokens(self) -> Option<u32> {
+        match self {
+            Verbosity::Terse => Some(40),
+            Verbosity::Normal | Verbosity::Verbose => None,
+        }
+    }
+}
+
+/// How an agent picks the partner it addresses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipientPreference {
+    /// Favor the partners the agent has talked to most.
+    #[default]
+    Most,
+
+    /// Favor the partners the agent has talked to least.
+    Least,
+}
+
+/// How the agent opening a conversation is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StarterPolicy {
+    /// The first agent by name.
+    #[default]
+    First,
+
+    /// The most extraverted agent (first by name on ties).
+    Extraverted,
+
+    /// Each agent in turn, by name, one per conversation started.
+    Rotate,
+}
+
+/// Selects the backend used to generate agent responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Generate responses with a local Ollama server.
+    #[default]
+    Ollama,
+
+    /// Return canned responses without contacting any model server.
+    Mock,
+}
+
+/// Selects how agent messages are classified by sentiment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SentimentMode {
+    /// Do not tag messages.
+    #[default]
+    Off,
+
+    /// Tag messages with a cheap keyword heuristic.
+    Keywords,
+
+    /// Tag messages with an extra model call per message.
+    Model,
+}
+
+/// Defines the appearance of the terminal interface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Name of the built-in palette used for agent colors ("default" or "colorblind").
+    pub palette: String,
+
+    /// Custom agent colors (names like "red" or hex like "#ff8800"); overrides `palette` when set.
+    pub colors: Vec<String>,
+
+    /// Color used for messages sent by the user.
+    pub user_color: String,
+
+    /// Color used for system messages.
+    pub system_color: String,
+
+    /// Color used for messages broadcast to everyone.
+    pub everyone_color: String,
+
+    /// Color used for messages sent by the moderator.
+    pub moderator_color: String,
+
+    /// Color used for the observer's summaries.
+    pub observer_color: String,
+
+    /// Most conversation columns shown by the split view before it falls back to a single one.
+    pub max_split_columns: usize,
+
+    /// Width of the messages panel, as a percentage of the screen (1-100).
+    pub messages_pct: u16,
+
+    /// Whether the agents panel is shown next to the messages.
+    pub show_agents_panel: bool,
+
+    /// Whether thinking agents are shown with an estimate of their wait, e.g. "Alice (~4s)".
+    pub show_wait_estimates: bool,
+
+    /// Narrowest terminal, in columns, the layout is drawn in; smaller ones show a notice.
+    pub min_width: u16,
+
+    /// Shortest terminal, in rows, the layout is drawn in; smaller ones show a notice.
+    pub min_height: u16,
+
+    /// System messages shown at startup, where `{commands}` lists the available commands
+    /// (those of the configured language when unset).
+    pub welcome_messages: Option<Vec<String>>,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            palette: "default".to_string(),
+            colors: Vec::new(),
+            user_color: "white".to_string(),
+            system_color: "blue".to_string(),
+            everyone_color: "gray".to_string(),
+            moderator_color: "magenta".to_string(),
+            observer_color: "cyan".to_string(),
+            max_split_columns: 4,
+            messages_pct: 70,
+            show_agents_panel: true,
+            show_wait_estimates: true,
+            min_width: 40,
+            min_height: 10,
+            welcome_messages: None,
+        }
+    }
 }
 
 /// Defines the configuration of an individual agent.
@@ -51,6 +493,411 @@ pub struct AgentConfig {
 
     /// Starting position of the agent in the world (x, y).
     pub initial_position: (i32, i32),
+
+    /// Ollama model for this agent; falls back to the global `ollama_model` when unset.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Probability (0-1) that the agent responds on a tick when it has heard something;
+    /// falls back to the agent's extraversion when unset.
+    #[serde(default)]
+    pub talkativeness: Option<f32>,
+
+    /// Maximum number of tokens per response; falls back to the global `max_tokens` when unset.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// Context budget of the agent's model; falls back to the global `context_tokens` when unset.
+    #[serde(default)]
+    pub context_tokens: Option<usize>,
+
+    /// How long the agent's responses should be.
+    #[serde(default)]
+    pub verbosity: Verbosity,
+
+    /// Emoji or short glyph shown before the agent's name; picked from the name when unset.
+    #[serde(default)]
+    pub avatar: Option<String>,
+
+    /// Color of the agent's messages (a name like "red" or hex like "#ff8800"); picked from
+    /// the name in the palette when unset.
+    #[serde(default)]
+    pub color: Option<String>,
+
+    /// Objective the agent pursues in the discussion (e.g. "convince others to adopt solar
+    /// power").
+    #[serde(default)]
+    pub goal: Option<String>,
+
+    /// Subjects the agent cares about besides the discussion topic.
+    #[serde(default)]
+    pub interests: Vec<String>,
+
+    /// Keywords of the topics the agent is an expert on; it defers on other topics.
+    #[serde(default)]
+    pub expertise: Vec<String>,
+
+    /// Language the agent responds in, e.g. "French" (whatever the model picks when unset).
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Whether the agent is voiced by the model or is a deterministic tool.
+    #[serde(default)]
+    pub kind: AgentKind,
+
+    /// Name of the tool answering for a tool agent, one of `TOOL_NAMES`.
+    #[serde(default)]
+    pub tool: Option<String>,
+
+    /// Explicit personality traits; override `personality_template` when set.
+    #[serde(default)]
+    pub personality: Option<Personality>,
+}
+
+/// Defines a room: a sub-discussion only its agents take part in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomConfig {
+    /// Room name, used by the `room` command.
+    pub name: String,
+
+    /// Names of the agents in the room.
+    pub agents: Vec<String>,
+
+    /// Topic discussed in the room; falls back to the global discussion topic when unset.
+    #[serde(default)]
+    pub topic: Option<String>,
+}
+
+/// Describes agents generated at startup instead of being listed one by one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProceduralAgents {
+    /// Number of agents to generate.
+    pub count: usize,
+
+    /// Prefix of the generated names, followed by a number ("Agent-1", "Agent-2", ...).
+    #[serde(default = "default_name_prefix")]
+    pub name_prefix: String,
+
+    /// Whether each agent gets random Big Five traits instead of a balanced personality.
+    #[serde(default = "default_randomize_personality")]
+    pub randomize_personality: bool,
+}
+
+fn default_name_prefix() -> String {
+    "Agent".to_string()
+}
+
+fn default_randomize_personality() -> bool {
+    true
+}
+
+impl ProceduralAgents {
+    /// Returns the names of the agents to generate.
+    pub fn names(&self) -> Vec<String> {
+        (1..=self.count)
+            .map(|i| format!("{}-{}", self.name_prefix, i))
+            .collect()
+    }
+
+    /// Generates the agent configurations, spread randomly over the world.
+    ///
+    /// # Arguments
+    /// * `world` - The world the agents are placed in.
+    /// * `rng` - Random source, seeded for reproducible runs.
+    ///
+    /// # Returns
+    /// * One `AgentConfig` per generated agent.
+    pub fn generate(&self, world: &WorldConfig, rng: &mut impl Rng) -> Vec<AgentConfig> {
+        self.names()
+            .into_iter()
+            .map(|name| {
+                let personality = self.randomize_personality.then(|| {
+                    Personality::new(rng.gen(), rng.gen(), rng.gen(), rng.gen(), rng.gen())
+                });
+                AgentConfig {
+                    name,
+                    personality_template: if personality.is_some() {
+                        "random".to_string()
+                    } else {
+                        "balanced".to_string()
+                    },
+                    initial_energy: 100.0,
+                    initial_position: (
+                        rng.gen_range(0..world.width.max(1)),
+                        rng.gen_range(0..world.height.max(1)),
+                    ),
+                    model: None,
+                    talkativeness: None,
+                    max_tokens: None,
+                    context_tokens: None,
+                    verbosity: Verbosity::default(),
+                    avatar: None,
+                    color: None,
+                    goal: None,
+                    interests: Vec::new(),
+                    expertise: Vec::new(),
+                    language: None,
+                    kind: AgentKind::Persona,
+                    tool: None,
+                    personality,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Defines how agents' personalities drift with the tone of their interactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriftConfig {
+    /// Whether traits drift at all.
+    pub enabled: bool,
+
+    /// Largest change applied to a trait at each drift, on the 0-1 trait scale.
+    pub rate: f32,
+
+    /// Number of ticks between two drifts, each based on the messages heard since the last.
+    pub interval: u64,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: 0.02,
+            interval: 10,
+        }
+    }
+}
+
+/// Defines when a runaway discussion pauses the simulation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloodGuardConfig {
+    /// Whether the simulation pauses itself at all.
+    pub enabled: bool,
+
+    /// Most new messages a tick may produce before it counts toward a pause.
+    pub max_messages_per_tick: usize,
+
+    /// Number of consecutive ticks over the limit that pause the simulation.
+    pub ticks: u64,
+}
+
+impl Default for FloodGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_messages_per_tick: 10,
+            ticks: 5,
+        }
+    }
+}
+
+/// Defines the summaries that keep long discussions anchored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummaryConfig {
+    /// Whether the discussion is summarized at all.
+    pub enabled: bool,
+
+    /// Number of agent turns on a topic between two summaries.
+    pub turns: usize,
+
+    /// Whether each summary is also shown to the user.
+    pub post_to_user: bool,
+
+    /// Summary prompt, with `{topic}` and `{recent}` placeholders.
+    pub prompt: String,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            turns: 20,
+            post_to_user: true,
+            prompt: DEFAULT_SUMMARY_PROMPT.to_string(),
+        }
+    }
+}
+
+/// Defines how an agent speaking alone tick after tick is reined in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonologueConfig {
+    /// Whether monologues are curbed at all.
+    pub enabled: bool,
+
+    /// Factor (0-1) applied to the agent's speaking probability for each consecutive tick it
+    /// spoke alone.
+    pub probability_decay: f32,
+
+    /// Factor (0-1) applied to the agent's `max_tokens` for each consecutive tick it spoke
+    /// alone.
+    pub token_decay: f32,
+
+    /// Fewest tokens a reply is cut down to.
+    pub min_tokens: u32,
+
+    /// Budget the decay starts from for agents without `max_tokens`.
+    pub default_tokens: u32,
+}
+
+impl Default for MonologueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            probability_decay: 0.5,
+            token_decay: 0.7,
+            min_tokens: 16,
+            default_tokens: 150,
+        }
+    }
+}
+
+/// Defines how agents recall relevant past messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingsConfig {
+    /// Ollama model computing the embeddings.
+    pub model: String,
+
+    /// Number of past messages recalled in each prompt.
+    pub top_k: usize,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            model: "nomic-embed-text".to_string(),
+            top_k: 3,
+        }
+    }
+}
+
+/// Defines the clean-up steps applied to every raw model response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessConfig {
+    /// Whether quotes surrounding the whole response are removed.
+    pub trim_quotes: bool,
+
+    /// Whether a leading role label such as "Assistant:" or the agent's own name is removed.
+    pub strip_role_prefix: bool,
+
+    /// Whether runs of whitespace, line breaks included, are collapsed into single spaces.
+    pub collapse_whitespace: bool,
+
+    /// Number of sentences a response is cut to (unlimited when unset).
+    pub max_sentences: Option<usize>,
+}
+
+impl Default for PostProcessConfig {
+    fn default() -> Self {
+        Self {
+            trim_quotes: true,
+            strip_role_prefix: true,
+            collapse_whitespace: true,
+            max_sentences: None,
+        }
+    }
+}
+
+/// Defines the autosave snapshot restored with `--resume`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// JSON file the snapshot is written to and restored from.
+    pub path: String,
+
+    /// Ticks between snapshots (only written when the simulation stops if 0).
+    pub interval: u64,
+
+    /// Hours after which a snapshot is too old to be offered at startup.
+    pub max_age_hours: u64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            path: "saves/autosave.json".to_string(),
+            interval: 20,
+            max_age_hours: 24,
+        }
+    }
+}
+
+/// Defines the cache of generated responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// JSON file the cache is saved to, so it outlives the run (in memory only when unset).
+    pub path: Option<String>,
+
+    /// Seconds a cached response stays valid (forever when unset).
+    pub ttl_secs: Option<u64>,
+
+    /// Most responses kept; the oldest are evicted past it (no limit when unset).
+    pub max_entries: Option<usize>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            path: Some("response_cache.json".to_string()),
+            ttl_secs: Some(86_400),
+            max_entries: Some(1000),
+        }
+    }
+}
+
+/// Defines the moderator steering the discussion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModeratorConfig {
+    /// Moderator's display name.
+    pub name: String,
+
+    /// Ollama model for the moderator; falls back to the global `ollama_model` when unset.
+    pub model: Option<String>,
+}
+
+impl Default for ModeratorConfig {
+    fn default() -> Self {
+        Self {
+            name: "Moderator".to_string(),
+            model: None,
+        }
+    }
+}
+
+/// Defines the observer summarizing the discussion for the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObserverConfig {
+    /// Observer's display name.
+    pub name: String,
+
+    /// Ollama model for the observer; falls back to the global `ollama_model` when unset.
+    pub model: Option<String>,
+
+    /// Number of ticks between two summaries (never when 0).
+    pub interval: u64,
+
+    /// Template of the summary prompt, with `{name}`, `{topic}`, and `{recent}` placeholders.
+    pub prompt: String,
+}
+
+impl Default for ObserverConfig {
+    fn default() -> Self {
+        Self {
+            name: "Observer".to_string(),
+            model: None,
+            interval: 10,
+            prompt: DEFAULT_OBSERVER_PROMPT.to_string(),
+        }
+    }
 }
 
 impl Config {
@@ -62,6 +909,29 @@ impl Config {
                 height: 100,
                 ticks_per_hour: 60,
                 hours_per_day: 24,
+                recipient_preference: RecipientPreference::default(),
+                max_ticks: None,
+                max_messages: None,
+                max_idle_ticks: None,
+                reply_delay_ticks: None,
+                energy_regen: default_energy_regen(),
+                energy_cap: default_energy_cap(),
+                idle_decay: None,
+                starter: StarterPolicy::default(),
+                starting_agent: None,
+                relevance_threshold: None,
+                repetition_threshold: None,
+                generation_timeout_secs: None,
+                moderator_interval: default_moderator_interval(),
+                vocative_addressing: false,
+                speak_cooldown_ticks: None,
+                memory_interval: None,
+                goal_check_interval: None,
+                personality_drift: DriftConfig::default(),
+                flood_guard: FloodGuardConfig::default(),
+                summary: SummaryConfig::default(),
+                monologue: MonologueConfig::default(),
+                opening_messages: Vec::new(),
             },
             agents: vec![
                 AgentConfig {
@@ -69,39 +939,381 @@ impl Config {
                     personality_template: "friendly".to_string(),
                     initial_energy: 100.0,
                     initial_position: (10, 10),
+                    model: None,
+                    talkativeness: None,
+                    max_tokens: None,
+                    context_tokens: None,
+                    verbosity: Verbosity::default(),
+                    avatar: None,
+                    color: None,
+                    goal: None,
+                    interests: Vec::new(),
+                    expertise: Vec::new(),
+                    language: None,
+                    kind: AgentKind::Persona,
+                    tool: None,
+                    personality: None,
                 },
                 AgentConfig {
                     name: "Bob".to_string(),
                     personality_template: "curious".to_string(),
                     initial_energy: 100.0,
                     initial_position: (20, 20),
+                    model: None,
+                    talkativeness: None,
+                    max_tokens: None,
+                    context_tokens: None,
+                    verbosity: Verbosity::default(),
+                    avatar: None,
+                    color: None,
+                    goal: None,
+                    interests: Vec::new(),
+                    expertise: Vec::new(),
+                    language: None,
+                    kind: AgentKind::Persona,
+                    tool: None,
+                    personality: None,
                 },
                 AgentConfig {
                     name: "Charlie".to_string(),
                     personality_template: "cautious".to_string(),
                     initial_energy: 100.0,
                     initial_position: (30, 30),
+                    model: None,
+                    talkativeness: None,
+                    max_tokens: None,
+                    context_tokens: None,
+                    verbosity: Verbosity::default(),
+                    avatar: None,
+                    color: None,
+                    goal: None,
+                    interests: Vec::new(),
+                    expertise: Vec::new(),
+                    language: None,
+                    kind: AgentKind::Persona,
+                    tool: None,
+                    personality: None,
                 },
             ],
+            rooms: Vec::new(),
+            procedural_agents: None,
             debug: true,
             ollama_model: None,
+            backend: BackendKind::default(),
+            mock_responses: Vec::new(),
+            ui: UiConfig::default(),
+            save_dir: default_save_dir(),
+            autosave: default_autosave(),
+            snapshot: SnapshotConfig::default(),
+            sentiment: SentimentMode::default(),
+            seed: None,
+            ws_port: None,
+            event_log: None,
+            embeddings: None,
+            cache: None,
+            moderator: None,
+            observer: None,
+            postprocess: PostProcessConfig::default(),
+            prompt_template: PromptTemplate::default(),
+            opening_prompt_template: PromptTemplate::opening(),
+            global_prompt_prefix: String::new(),
+            global_prompt_suffix: String::new(),
+            max_tokens: None,
+            context_tokens: None,
+            skip_splash: false,
+            language: default_language(),
+            replay_tick_ms: default_replay_tick_ms(),
+            health_check_interval_secs: default_health_check_interval_secs(),
+            max_concurrent_requests: default_max_concurrent_requests(),
         }
     }
 
-    /// Loads a configuration from a JSON file.
+    /// Checks that a name can be given to an agent: not empty, not reserved, and not taken by
+    /// the moderator or the observer. Whether another agent has it is up to the caller.
+    ///
+    /// # Arguments
+    /// * `name` - The trimmed name.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the name is free.
+    /// * `Err(Error::InvalidConfig)` saying why it is not.
+    pub fn check_agent_name(&self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::InvalidConfig(
+                "agent names must not be empty".to_string(),
+            ));
+        }
+        if RESERVED_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(name))
+        {
+            return Err(Error::InvalidConfig(format!(
+                "agent name '{}' is reserved",
+                name
+            )));
+        }
+        if self
+            .moderator
+            .as_ref()
+            .is_some_and(|moderator| moderator.name == name)
+        {
+            return Err(Error::InvalidConfig(format!(
+                "agent name '{}' is used by the moderator",
+                name
+            )));
+        }
+        if self
+            .observer
+            .as_ref()
+            .is_some_and(|observer| observer.name == name)
+        {
+            return Err(Error::InvalidConfig(format!(
+                "agent name '{}' is used by the observer",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Trims the whitespace around agent, moderator and observer names, so a name that passes
+    /// validation is also the one lookups and addressed messages compare against.
+    pub fn trim_names(&mut self) {
+        for agent in &mut self.agents {
+            agent.name = agent.name.trim().to_string();
+        }
+        if let Some(moderator) = &mut self.moderator {
+            moderator.name = moderator.name.trim().to_string();
+        }
+        if let Some(observer) = &mut self.observer {
+            observer.name = observer.name.trim().to_string();
+        }
+    }
+
+    /// Checks the values that deserialize fine but would start the simulation in a bad state:
+    /// agent names, energies, colors and personality templates, UI colors, tick rates, rooms
+    /// and limits.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the configuration is valid.
+    /// * `Err(Error::InvalidField)` naming the first field with an invalid value, or
+    ///   `Err(Error::InvalidConfig)` for names that clash.
+    pub fn validate(&self) -> Result<()> {
+        let cap = self.world.energy_cap;
+        if cap <= 0.0 {
+            return Err(invalid_field("world.energy_cap", "must be positive"));
+        }
+        for (field, factor) in [
+            (
+                "world.monologue.probability_decay",
+                self.world.monologue.probability_decay,
+            ),
+            (
+                "world.monologue.token_decay",
+                self.world.monologue.token_decay,
+            ),
+        ] {
+            if !(0.0..=1.0).contains(&factor) {
+                return Err(invalid_field(field, "must be between 0 and 1"));
+            }
+        }
+        if self
+            .world
+            .repetition_threshold
+            .is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+        {
+            return Err(invalid_field(
+                "world.repetition_threshold",
+                "must be between 0 and 1",
+            ));
+        }
+        for (field, rate) in [
+            ("world.ticks_per_hour", u64::from(self.world.ticks_per_hour)),
+            ("world.hours_per_day", u64::from(self.world.hours_per_day)),
+            ("replay_tick_ms", self.replay_tick_ms),
+        ] {
+            if rate == 0 {
+                return Err(invalid_field(field, "must be positive"));
+            }
+        }
+
+        for (i, agent) in self.agents.iter().enumerate() {
+            if agent.name.trim().is_empty() {
+                return Err(invalid_field(
+                    &format!("agents[{}].name", i),
+                    "must not be empty",
+                ));
+            }
+            if !(0.0..=cap).contains(&agent.initial_energy) {
+                return Err(invalid_field(
+                    &format!("agents[{}].initial_energy", i),
+                    &format!("must be between 0 and world.energy_cap ({})", cap),
+                ));
+            }
+            if agent.kind == AgentKind::Tool
+                && !agent
+                    .tool
+                    .as_deref()
+                    .is_some_and(|tool| TOOL_NAMES.contains(&tool))
+            {
+                return Err(invalid_field(
+                    &format!("agents[{}].tool", i),
+                    &format!("must be one of {}", TOOL_NAMES.join(", ")),
+                ));
+            }
+            if let Some(color) = agent.color.as_deref() {
+                check_color(&format!("agents[{}].color", i), color)?;
+            }
+            // Explicit traits make the template a mere label
+            if agent.personality.is_none()
+                && !TEMPLATE_NAMES.contains(&agent.personality_template.as_str())
+            {
+                return Err(invalid_field(
+                    &format!("agents[{}].personality_template", i),
+                    &format!(
+                        "'{}' is not one of {}",
+                        agent.personality_template,
+                        TEMPLATE_NAMES.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        for (i, color) in self.ui.colors.iter().enumerate() {
+            check_color(&format!("ui.colors[{}]", i), color)?;
+        }
+        for (field, color) in [
+            ("ui.user_color", &self.ui.user_color),
+            ("ui.system_color", &self.ui.system_color),
+            ("ui.everyone_color", &self.ui.everyone_color),
+            ("ui.moderator_color", &self.ui.moderator_color),
+            ("ui.observer_color", &self.ui.observer_color),
+        ] {
+            check_color(field, color)?;
+        }
+
+        if let Some(moderator) = &self.moderator {
+            if RESERVED_NAMES
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(moderator.name.trim()))
+            {
+                return Err(Error::InvalidConfig(format!(
+                    "moderator name '{}' is reserved",
+                    moderator.name.trim()
+                )));
+            }
+        }
+
+        let generated = self
+            .procedural_agents
+            .as_ref()
+            .map(ProceduralAgents::names)
+            .unwrap_or_default();
+        let names = self
+            .agents
+            .iter()
+            .map(|agent| agent.name.as_str())
+            .chain(generated.iter().map(String::as_str));
+
+        let mut seen = Vec::new();
+        for name in names {
+            let name = name.trim();
+            self.check_agent_name(name)?;
+            if seen.contains(&name) {
+                return Err(Error::InvalidConfig(format!(
+                    "agent name '{}' is used more than once",
+                    name
+                )));
+            }
+            seen.push(name);
+        }
+
+        for opening in &self.world.opening_messages {
+            if opening.recipient != "everyone" && !seen.contains(&opening.recipient.as_str()) {
+                return Err(Error::InvalidConfig(format!(
+                    "opening message recipient '{}' is not an agent",
+                    opening.recipient
+                )));
+            }
+        }
+
+        let mut rooms: Vec<&str> = Vec::new();
+        for room in &self.rooms {
+            if room.name.trim().is_empty() || rooms.contains(&room.name.as_str()) {
+                return Err(Error::InvalidConfig(format!(
+                    "room name '{}' is empty or used twice",
+                    room.name
+                )));
+            }
+            rooms.push(&room.name);
+            if let Some(unknown) = room.agents.iter().find(|a| !seen.contains(&a.as_str())) {
+                return Err(Error::InvalidConfig(format!(
+                    "room '{}' lists '{}', which is not an agent",
+                    room.name, unknown
+                )));
+            }
+        }
+
+        if self.max_concurrent_requests == 0 {
+            return Err(Error::InvalidConfig(
+                "max_concurrent_requests must be at least 1".to_string(),
+            ));
+        }
+
+        let drift_rate = self.world.personality_drift.rate;
+        if !(0.0..=1.0).contains(&drift_rate) {
+            return Err(Error::InvalidConfig(format!(
+                "personality drift rate {} is not between 0 and 1",
+                drift_rate
+            )));
+        }
+        Ok(())
+    }
+
+    /// Loads a configuration from a JSON file and validates it.
+    ///
+    /// Fields that are missing or hold values of the wrong type fall back to their defaults
+    /// instead of rejecting the whole file; each rejected field is reported as a warning.
     ///
     /// # Arguments
     /// * `path` - The file path to load the configuration from.
     ///
     /// # Returns
-    /// * `Ok(Config)` if the file is successfully read and parsed.
-    /// * `Err(Box<dyn std::error::Error>)` if an error occurs.
-    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+    /// * `Ok((Config, warnings))` if the file is read, is valid JSON and passes `validate`.
+    /// * `Err(Error::ConfigParse)` if the file is not a JSON object.
+    /// * `Err(Error::InvalidField)` or `Err(Error::InvalidConfig)` if a value is invalid.
+    /// * `Err(Error)` if the file cannot be read.
+    pub fn load(path: &Path) -> Result<(Self, Vec<String>)> {
         let mut file = File::open(path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        let config: Config = serde_json::from_str(&contents)?;
-        Ok(config)
+        let value: serde_json::Value =
+            serde_json::from_str(&contents).map_err(Error::ConfigParse)?;
+        let (mut config, warnings) = Self::from_value(value)?;
+        config.trim_names();
+        config.validate()?;
+        Ok((config, warnings))
+    }
+
+    /// Builds a configuration from JSON, applying each valid field over the defaults.
+    ///
+    /// # Arguments
+    /// * `value` - The parsed configuration file.
+    ///
+    /// # Returns
+    /// * `Ok((Config, warnings))` with one warning per field that was rejected.
+    /// * `Err(Error::ConfigParse)` if the value is not a JSON object.
+    pub fn from_value(value: serde_json::Value) -> Result<(Self, Vec<String>)> {
+        if !value.is_object() {
+            return Err(Error::ConfigParse(serde::de::Error::custom(
+                "the configuration must be a JSON object",
+            )));
+        }
+
+        let mut merged = serde_json::to_value(Self::default())?;
+        let mut warnings = Vec::new();
+        merge_fields(&mut merged, &mut Vec::new(), &value, &mut warnings);
+        let config = serde_json::from_value(merged)?;
+        Ok((config, warnings))
     }
 
     /// Saves the current configuration to a JSON file.
@@ -111,11 +1323,293 @@ impl Config {
     ///
     /// # Returns
     /// * `Ok(())` if the file is successfully written.
-    /// * `Err(Box<dyn std::error::Error>)` if an error occurs.
-    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    /// * `Err(Error)` if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
         let json = serde_json::to_string_pretty(self)?;
         let mut file = File::create(path)?;
         file.write_all(json.as_bytes())?;
         Ok(())
     }
 }
+
+/// Copies the fields of `user` into `root` one at a time, keeping only those that still
+/// deserialize into a valid `Config`.
+///
+/// Nested objects (e.g. `world`) are merged field by field, so one bad value does not discard
+/// its siblings. Entries of `agents` are merged over the default agent of the same name, and
+/// keys that match no field are reported rather than silently dropped.
+///
+/// # Arguments
+/// * `root` - The whole configuration being built, starting from the defaults.
+/// * `path` - Keys leading from `root` to the object being merged.
+/// * `user` - The user's value for that object.
+/// * `warnings` - Collects a message for each rejected field.
+fn merge_fields(
+    root: &mut serde_json::Value,
+    path: &mut Vec<String>,
+    user: &serde_json::Value,
+    warnings: &mut Vec<String>,
+) {
+    let Some(fields) = user.as_object() else {
+        return;
+    };
+
+    for (key, value) in fields {
+        let parent_pointer = json_pointer(path);
+        path.push(key.clone());
+
+        let current = root.pointer(&json_pointer(path)).cloned();
+        if current.is_none() {
+            warnings.push(format!("ignoring unknown field '{}'", path.join(".")));
+            path.pop();
+            continue;
+        }
+        if value.is_object() && current.as_ref().is_some_and(|c| c.is_object()) {
+            merge_fields(root, path, value, warnings);
+            path.pop();
+            continue;
+        }
+
+        let Some(parent) = root
+            .pointer_mut(&parent_pointer)
+            .and_then(|p| p.as_object_mut())
+        else {
+            path.pop();
+            continue;
+        };
+        let value = match (value, &current) {
+            (serde_json::Value::Array(entries), Some(serde_json::Value::Array(defaults)))
+                if path.len() == 1 && key == "agents" =>
+            {
+                merge_agents(defaults, entries, warnings)
+            }
+            _ => value.clone(),
+        };
+        parent.insert(key.clone(), value);
+
+        if let Err(e) = serde_json::from_value::<Config>(root.clone()) {
+            warnings.push(format!("ignoring '{}': {}", path.join("."), e));
+            if let Some(parent) = root
+                .pointer_mut(&parent_pointer)
+                .and_then(|p| p.as_object_mut())
+            {
+                match current {
+                    Some(previous) => parent.insert(key.clone(), previous),
+                    None => parent.remove(key),
+                };
+            }
+        }
+
+        path.pop();
+    }
+}
+
+/// Merges the user's agents over the default agents with the same names.
+///
+/// The user's list still decides which agents exist; a matching default only fills in the
+/// fields an entry leaves out.
+///
+/// # Arguments
+/// * `defaults` - The default agents.
+/// * `entries` - The user's agents.
+/// * `warnings` - Collects a message for each key that matches no agent field.
+///
+/// # Returns
+/// * The merged `agents` array.
+fn merge_agents(
+    defaults: &[serde_json::Value],
+    entries: &[serde_json::Value],
+    warnings: &mut Vec<String>,
+) -> serde_json::Value {
+    let known = defaults.first().and_then(|agent| agent.as_object());
+    let merged = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let Some(fields) = entry.as_object() else {
+                return entry.clone();
+            };
+            if let Some(known) = known {
+                for key in fields.keys().filter(|key| !known.contains_key(*key)) {
+                    warnings.push(format!("ignoring unknown field 'agents.{}.{}'", i, key));
+                }
+            }
+
+            let name = fields.get("name");
+            let mut agent = defaults
+                .iter()
+                .find(|agent| name.is_some() && agent.get("name") == name)
+                .cloned()
+                .unwrap_or_else(|| entry.clone());
+            if let Some(agent) = agent.as_object_mut() {
+                for (key, value) in fields {
+                    agent.insert(key.clone(), value.clone());
+                }
+            }
+            agent
+        })
+        .collect();
+    serde_json::Value::Array(merged)
+}
+
+/// Builds the error for a field holding an invalid value.
+fn invalid_field(field: &str, reason: &str) -> Error {
+    Error::InvalidField {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Checks that a color is one the UI can draw, a name like "red" or hex like "#ff8800".
+fn check_color(field: &str, color: &str) -> Result<()> {
+    color.parse::<Color>().map(|_| ()).map_err(|_| {
+        invalid_field(
+            field,
+            &format!(
+                "'{}' is not a color name or hex code like \"#ff8800\"",
+                color
+            ),
+        )
+    })
+}
+
+/// Builds a JSON pointer (RFC 6901) from a list of object keys.
+fn json_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rejects_invalid_names() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        config.agents[1].name = "Alice".to_string();
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "invalid configuration: agent name 'Alice' is used more than once"
+        );
+
+        config.agents[1].name = "System".to_string();
+        assert!(config.validate().is_err());
+
+        config.agents[1].name = " ".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_trimmed_names_are_stored_and_moderator_name_checked() {
+        let mut config = Config::default();
+        config.agents[0].name = " Alice ".to_string();
+        config.trim_names();
+        assert_eq!(config.agents[0].name, "Alice");
+        assert!(config.validate().is_ok());
+
+        config.moderator = Some(ModeratorConfig {
+            name: "system".to_string(),
+            model: None,
+        });
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "invalid configuration: moderator name 'system' is reserved"
+        );
+    }
+
+    #[test]
+    fn test_validate_names_offending_field() {
+        let mut config = Config::default();
+        config.agents[2].initial_energy = 150.0;
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "invalid configuration: 'agents[2].initial_energy' must be between 0 and \
+             world.energy_cap (100)"
+        );
+
+        config.agents[2].initial_energy = 50.0;
+        config.agents[1].personality_template = "grumpy".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidField { field, .. }) if field == "agents[1].personality_template"
+        ));
+        config.agents[1].personality = Some(Personality::new(0.5, 0.5, 0.5, 0.5, 0.5));
+        assert!(config.validate().is_ok());
+
+        config.agents[0].color = Some("#ff8800".to_string());
+        config.ui.colors = vec!["red".to_string(), "purplish".to_string()];
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidField { field, .. }) if field == "ui.colors[1]"
+        ));
+        config.ui.colors.pop();
+        config.ui.observer_color = "#12345".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidField { field, .. }) if field == "ui.observer_color"
+        ));
+        config.ui.observer_color = "cyan".to_string();
+
+        config.world.ticks_per_hour = 0;
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvalidField { field, .. }) if field == "world.ticks_per_hour"
+        ));
+    }
+
+    #[test]
+    fn test_from_value_keeps_valid_fields() {
+        let value = serde_json::json!({
+            "world": { "width": 42, "energy_cap": "lots" },
+            "debug": false,
+            "autosave": "yes",
+        });
+        let (config, warnings) = Config::from_value(value).unwrap();
+
+        assert_eq!(config.world.width, 42);
+        assert_eq!(config.world.energy_cap, default_energy_cap());
+        assert!(!config.debug);
+        assert_eq!(config.autosave, default_autosave());
+        assert_eq!(config.agents.len(), Config::default().agents.len());
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings
+            .iter()
+            .any(|w| w.starts_with("ignoring 'autosave'")));
+        assert!(warnings
+            .iter()
+            .any(|w| w.starts_with("ignoring 'world.energy_cap'")));
+    }
+
+    #[test]
+    fn test_from_value_merges_agents_by_name() {
+        let value = serde_json::json!({
+            "agents": [
+                { "name": "Bob", "model": "llama3", "mood": "grumpy" },
+                { "name": "Dave", "personality_template": "curious", "initial_energy": 50.0,
+                  "initial_position": [1, 2] },
+            ],
+            "colour": "blue",
+        });
+        let (config, warnings) = Config::from_value(value).unwrap();
+
+        let bob_default = Config::default().agents[1].clone();
+        assert_eq!(config.agents.len(), 2);
+        assert_eq!(config.agents[0].name, "Bob");
+        assert_eq!(config.agents[0].model.as_deref(), Some("llama3"));
+        assert_eq!(
+            config.agents[0].personality_template,
+            bob_default.personality_template
+        );
+        assert_eq!(config.agents[1].name, "Dave");
+        assert_eq!(
+            warnings,
+            vec![
+                "ignoring unknown field 'agents.0.mood'".to_string(),
+                "ignoring unknown field 'colour'".to_string(),
+            ]
+        );
+    }
+}
@@ -1,7 +1,58 @@
 // conversation_manager.rs
 
+use crate::config::RecipientPreference;
 use crate::message::Message;
-use std::collections::HashMap;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// Who talked to whom: participants linked by the direct messages they exchanged.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionGraph {
+    /// Names of the participants, sorted, including those that left but still have links.
+    pub nodes: Vec<String>,
+
+    /// One link per pair that exchanged direct messages, sorted by pair.
+    pub edges: Vec<GraphEdge>,
+}
+
+/// A link between two participants of an `InteractionGraph`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphEdge {
+    /// First participant of the pair, in `pair_key` order.
+    pub source: String,
+
+    /// Second participant of the pair.
+    pub target: String,
+
+    /// Number of direct messages exchanged, both ways.
+    pub messages: u32,
+}
+
+impl InteractionGraph {
+    /// Renders the graph in the DOT language, for Graphviz or Gephi.
+    ///
+    /// # Returns
+    /// * An undirected graph whose edges are labeled and weighted by their message count.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph protopolis {\n");
+        for node in &self.nodes {
+            dot.push_str(&format!("  {};\n", quote_dot(node)));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "  {} -- {} [label={}, weight={}];\n",
+                quote_dot(&edge.source),
+                quote_dot(&edge.target),
+                edge.messages,
+                edge.messages
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
 
 /// Manages conversations between agents by storing message history and active conversations.
 pub struct ConversationManager {
@@ -10,42 +61,360 @@ pub struct ConversationManager {
 
     /// Tracks active conversations by storing ongoing communication partners.
     active_conversations: HashMap<String, Vec<String>>,
+
+    /// Number of direct messages exchanged between each pair of agents.
+    interaction_counts: HashMap<(String, String), u32>,
+
+    /// Names of the agents taking part in the simulation.
+    participants: Vec<String>,
+
+    /// Whether agents favor their most or least frequent partners.
+    recipient_preference: RecipientPreference,
 }
 
 impl ConversationManager {
     /// Creates a new, empty conversation manager.
-    pub fn new() -> Self {
+    ///
+    /// # Arguments
+    /// * `recipient_preference` - Which partners `choose_recipient` favors.
+    pub fn new(recipient_preference: RecipientPreference) -> Self {
         Self {
             conversations: HashMap::new(),
             active_conversations: HashMap::new(),
+            interaction_counts: HashMap::new(),
+            participants: Vec::new(),
+            recipient_preference,
         }
     }
 
+    /// Registers an agent as a possible conversation partner.
+    pub fn add_participant(&mut self, name: &str) {
+        if !self.participants.iter().any(|p| p == name) {
+            self.participants.push(name.to_string());
+        }
+    }
+
+    /// Stops offering an agent that left as a conversation partner; its messages are kept.
+    pub fn remove_participant(&mut self, name: &str) {
+        self.participants.retain(|p| p != name);
+    }
+
     /// Adds a message to the conversation history and updates active conversations.
     ///
     /// # Arguments
     /// * `message` - The message to be stored.
     pub fn add_message(&mut self, message: Message) {
-        let conversation_key = if message.sender < message.recipient {
-            (message.sender.clone(), message.recipient.clone())
-        } else {
-            (message.recipient.clone(), message.sender.clone())
-        };
+        let conversation_key = pair_key(&message.sender, &message.recipient);
+
+        // Only direct messages between agents build relationships
+        if self.participants.contains(&message.sender)
+            && self.participants.contains(&message.recipient)
+        {
+            *self
+                .interaction_counts
+                .entry(conversation_key.clone())
+                .or_default() += 1;
+        }
 
         self.conversations
             .entry(conversation_key)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(message.clone());
 
         // Update active conversations
         self.active_conversations
             .entry(message.sender.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(message.recipient.clone());
 
         self.active_conversations
             .entry(message.recipient.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(message.sender.clone());
     }
+
+    /// Returns how many direct messages two agents have exchanged.
+    pub fn interaction_count(&self, a: &str, b: &str) -> u32 {
+        self.interaction_counts
+            .get(&pair_key(a, b))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Builds the graph of who talked to whom from the interaction counts.
+    pub fn interaction_graph(&self) -> InteractionGraph {
+        let mut edges: Vec<GraphEdge> = self
+            .interaction_counts
+            .iter()
+            .map(|((source, target), &messages)| GraphEdge {
+                source: source.clone(),
+                target: target.clone(),
+                messages,
+            })
+            .collect();
+        edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+        let nodes: BTreeSet<&String> = self
+            .participants
+            .iter()
+            .chain(edges.iter().flat_map(|edge| [&edge.source, &edge.target]))
+            .collect();
+        InteractionGraph {
+            nodes: nodes.into_iter().cloned().collect(),
+            edges,
+        }
+    }
+
+    /// Chooses who an agent should address based on its relationships.
+    ///
+    /// Partners are drawn at random, weighted toward the most or least frequent ones, so every
+    /// partner stays reachable.
+    ///
+    /// # Arguments
+    /// * `agent` - Name of the agent about to speak.
+    /// * `rng` - The simulation's random number generator.
+    ///
+    /// # Returns
+    /// * The name of the drawn partner, or `"everyone"` if there is none.
+    ///   With `RecipientPreference::Most`, an agent without any relationship yet addresses everyone.
+    pub fn choose_recipient(&self, agent: &str, rng: &mut impl Rng) -> String {
+        let mut partners: Vec<(&String, u32)> = self
+            .participants
+            .iter()
+            .filter(|p| p.as_str() != agent)
+            .map(|p| (p, self.interaction_count(agent, p)))
+            .collect();
+        // Sort by name so seeded runs draw the same partners
+        partners.sort_by(|a, b| a.0.cmp(b.0));
+
+        if self.recipient_preference == RecipientPreference::Most
+            && partners.iter().all(|(_, count)| *count == 0)
+        {
+            return "everyone".to_string();
+        }
+
+        let weights = partners.iter().map(|(_, count)| {
+            let count = *count as f64;
+            match self.recipient_preference {
+                RecipientPreference::Most => count + 1.0,
+                RecipientPreference::Least => 1.0 / (count + 1.0),
+            }
+        });
+        match WeightedIndex::new(weights) {
+            Ok(index) => partners[index.sample(rng)].0.clone(),
+            Err(_) => "everyone".to_string(),
+        }
+    }
+
+    /// Returns the total number of stored messages.
+    pub fn message_count(&self) -> usize {
+        self.conversations.values().map(Vec::len).sum()
+    }
+
+    /// Returns how many messages each sender has sent, sorted by sender name.
+    pub fn messages_per_sender(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for message in self.conversations.values().flatten() {
+            *counts.entry(message.sender.as_str()).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(sender, count)| (sender.to_string(), count))
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    /// Returns the average length, in characters, of the messages sent by participants.
+    ///
+    /// # Returns
+    /// * `Some(f64)` with the average length.
+    /// * `None` if no participant has sent a message yet.
+    pub fn average_response_length(&self) -> Option<f64> {
+        let lengths: Vec<usize> = self
+            .conversations
+            .values()
+            .flatten()
+            .filter(|m| self.participants.contains(&m.sender))
+            .map(|m| m.content.text.chars().count())
+            .collect();
+        if lengths.is_empty() {
+            return None;
+        }
+        Some(lengths.iter().sum::<usize>() as f64 / lengths.len() as f64)
+    }
+
+    /// Returns every stored message in chronological order.
+    pub fn messages(&self) -> Vec<&Message> {
+        let mut messages: Vec<&Message> = self.conversations.values().flatten().collect();
+        messages.sort_by_key(|m| m.timestamp);
+        messages
+    }
+
+    /// Returns the messages sent in a room, in chronological order.
+    pub fn messages_in_room(&self, room: &str) -> Vec<&Message> {
+        self.messages()
+            .into_iter()
+            .filter(|m| m.room.as_deref() == Some(room))
+            .collect()
+    }
+
+    /// Renders the full transcript as Markdown, one section per message.
+    ///
+    /// # Returns
+    /// * A Markdown document with a `### [timestamp] Sender → Recipient` header per message.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Protopolis transcript\n");
+        for message in self.messages() {
+            markdown.push_str(&format!(
+                "\n### [{}] {} → {}\n\n{}\n",
+                message.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                escape_markdown(&message.sender),
+                escape_markdown(&message.recipient),
+                escape_markdown(&message.content.text)
+            ));
+        }
+        markdown
+    }
+}
+
+/// Builds the order-independent key identifying a pair of participants.
+pub fn pair_key(a: &str, b: &str) -> (String, String) {
+    if a < b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// Quotes a name as a DOT identifier.
+fn quote_dot(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Characters that carry meaning in Markdown and must be escaped in content.
+const MARKDOWN_SPECIAL_CHARS: &str = "\\`*_{}[]()#+-!|<>";
+
+/// Escapes characters that carry meaning in Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_SPECIAL_CHARS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn message(sender: &str, recipient: &str) -> Message {
+        Message::new(sender, recipient, "hello")
+    }
+
+    #[test]
+    fn test_choose_recipient_follows_preference() {
+        let mut most = ConversationManager::new(RecipientPreference::Most);
+        let mut least = ConversationManager::new(RecipientPreference::Least);
+        for manager in [&mut most, &mut least] {
+            for name in ["Alice", "Bob", "Charlie"] {
+                manager.add_participant(name);
+            }
+            for _ in 0..2 {
+                manager.add_message(message("Alice", "Bob"));
+                manager.add_message(message("Bob", "Alice"));
+            }
+            manager.add_message(message("Alice", "everyone"));
+        }
+        assert_eq!(most.interaction_count("Bob", "Alice"), 4);
+
+        // Bob has 4 exchanges with Alice and Charlie none: weights 5:1 for most, 1/5:1 for least
+        let mut rng = StdRng::seed_from_u64(7);
+        let draws = |manager: &ConversationManager, rng: &mut StdRng| {
+            let mut bob = 0;
+            for _ in 0..600 {
+                match manager.choose_recipient("Alice", rng).as_str() {
+                    "Bob" => bob += 1,
+                    other => assert_eq!(other, "Charlie"),
+                }
+            }
+            bob
+        };
+        let bob = draws(&most, &mut rng);
+        assert!((420..580).contains(&bob), "most drew Bob {} times", bob);
+        let bob = draws(&least, &mut rng);
+        assert!((20..180).contains(&bob), "least drew Bob {} times", bob);
+
+        assert_eq!(most.choose_recipient("Charlie", &mut rng), "everyone");
+    }
+
+    #[test]
+    fn test_stats_queries() {
+        let mut manager = ConversationManager::new(RecipientPreference::Most);
+        manager.add_participant("Alice");
+        manager.add_participant("Bob");
+        manager.add_message(message("Alice", "Bob"));
+        manager.add_message(message("Alice", "everyone"));
+        manager.add_message(Message::new("User", "Bob", "a much longer message"));
+
+        assert_eq!(
+            manager.messages_per_sender(),
+            vec![("Alice".to_string(), 2), ("User".to_string(), 1)]
+        );
+        assert_eq!(manager.average_response_length(), Some(5.0));
+    }
+
+    #[test]
+    fn test_markdown_escapes_special_characters() {
+        let mut manager = ConversationManager::new(RecipientPreference::Most);
+        manager.add_message(Message::new(
+            "Agent_1",
+            "everyone",
+            "# Not a *heading* [link](url)",
+        ));
+
+        let markdown = manager.to_markdown();
+        assert!(markdown.starts_with("# Protopolis transcript\n"));
+        assert!(markdown.contains("] Agent\\_1 → everyone\n"));
+        assert!(markdown.contains("\n\\# Not a \\*heading\\* \\[link\\]\\(url\\)\n"));
+    }
+
+    #[test]
+    fn test_interaction_graph() {
+        let mut manager = ConversationManager::new(RecipientPreference::Most);
+        for name in ["Alice", "Bob", "Charlie"] {
+            manager.add_participant(name);
+        }
+        manager.add_message(message("Bob", "Alice"));
+        manager.add_message(message("Alice", "Bob"));
+        manager.add_message(message("Charlie", "Alice"));
+        manager.add_message(message("Alice", "everyone"));
+        manager.remove_participant("Charlie");
+
+        let graph = manager.interaction_graph();
+        assert_eq!(graph.nodes, ["Alice", "Bob", "Charlie"]);
+        assert_eq!(
+            graph.edges,
+            [
+                GraphEdge {
+                    source: "Alice".to_string(),
+                    target: "Bob".to_string(),
+                    messages: 2,
+                },
+                GraphEdge {
+                    source: "Alice".to_string(),
+                    target: "Charlie".to_string(),
+                    messages: 1,
+                },
+            ]
+        );
+        assert!(graph
+            .to_dot()
+            .contains("  \"Alice\" -- \"Bob\" [label=2, weight=2];\n"));
+    }
 }
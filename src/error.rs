@@ -0,0 +1,59 @@
+// error.rs
+
+use thiserror::Error;
+
+/// Errors that can occur across the simulation.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The requested model is not installed on the Ollama server.
+    #[error("model '{0}' not found")]
+    ModelNotFound(String),
+
+    /// The Ollama server could not be reached.
+    #[error("cannot connect to Ollama: {0}")]
+    OllamaConnection(String),
+
+    /// A generation took longer than the configured timeout.
+    #[error("generation timed out")]
+    Timeout,
+
+    /// The model returned nothing but whitespace, even when asked again.
+    #[error("model returned an empty response")]
+    EmptyResponse,
+
+    /// The model repeated one of the agent's recent responses, even when asked not to.
+    #[error("model repeated a recent response")]
+    RepeatedResponse,
+
+    /// The model failed to generate a response for another reason.
+    #[error("generation failed: {0}")]
+    Generation(String),
+
+    /// The configuration file is not valid JSON or does not match the expected shape.
+    #[error("invalid configuration file: {0}")]
+    ConfigParse(serde_json::Error),
+
+    /// The configuration parsed but contains invalid values.
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// A configuration field holds a value outside what it accepts.
+    #[error("invalid configuration: '{field}' {reason}")]
+    InvalidField {
+        /// Path of the offending field, e.g. `agents[0].initial_energy`.
+        field: String,
+        /// What is wrong with its value.
+        reason: String,
+    },
+
+    /// Data could not be serialized or deserialized.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// A file could not be read or written.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Result type used throughout the crate.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,50 @@
+// interest.rs
+
+use std::collections::HashSet;
+
+/// Words shorter than this are ignored when comparing texts.
+const MIN_KEYWORD_LEN: usize = 4;
+
+/// Extracts the lowercase keywords of a text.
+fn keywords(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_KEYWORD_LEN)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Scores how relevant a message is to a set of interests by keyword overlap.
+///
+/// # Arguments
+/// * `message` - The text heard by the agent.
+/// * `interests` - The current topic and the agent's interests.
+///
+/// # Returns
+/// * A score between 0 (nothing in common) and 1 (every keyword of the smaller side is
+///   shared), or 1 when there are no interests to compare against.
+pub fn relevance(message: &str, interests: &[&str]) -> f32 {
+    let reference: HashSet<String> = interests.iter().flat_map(|i| keywords(i)).collect();
+    if reference.is_empty() {
+        return 1.0;
+    }
+    let heard = keywords(message);
+    if heard.is_empty() {
+        return 0.0;
+    }
+
+    let shared = heard.intersection(&reference).count();
+    shared as f32 / heard.len().min(reference.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relevance_by_keyword_overlap() {
+        let interests = ["urban planning", "public transport"];
+        assert_eq!(relevance("What about public transport?", &interests), 0.5);
+        assert_eq!(relevance("I like cooking pasta", &interests), 0.0);
+        assert_eq!(relevance("Anything goes", &[]), 1.0);
+    }
+}
@@ -2,40 +2,151 @@
 
 // Module declarations
 mod agent;
+mod backend;
+mod cache;
 mod config;
 mod conversation_manager;
+mod error;
+mod interest;
+mod memory;
 mod message;
 mod personality;
+mod postprocess;
+mod prompt;
+mod recall;
+mod repetition;
+mod replay;
+mod scene;
+mod sentiment;
+mod server;
 mod simulation;
 mod state;
+mod strings;
+mod tool;
 mod ui;
+mod utils;
 
 use crate::config::Config;
+use crate::error::Error;
+use crate::replay::Replay;
 use crate::simulation::Simulation;
 use crate::ui::UI;
-use std::path::Path;
+use crate::utils::SavedSession;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
-use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the simulation to finish its shutdown before exiting.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Scenarios can live in their own files, picked with --config
+    let config_path = match args.iter().position(|arg| arg == "--config") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                eprintln!("Missing path after --config");
+                std::process::exit(1);
+            }
+        },
+        None => PathBuf::from("config.json"),
+    };
+    if let Some(parent) = config_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.is_dir() {
+            eprintln!(
+                "Cannot use {}: directory {} does not exist",
+                config_path.display(),
+                parent.display()
+            );
+            std::process::exit(1);
+        }
+    }
+    let config_path = config_path.as_path();
+
     // Load configuration file
-    let config_path = Path::new("config.json");
     let mut config = match Config::load(config_path) {
-        Ok(config) => config,
+        Ok((config, warnings)) => {
+            for warning in warnings {
+                eprintln!("Warning: {}: {}", config_path.display(), warning);
+            }
+            config
+        }
+        // Parsed but invalid: starting anyway would run on garbage values
+        Err(e @ (Error::InvalidField { .. } | Error::InvalidConfig(_))) => {
+            eprintln!("{}: {}", config_path.display(), e);
+            std::process::exit(1);
+        }
         Err(e) => {
             eprintln!("Error loading configuration: {}", e);
             let config = config::Config::default();
-            let _ = config.save(Path::new("config.json"));
+            match e {
+                // No file yet: start one from the defaults
+                Error::Io(ref io) if io.kind() == io::ErrorKind::NotFound => {
+                    let _ = config.save(config_path);
+                }
+                // Unparseable: keep the user's file aside before replacing it
+                Error::ConfigParse(_) => {
+                    let mut backup = config_path.as_os_str().to_owned();
+                    backup.push(".bak");
+                    let backup = PathBuf::from(backup);
+                    match std::fs::copy(config_path, &backup) {
+                        Ok(_) => {
+                            eprintln!(
+                                "Backed up the invalid configuration to {}",
+                                backup.display()
+                            );
+                            let _ = config.save(config_path);
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "Could not back up {}, leaving it as is: {}",
+                                config_path.display(),
+                                e
+                            )
+                        }
+                    }
+                }
+                // Unreadable: use the defaults for this run without touching the file
+                _ => {}
+            }
             config
         }
     };
 
-    if config.ollama_model.is_none() {
+    // Command-line flags override the configuration file
+    if args.iter().any(|arg| arg == "--skip-splash") {
+        config.skip_splash = true;
+    }
+    if args.iter().any(|arg| arg == "--no-cache") {
+        config.cache = None;
+    }
+
+    // Replaying a transcript needs no model, so it skips the model selection entirely
+    let replay_path = args
+        .iter()
+        .position(|arg| arg == "--replay")
+        .and_then(|i| args.get(i + 1));
+    let replay_messages = match replay_path {
+        Some(path) => match utils::load_transcript(Path::new(path)) {
+            Ok(messages) => Some(messages),
+            Err(e) => {
+                eprintln!("Error loading transcript {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if replay_messages.is_none()
+        && config.ollama_model.is_none()
+        && config.backend == config::BackendKind::Ollama
+    {
         println!("No Ollama model configured. Please choose a model from the list below:");
-        let output = std::process::Command::new("ollama")
-            .arg("list")
-            .output();
+        let output = std::process::Command::new("ollama").arg("list").output();
 
         match output {
             Ok(output) => {
@@ -90,24 +201,103 @@ fn main() {
         }
     }
 
+    // Offer to pick up where the last session left off
+    let resume = args.iter().any(|arg| arg == "--resume");
+    let snapshot = match replay_messages {
+        Some(_) => None,
+        None => offer_snapshot(&config, resume),
+    };
+
     // Create communication channels
     let (ui_tx, sim_rx) = mpsc::channel();
     let (sim_tx, ui_rx) = mpsc::channel();
 
-    // Spawn the simulation thread
-    let simulation_thread = thread::spawn(move || {
-        let mut simulation = Simulation::new(config, sim_tx, sim_rx);
-        simulation.run();
+    // The UI keeps its own copy of the configuration for appearance settings
+    let mut ui = UI::new(ui_tx, ui_rx, &config, config_path);
+
+    // Spawn the simulation thread, or the replay standing in for it
+    let simulation_thread = thread::spawn(move || match replay_messages {
+        Some(messages) => {
+            let tick_duration = Duration::from_millis(config.replay_tick_ms);
+            Replay::new(messages, tick_duration, sim_tx, sim_rx).run();
+        }
+        None => {
+            let mut simulation = Simulation::new(config, sim_tx, sim_rx);
+            if let Some(session) = &snapshot {
+                simulation.restore(session);
+            }
+            simulation.start_server();
+            simulation.run();
+        }
     });
 
-    // Initialize and start the user interface
-    let mut ui = UI::new(ui_tx, ui_rx);
+    // Start the user interface
     if let Err(err) = ui.run() {
         eprintln!("Error running UI: {}", err);
     }
 
-    // Wait for the simulation thread to finish
-    if let Err(e) = simulation_thread.join() {
-        eprintln!("Error joining the simulation thread: {:?}", e);
+    // Wait for the simulation thread to flush its work, but don't hang forever
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    while !simulation_thread.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    if simulation_thread.is_finished() {
+        if let Err(e) = simulation_thread.join() {
+            eprintln!("Error joining the simulation thread: {:?}", e);
+        }
+    } else {
+        eprintln!(
+            "Simulation did not shut down within {} seconds, exiting anyway.",
+            SHUTDOWN_TIMEOUT.as_secs()
+        );
+    }
+}
+
+/// Looks for the autosave snapshot and decides whether to restore it.
+///
+/// With `--resume` the snapshot is restored whatever its age. Otherwise a recent snapshot is
+/// offered with a yes/no prompt, when there is a terminal to answer it.
+///
+/// # Arguments
+/// * `config` - The configuration naming the snapshot file and its maximum age.
+/// * `resume` - Whether `--resume` was passed.
+///
+/// # Returns
+/// * The session to restore, or `None` to start fresh.
+fn offer_snapshot(config: &Config, resume: bool) -> Option<SavedSession> {
+    let path = Path::new(&config.snapshot.path);
+    if !path.exists() {
+        if resume {
+            eprintln!("No snapshot to resume at {}", path.display());
+        }
+        return None;
     }
+
+    let session = match utils::load_conversations(path) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Error loading snapshot {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if resume {
+        return Some(session);
+    }
+    if !session.is_recent(config.snapshot.max_age_hours) || !io::stdin().is_terminal() {
+        return None;
+    }
+
+    print!(
+        "Found a session saved at {} with {} agent(s). Restore it? [y/N] ",
+        session
+            .saved_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M"),
+        session.agents.len()
+    );
+    io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok()?;
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes").then_some(session)
 }
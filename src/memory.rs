@@ -0,0 +1,80 @@
+// memory.rs
+
+use std::sync::{Arc, RwLock};
+
+/// Most facts kept in the shared memory.
+pub const MAX_FACTS: usize = 10;
+
+/// Facts distilled from the discussion, shared by every agent.
+///
+/// Clones share the same facts, so agents generating on other threads read the latest
+/// synthesis.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalMemory(Arc<RwLock<Vec<String>>>);
+
+impl GlobalMemory {
+    /// Returns a copy of the known facts.
+    pub fn facts(&self) -> Vec<String> {
+        self.0.read().map(|facts| facts.clone()).unwrap_or_default()
+    }
+
+    /// Replaces the known facts, keeping at most `MAX_FACTS`.
+    pub fn replace(&self, mut facts: Vec<String>) {
+        facts.truncate(MAX_FACTS);
+        if let Ok(mut known) = self.0.write() {
+            *known = facts;
+        }
+    }
+
+    /// Lists the known facts for a prompt, one per line.
+    ///
+    /// # Returns
+    /// * The facts as a bulleted list, or `"none yet"` when nothing is known.
+    pub fn describe(&self) -> String {
+        let facts = self.facts();
+        if facts.is_empty() {
+            return "none yet".to_string();
+        }
+        facts
+            .iter()
+            .map(|fact| format!("- {}", fact))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Extracts the facts from a model's reply, one per non-empty line.
+///
+/// # Arguments
+/// * `reply` - The model's reply, usually a bulleted list.
+///
+/// # Returns
+/// * The facts without their bullets, at most `MAX_FACTS`.
+pub fn parse_facts(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['-', '*', '•']).trim())
+        .filter(|fact| !fact.is_empty())
+        .take(MAX_FACTS)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_facts_strips_bullets() {
+        let facts = parse_facts("- Trams are quiet\n\n* Bob lives downtown\n  Buses are cheap  ");
+        assert_eq!(
+            facts,
+            vec!["Trams are quiet", "Bob lives downtown", "Buses are cheap"]
+        );
+
+        let memory = GlobalMemory::default();
+        assert_eq!(memory.describe(), "none yet");
+        memory.clone().replace(facts);
+        assert_eq!(memory.describe().lines().count(), 3);
+    }
+}
@@ -1,11 +1,115 @@
 // message.rs
 
+use crate::sentiment::Sentiment;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
 
-/// Type alias for message content, allowing flexible JSON structures.
-pub type MessageContent = Value;
+/// Content of a message: text plus optional structured parts.
+///
+/// Serialized as a bare string when it only holds text, and deserialized from either a bare
+/// string or an object, so older saves and clients keep working.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawContent", into = "RawContent")]
+pub struct MessageContent {
+    /// The text of the message.
+    pub text: String,
+
+    /// Name of an agent the message cites.
+    pub cited_agent: Option<String>,
+
+    /// Action the sender took, described in words.
+    pub action: Option<String>,
+
+    /// Tool call made by the sender.
+    pub tool_call: Option<String>,
+}
+
+impl MessageContent {
+    /// Returns the labelled optional parts that are present, in display order.
+    pub fn parts(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("cites", &self.cited_agent),
+            ("action", &self.action),
+            ("tool", &self.tool_call),
+        ]
+        .into_iter()
+        .filter_map(|(label, part)| part.as_deref().map(|value| (label, value)))
+        .collect()
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+impl fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Wire format of `MessageContent`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawContent {
+    Text(String),
+    Parts {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cited_agent: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        action: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_call: Option<String>,
+    },
+}
+
+impl From<RawContent> for MessageContent {
+    fn from(raw: RawContent) -> Self {
+        match raw {
+            RawContent::Text(text) => Self::from(text),
+            RawContent::Parts {
+                text,
+                cited_agent,
+                action,
+                tool_call,
+            } => Self {
+                text,
+                cited_agent,
+                action,
+                tool_call,
+            },
+        }
+    }
+}
+
+impl From<MessageContent> for RawContent {
+    fn from(content: MessageContent) -> Self {
+        if content.parts().is_empty() {
+            RawContent::Text(content.text)
+        } else {
+            RawContent::Parts {
+                text: content.text,
+                cited_agent: content.cited_agent,
+                action: content.action,
+                tool_call: content.tool_call,
+            }
+        }
+    }
+}
 
 /// Represents a message exchanged between agents.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +126,71 @@ pub struct Message {
     /// Identifier of the recipient (could be an agent name or broadcast).
     pub recipient: String,
 
-    /// The actual message content, stored as a flexible JSON value.
+    /// The actual message content.
     pub content: MessageContent,
+
+    /// Simulation tick at which the message was sent.
+    #[serde(default)]
+    pub tick: u64,
+
+    /// Tone of the message, when sentiment tagging is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<Sentiment>,
+
+    /// Room the message was sent in; every agent hears it when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+impl Message {
+    /// Creates a new message with a fresh ID, timestamped now.
+    ///
+    /// # Arguments
+    /// * `sender` - Identifier of the sender.
+    /// * `recipient` - Identifier of the recipient.
+    /// * `content` - The message content.
+    ///
+    /// # Returns
+    /// * A new untagged `Message`.
+    pub fn new(sender: &str, recipient: &str, content: impl Into<MessageContent>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            sender: sender.to_string(),
+            recipient: recipient.to_string(),
+            content: content.into(),
+            tick: 0,
+            sentiment: None,
+            room: None,
+        }
+    }
+
+    /// Stamps the message with the simulation tick it was sent at.
+    pub fn at_tick(mut self, tick: u64) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Scopes the message to a room, or to the whole simulation when `None`.
+    pub fn in_room(mut self, room: Option<String>) -> Self {
+        self.room = room;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_accepts_bare_strings_and_parts() {
+        let text: MessageContent = serde_json::from_str("\"hello\"").unwrap();
+        assert_eq!(text, MessageContent::from("hello"));
+        assert_eq!(serde_json::to_string(&text).unwrap(), "\"hello\"");
+
+        let parts: MessageContent =
+            serde_json::from_str(r#"{"text": "hello", "cited_agent": "Bob"}"#).unwrap();
+        assert_eq!(parts.text, "hello");
+        assert_eq!(parts.parts(), vec![("cites", "Bob")]);
+    }
 }
@@ -1,7 +1,9 @@
 // personality.rs
 
+use serde::{Deserialize, Serialize};
+
 /// Represents an agent's personality using the Big Five personality traits.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Personality {
     /// Openness to experience (curiosity, creativity).
     pub openness: f32,
@@ -48,6 +50,67 @@ impl Personality {
     }
 }
 
+/// Names of the Big Five traits, as accepted by `Personality::trait_mut`.
+pub const TRAIT_NAMES: [&str; 5] = [
+    "openness",
+    "conscientiousness",
+    "extraversion",
+    "agreeableness",
+    "neuroticism",
+];
+
+impl Personality {
+    /// Looks up a trait by name.
+    ///
+    /// # Arguments
+    /// * `name` - One of `TRAIT_NAMES`.
+    ///
+    /// # Returns
+    /// * A mutable reference to the trait value, or `None` if the name is not a Big Five trait.
+    pub fn trait_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "openness" => Some(&mut self.openness),
+            "conscientiousness" => Some(&mut self.conscientiousness),
+            "extraversion" => Some(&mut self.extraversion),
+            "agreeableness" => Some(&mut self.agreeableness),
+            "neuroticism" => Some(&mut self.neuroticism),
+            _ => None,
+        }
+    }
+}
+
+impl Personality {
+    /// Shifts the traits shaped by the tone of an agent's interactions, keeping them in [0, 1].
+    ///
+    /// Hostile exchanges raise neuroticism and lower agreeableness; friendly ones do the
+    /// opposite.
+    ///
+    /// # Arguments
+    /// * `mood` - Net tone of the interactions, from -1 (all negative) to 1 (all positive).
+    /// * `rate` - Largest change applied to a trait.
+    pub fn drift(&mut self, mood: f32, rate: f32) {
+        let shift = (mood * rate).clamp(-rate, rate);
+        self.neuroticism = (self.neuroticism - shift).clamp(0.0, 1.0);
+        self.agreeableness = (self.agreeableness + shift).clamp(0.0, 1.0);
+    }
+
+    /// Describes the traits on one line, e.g. "openness 0.62, conscientiousness 0.50, ...".
+    pub fn describe(&self) -> String {
+        format!(
+            "openness {:.2}, conscientiousness {:.2}, extraversion {:.2}, agreeableness {:.2}, \
+            neuroticism {:.2}",
+            self.openness,
+            self.conscientiousness,
+            self.extraversion,
+            self.agreeableness,
+            self.neuroticism
+        )
+    }
+}
+
+/// Names of the predefined personality templates, as accepted by `get_personality_template`.
+pub const TEMPLATE_NAMES: [&str; 4] = ["friendly", "curious", "cautious", "balanced"];
+
 /// Generates a personality based on a predefined template.
 ///
 /// # Arguments
@@ -0,0 +1,123 @@
+// postprocess.rs
+
+use crate::config::PostProcessConfig;
+
+/// Role labels models sometimes open their responses with.
+const ROLE_PREFIXES: [&str; 4] = ["Assistant", "AI", "Response", "Answer"];
+
+/// Pairs of quotes that may surround a whole response.
+const QUOTE_PAIRS: [(char, char); 3] = [('"', '"'), ('“', '”'), ('\'', '\'')];
+
+/// Cleans up a raw model response with the enabled post-processing steps.
+///
+/// Steps run in order: role prefixes are stripped, surrounding quotes trimmed, whitespace
+/// collapsed, then the response is cut to its first sentences.
+///
+/// # Arguments
+/// * `config` - Which steps are enabled.
+/// * `response` - The raw response.
+/// * `speaker` - Name of the agent that generated it, also stripped as a role prefix.
+///
+/// # Returns
+/// * The cleaned-up response, or the trimmed raw response if nothing would be left of it.
+pub fn postprocess(config: &PostProcessConfig, response: &str, speaker: &str) -> String {
+    let mut text = response.trim();
+
+    // Prefixes and quotes may nest, e.g. `Alice: "Hello"`
+    loop {
+        let before = text;
+        if config.strip_role_prefix {
+            text = strip_role_prefix(text, speaker);
+        }
+        if config.trim_quotes {
+            text = trim_quotes(text);
+        }
+        if text == before {
+            break;
+        }
+    }
+
+    let mut text = if config.collapse_whitespace {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text.to_string()
+    };
+
+    if let Some(max) = config.max_sentences {
+        text = first_sentences(&text, max).to_string();
+    }
+
+    if text.is_empty() {
+        response.trim().to_string()
+    } else {
+        text
+    }
+}
+
+/// Removes a leading `Role:` label, matched case-insensitively.
+fn strip_role_prefix<'a>(text: &'a str, speaker: &str) -> &'a str {
+    ROLE_PREFIXES
+        .iter()
+        .chain(std::iter::once(&speaker))
+        .find_map(|role| {
+            let head = text.get(..role.len())?;
+            let rest = text[role.len()..].strip_prefix(':')?;
+            head.eq_ignore_ascii_case(role).then(|| rest.trim_start())
+        })
+        .unwrap_or(text)
+}
+
+/// Removes one pair of quotes surrounding the whole text.
+fn trim_quotes(text: &str) -> &str {
+    QUOTE_PAIRS
+        .iter()
+        .find_map(|&(open, close)| text.strip_prefix(open)?.strip_suffix(close).map(str::trim))
+        .unwrap_or(text)
+}
+
+/// Keeps the first sentences of a text, ending at `.`, `!`, or `?` followed by whitespace.
+fn first_sentences(text: &str, max: usize) -> &str {
+    let mut count = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let ends_sentence = matches!(c, '.' | '!' | '?')
+            && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if ends_sentence {
+            count += 1;
+            if count >= max {
+                return &text[..i + c.len_utf8()];
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postprocess_cleans_up_response() {
+        let config = PostProcessConfig {
+            max_sentences: Some(2),
+            ..PostProcessConfig::default()
+        };
+        let response = "Alice: \"Hello  there.\nHow are you? I am fine. Thanks!\"";
+        assert_eq!(
+            postprocess(&config, response, "Alice"),
+            "Hello there. How are you?"
+        );
+
+        let disabled = PostProcessConfig {
+            trim_quotes: false,
+            strip_role_prefix: false,
+            collapse_whitespace: false,
+            max_sentences: None,
+        };
+        assert_eq!(
+            postprocess(&disabled, "  Assistant: \"Hi\"  ", "Alice"),
+            "Assistant: \"Hi\""
+        );
+        assert_eq!(postprocess(&config, "\"\"", "Alice"), "\"\"");
+    }
+}
@@ -1,32 +1,185 @@
-use crate::message::Message;
-use crate::personality::Personality;
-
-pub struct Prompt;
-
-impl Prompt {
-    pub const FIRST_PROMPT: &'static str = "The entrypoint of this world is: {0}.\n\nYou are {1}, you have the following personality : {2}.\
-                                            You will be able to interact with other agents in the system. Try to keep your answers short and concise.\
-                                            However, you are free, dont feel constrained by the system. You can do whatever you want. \
-                                            You can move around the world, talk to other agents, or just observe.";
-    pub const GET_MESSAGE: &'static str = "\n\n{1} -> {2} : {3}";
-
-    pub fn get_first_prompt(
-        system_prompt: String,
-        name: String,
-        personality: Personality,
-    ) -> String {
-        Self::FIRST_PROMPT
-            .to_string()
-            .replace("{0}", system_prompt.as_str())
-            .replace("{1}", name.as_str())
-            .replace("{2}", personality.get_description())
+// prompt.rs
+
+use serde::{Deserialize, Serialize};
+
+/// Default template used to prompt agents for a response.
+pub const DEFAULT_AGENT_PROMPT: &str =
+    "You are {name}, an AI agent with the following personality traits:\n\
+    {personality}\n\
+    {verbosity} while staying in character.\n\
+    {goal}{expertise}{language}\n\
+    Discussion topic: {topic}\n\n\
+    Known facts:\n{facts}\n\n\
+    Relevant memories:\n{memories}\n\n\
+    Conversation history:\n{history}\n\n\
+    Recent messages:\n{recent}\n\n\
+    How would you respond?";
+
+/// Default template used to prompt the agent opening a discussion for its first turn.
+pub const DEFAULT_OPENING_PROMPT: &str =
+    "You are {name}, an AI agent with the following personality traits:\n\
+    {personality}\n\
+    {verbosity} while staying in character.\n\
+    {goal}{expertise}{language}\n\
+    You are opening the discussion on {topic}. Set the stage: introduce the subject, say why \
+    it matters, and invite the others to share their views.\n\n\
+    Known facts:\n{facts}\n\n\
+    Relevant memories:\n{memories}\n\n\
+    Conversation history:\n{history}\n\n\
+    How do you open the discussion?";
+
+/// Default template used to prompt the observer for a summary.
+pub const DEFAULT_OBSERVER_PROMPT: &str =
+    "You are {name}, a silent observer of a discussion between AI agents about {topic}.\n\n\
+    Recent messages:\n{recent}\n\n\
+    Summarize for a human reader, in 2-3 sentences, what was discussed and where the \
+    discussion is heading. Do not take part in it.";
+
+/// Default template used to summarize the discussion for its participants.
+pub const DEFAULT_SUMMARY_PROMPT: &str =
+    "The following messages come from a discussion between AI agents about {topic}.\n\n\
+    Messages:\n{recent}\n\n\
+    Summarize in 2-3 sentences the points made so far and where the participants agree or \
+    disagree, so they remember what has been said.";
+
+/// Appended to an agent's prompt when it is asked again after repeating itself.
+pub const REPETITION_NUDGE: &str =
+    "Avoid repeating yourself: say something you have not said yet in this discussion.";
+
+/// A prompt with named `{placeholder}` slots, loaded from the configuration.
+///
+/// Supported placeholders are `{name}`, `{personality}`, `{verbosity}`, `{goal}`,
+/// `{expertise}`, `{language}`, `{history}`, `{recent}`, `{topic}`, `{facts}`, and
+/// `{memories}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PromptTemplate(pub String);
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_AGENT_PROMPT.to_string())
+    }
+}
+
+impl PromptTemplate {
+    /// Returns the default template of the prompt opening a discussion.
+    pub fn opening() -> Self {
+        Self(DEFAULT_OPENING_PROMPT.to_string())
+    }
+
+    /// Renders the template with the given placeholder values.
+    ///
+    /// # Arguments
+    /// * `values` - Pairs of placeholder name (without braces) and replacement text.
+    ///
+    /// # Returns
+    /// * The rendered prompt.
+    pub fn render(&self, values: &[(&str, &str)]) -> String {
+        render(&self.0, values)
+    }
+
+    /// Returns a copy of the template with the section holding a placeholder removed.
+    ///
+    /// # Arguments
+    /// * `key` - Name of the placeholder (without braces).
+    ///
+    /// # Returns
+    /// * The template without the section, see `omit_section`.
+    pub fn without_section(&self, key: &str) -> Self {
+        Self(omit_section(&self.0, key))
+    }
+}
+
+/// Removes the section of a template holding a placeholder, heading included.
+///
+/// A section is a heading line ending with a colon, followed by a line starting with the
+/// placeholder, and the blank line after it, e.g. "Conversation history:\n{history}\n\n".
+/// Templates where the placeholder is not laid out this way are returned unchanged.
+///
+/// # Arguments
+/// * `template` - The template text.
+/// * `key` - Name of the placeholder (without braces).
+///
+/// # Returns
+/// * The template without the section.
+pub fn omit_section(template: &str, key: &str) -> String {
+    let placeholder = format!("{{{}}}", key);
+    let Some(at) = template.find(&placeholder) else {
+        return template.to_string();
+    };
+    let Some(heading_end) = at
+        .checked_sub(1)
+        .filter(|&i| template[i..].starts_with('\n'))
+    else {
+        return template.to_string();
+    };
+    let heading_start = template[..heading_end].rfind('\n').map_or(0, |i| i + 1);
+    if !template[heading_start..heading_end]
+        .trim_end()
+        .ends_with(':')
+    {
+        return template.to_string();
+    }
+
+    let rest = &template[at + placeholder.len()..];
+    let rest = rest.strip_prefix('\n').unwrap_or(rest);
+    let rest = rest.strip_prefix('\n').unwrap_or(rest);
+    format!("{}{}", &template[..heading_start], rest)
+}
+
+/// Substitutes `{key}` placeholders in a template in a single pass.
+///
+/// Unknown placeholders are kept as-is, and substituted text is never scanned again, so
+/// values containing braces (e.g. messages quoting `{name}`) are inserted verbatim.
+///
+/// # Arguments
+/// * `template` - The template text.
+/// * `values` - Pairs of placeholder name (without braces) and replacement text.
+///
+/// # Returns
+/// * The rendered text.
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let replacement = after.find('}').and_then(|end| {
+            let key = &after[..end];
+            values
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| (*value, end))
+        });
+
+        match replacement {
+            Some((value, end)) => {
+                output.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = after;
+            }
+        }
     }
+    output.push_str(rest);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn get_message(message: Message) -> String {
-        Self::GET_MESSAGE
-            .to_string()
-            .replace("{1}", &message.sender)
-            .replace("{2}", &message.recipient)
-            .replace("{3}", &message.content.to_string())
+    #[test]
+    fn test_render_substitutes_named_placeholders() {
+        let rendered = render(
+            "{name} talks about {topic} {unknown}",
+            &[("name", "Alice"), ("topic", "{name}")],
+        );
+        assert_eq!(rendered, "Alice talks about {name} {unknown}");
     }
 }
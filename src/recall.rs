@@ -0,0 +1,117 @@
+// recall.rs
+
+use std::collections::{HashMap, VecDeque};
+
+/// Number of messages kept in the index; older ones can no longer be recalled.
+const INDEX_CAPACITY: usize = 2000;
+
+/// Past messages indexed by embedding, recalled by similarity to what an agent is answering.
+#[derive(Debug, Default)]
+pub struct MemoryIndex {
+    /// Embedding and rendered line of each indexed message, keyed by message id.
+    entries: HashMap<String, (Vec<f32>, String)>,
+    /// Ids of the indexed messages, oldest first.
+    order: VecDeque<String>,
+}
+
+impl MemoryIndex {
+    /// Indexes a message, evicting the oldest one past `INDEX_CAPACITY`.
+    ///
+    /// # Arguments
+    /// * `id` - The message id.
+    /// * `vector` - The embedding of the message text.
+    /// * `line` - The message as it should appear in a prompt.
+    pub fn insert(&mut self, id: String, vector: Vec<f32>, line: String) {
+        if self.entries.insert(id.clone(), (vector, line)).is_some() {
+            return;
+        }
+        self.order.push_back(id);
+        if self.order.len() > INDEX_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Finds the indexed messages most similar to a query.
+    ///
+    /// # Arguments
+    /// * `query` - The embedding of the current context.
+    /// * `k` - How many messages to return.
+    /// * `skip` - Returns true for lines that should not be recalled (e.g. already in the prompt).
+    ///
+    /// # Returns
+    /// * Up to `k` message lines, most similar first.
+    pub fn most_similar(
+        &self,
+        query: &[f32],
+        k: usize,
+        skip: impl Fn(&str) -> bool,
+    ) -> Vec<String> {
+        let mut scored: Vec<(f32, &String, &String)> = self
+            .entries
+            .iter()
+            .filter(|(_, (_, line))| !skip(line))
+            .map(|(id, (vector, line))| (cosine_similarity(query, vector), id, line))
+            .collect();
+        // Ties are broken by id so recall does not depend on map order
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(k)
+            .map(|(_, _, line)| line.clone())
+            .collect()
+    }
+}
+
+/// Computes the cosine similarity of two vectors.
+///
+/// # Returns
+/// * A value between -1 and 1, or 0 when either vector is empty or zero, or their sizes differ.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_similar_ranks_by_cosine() {
+        let mut index = MemoryIndex::default();
+        index.insert("a".to_string(), vec![1.0, 0.0], "trams".to_string());
+        index.insert("b".to_string(), vec![0.7, 0.7], "buses".to_string());
+        index.insert("c".to_string(), vec![0.0, 1.0], "pasta".to_string());
+
+        assert_eq!(
+            index.most_similar(&[1.0, 0.1], 2, |_| false),
+            vec!["trams", "buses"]
+        );
+        assert_eq!(
+            index.most_similar(&[1.0, 0.1], 2, |line| line == "trams"),
+            vec!["buses", "pasta"]
+        );
+        assert_eq!(cosine_similarity(&[1.0], &[0.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_index_forgets_oldest_messages() {
+        let mut index = MemoryIndex::default();
+        for i in 0..=INDEX_CAPACITY {
+            index.insert(i.to_string(), vec![1.0], format!("message {}", i));
+        }
+
+        assert_eq!(index.entries.len(), INDEX_CAPACITY);
+        assert!(!index.entries.contains_key("0"));
+        assert!(index.entries.contains_key(&INDEX_CAPACITY.to_string()));
+    }
+}
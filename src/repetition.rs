@@ -0,0 +1,56 @@
+// repetition.rs
+
+/// Lowercases a text and collapses its whitespace, so formatting alone never tells two
+/// responses apart.
+fn normalize(text: &str) -> Vec<char> {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .collect()
+}
+
+/// Scores how similar two responses are, by normalized Levenshtein distance.
+///
+/// # Arguments
+/// * `a` - The first response.
+/// * `b` - The second response.
+///
+/// # Returns
+/// * A score between 0 (nothing in common) and 1 (identical once normalized).
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // Two rows of the edit distance matrix are enough
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f32 / longest as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_ignores_case_and_spacing() {
+        assert_eq!(similarity("I love trains.", "i  love\ntrains."), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", ""), 0.0);
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-6);
+        assert!(similarity("Buses are cheaper.", "Bikes need lanes!") < 0.5);
+    }
+}
@@ -0,0 +1,188 @@
+// replay.rs
+
+use crate::message::Message;
+use crate::simulation::{SimulationToUI, UIToSimulation};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Plays a saved transcript back to the UI, standing in for a live simulation.
+///
+/// Messages are delivered on the tick they were sent at. Transcripts saved before messages
+/// carried a tick are replayed one message per tick.
+pub struct Replay {
+    messages: Vec<Message>,
+    ui_tx: Sender<SimulationToUI>,
+    sim_rx: Receiver<UIToSimulation>,
+    tick_duration: Duration,
+    current_tick: u64,
+    next_tick: u64,
+    paused: bool,
+}
+
+impl Replay {
+    /// Creates a replay of the given messages.
+    ///
+    /// # Arguments
+    /// * `messages` - The transcript, in chronological order.
+    /// * `tick_duration` - How long each replayed tick lasts.
+    /// * `ui_tx` - Channel used to send updates to the UI.
+    /// * `sim_rx` - Channel the UI sends commands on.
+    pub fn new(
+        mut messages: Vec<Message>,
+        tick_duration: Duration,
+        ui_tx: Sender<SimulationToUI>,
+        sim_rx: Receiver<UIToSimulation>,
+    ) -> Self {
+        if messages.iter().all(|m| m.tick == 0) {
+            for (i, message) in messages.iter_mut().enumerate() {
+                message.tick = i as u64 + 1;
+            }
+        }
+
+        // Start from the earliest recorded tick, so messages sent at tick 0 are played too
+        let first_tick = messages.iter().map(|m| m.tick).min().unwrap_or(0);
+        Self {
+            messages,
+            ui_tx,
+            sim_rx,
+            tick_duration,
+            current_tick: first_tick,
+            next_tick: first_tick,
+            paused: false,
+        }
+    }
+
+    /// Returns the tick of the last message in the transcript.
+    fn last_tick(&self) -> u64 {
+        self.messages.iter().map(|m| m.tick).max().unwrap_or(0)
+    }
+
+    /// Sends an update to the UI.
+    fn emit(&self, update: SimulationToUI) {
+        let _ = self.ui_tx.send(update);
+    }
+
+    /// Replays the transcript until it is stopped or the UI goes away.
+    pub fn run(&mut self) {
+        self.emit(SimulationToUI::StateUpdate(format!(
+            "Replaying {} messages over {} ticks",
+            self.messages.len(),
+            self.last_tick()
+        )));
+
+        let mut last_tick_time = Instant::now();
+        loop {
+            match self.sim_rx.try_recv() {
+                Ok(UIToSimulation::Stop) | Err(TryRecvError::Disconnected) => break,
+                Ok(command) => self.handle_command(command),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let finished = self.next_tick > self.last_tick();
+            if self.paused || finished || last_tick_time.elapsed() < self.tick_duration {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+            last_tick_time = Instant::now();
+            self.advance();
+        }
+    }
+
+    /// Plays the next tick, delivering the messages sent at it.
+    fn advance(&mut self) {
+        self.current_tick = self.next_tick;
+        self.next_tick += 1;
+        self.emit(SimulationToUI::TickUpdate(self.current_tick));
+        for message in self.messages.iter().filter(|m| m.tick == self.current_tick) {
+            self.emit(SimulationToUI::MessageUpdate(message.clone()));
+        }
+        if self.current_tick >= self.last_tick() {
+            self.emit(SimulationToUI::StateUpdate("Replay finished".to_string()));
+        }
+    }
+
+    /// Applies a command received from the UI.
+    fn handle_command(&mut self, command: UIToSimulation) {
+        match command {
+            UIToSimulation::Start | UIToSimulation::Resume => {
+                self.paused = false;
+                self.emit(SimulationToUI::StateUpdate("Replay resumed".to_string()));
+            }
+            UIToSimulation::Pause => {
+                self.paused = true;
+                self.emit(SimulationToUI::StateUpdate("Replay paused".to_string()));
+            }
+            UIToSimulation::Seek(tick) => self.seek(tick),
+            _ => self.emit(SimulationToUI::StateUpdate(
+                "Command not available during replay".to_string(),
+            )),
+        }
+    }
+
+    /// Jumps to a tick, redrawing every message sent up to it.
+    fn seek(&mut self, tick: u64) {
+        self.current_tick = tick.min(self.last_tick());
+        self.next_tick = self.current_tick + 1;
+        self.emit(SimulationToUI::ClearMessages);
+        for message in self.messages.iter().filter(|m| m.tick <= self.current_tick) {
+            self.emit(SimulationToUI::MessageUpdate(message.clone()));
+        }
+        self.emit(SimulationToUI::TickUpdate(self.current_tick));
+        self.emit(SimulationToUI::StateUpdate(format!(
+            "Replay moved to tick {}",
+            self.current_tick
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_seek_redraws_messages_up_to_tick() {
+        let (ui_tx, ui_rx) = mpsc::channel();
+        let (_sim_tx, sim_rx) = mpsc::channel();
+        let messages = vec![
+            Message::new("Alice", "Bob", "one"),
+            Message::new("Bob", "Alice", "two"),
+            Message::new("Alice", "Bob", "three"),
+        ];
+        let mut replay = Replay::new(messages, Duration::ZERO, ui_tx, sim_rx);
+
+        replay.seek(2);
+
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        assert!(matches!(updates[0], SimulationToUI::ClearMessages));
+        let replayed: Vec<String> = updates
+            .iter()
+            .filter_map(|update| match update {
+                SimulationToUI::MessageUpdate(m) => Some(m.content.text.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(replayed, vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_replay_starts_at_earliest_tick() {
+        let (ui_tx, ui_rx) = mpsc::channel();
+        let (_sim_tx, sim_rx) = mpsc::channel();
+        let mut opening = Message::new("System", "everyone", "opening");
+        opening.tick = 0;
+        let mut reply = Message::new("Alice", "everyone", "reply");
+        reply.tick = 1;
+        let mut replay = Replay::new(vec![opening, reply], Duration::ZERO, ui_tx, sim_rx);
+
+        replay.advance();
+
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        assert!(matches!(updates[0], SimulationToUI::TickUpdate(0)));
+        assert!(matches!(
+            &updates[1],
+            SimulationToUI::MessageUpdate(m) if m.content.text == "opening"
+        ));
+    }
+}
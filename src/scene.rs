@@ -0,0 +1,57 @@
+// scene.rs
+
+use crate::config::{AgentConfig, WorldConfig};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// A complete scenario shared as a single file: its cast, world settings, and topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    /// Name of the scene, shown when it is loaded.
+    #[serde(default)]
+    pub name: String,
+
+    /// Topic the discussion starts on once the scene is loaded, if any.
+    #[serde(default)]
+    pub topic: Option<String>,
+
+    /// Agents of the scene, replacing the current ones when it is loaded.
+    pub agents: Vec<AgentConfig>,
+
+    /// World settings, opening messages included; the current ones are kept when unset.
+    #[serde(default)]
+    pub world: Option<WorldConfig>,
+}
+
+impl Scene {
+    /// Loads a scene from a JSON file.
+    ///
+    /// # Arguments
+    /// * `path` - The scene file to read.
+    ///
+    /// # Returns
+    /// * `Ok(Scene)` if the file is successfully read and parsed.
+    /// * `Err(Error)` if an error occurs.
+    pub fn load(path: &Path) -> Result<Self> {
+        let scene: Scene = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(scene)
+    }
+
+    /// Writes the scene to a JSON file.
+    ///
+    /// # Arguments
+    /// * `path` - The scene file (its directory is created if missing).
+    ///
+    /// # Returns
+    /// * `Ok(())` once written.
+    /// * `Err(Error)` if an error occurs.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+// sentiment.rs
+
+use crate::backend::ModelBackend;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Number of texts whose model-assigned sentiment is remembered.
+const CACHE_CAPACITY: usize = 256;
+
+/// Overall tone of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl Sentiment {
+    /// Numeric tone of the sentiment: 1 for positive, 0 for neutral and -1 for negative.
+    pub fn score(self) -> f32 {
+        match self {
+            Sentiment::Positive => 1.0,
+            Sentiment::Neutral => 0.0,
+            Sentiment::Negative => -1.0,
+        }
+    }
+}
+
+/// Words hinting at a positive tone.
+const POSITIVE_WORDS: [&str; 14] = [
+    "agree",
+    "great",
+    "good",
+    "love",
+    "happy",
+    "glad",
+    "excellent",
+    "wonderful",
+    "thanks",
+    "thank",
+    "yes",
+    "nice",
+    "interesting",
+    "excited",
+];
+
+/// Words hinting at a negative tone.
+const NEGATIVE_WORDS: [&str; 14] = [
+    "disagree", "bad", "hate", "sad", "angry", "wrong", "terrible", "awful", "no", "not",
+    "worried", "afraid", "problem", "annoying",
+];
+
+/// Prompt asking a model to label the sentiment of a message.
+const CLASSIFY_PROMPT: &str = "Classify the sentiment of the following message as exactly one \
+                               word: positive, neutral or negative.\n\nMessage: {text}\n\nSentiment:";
+
+/// Classifies a text by counting positive and negative keywords.
+pub fn classify_keywords(text: &str) -> Sentiment {
+    let mut score = 0;
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+    {
+        if POSITIVE_WORDS.contains(&word.as_str()) {
+            score += 1;
+        } else if NEGATIVE_WORDS.contains(&word.as_str()) {
+            score -= 1;
+        }
+    }
+
+    match score {
+        s if s > 0 => Sentiment::Positive,
+        s if s < 0 => Sentiment::Negative,
+        _ => Sentiment::Neutral,
+    }
+}
+
+/// Classifies a text with a short model prompt.
+///
+/// # Returns
+/// * `Ok(Sentiment)` read from the model's label, neutral when it names none.
+/// * `Err(Error)` if the model could not be reached.
+pub async fn classify_with_model(
+    backend: &dyn ModelBackend,
+    model: &str,
+    text: &str,
+) -> Result<Sentiment> {
+    let prompt = CLASSIFY_PROMPT.replace("{text}", text);
+    let label = backend.generate(model, &prompt, None).await?.to_lowercase();
+    Ok(if label.contains("positive") {
+        Sentiment::Positive
+    } else if label.contains("negative") {
+        Sentiment::Negative
+    } else {
+        Sentiment::Neutral
+    })
+}
+
+/// Sentiments of recently classified texts, forgetting the oldest once full.
+#[derive(Debug, Default)]
+pub struct SentimentCache {
+    sentiments: HashMap<String, Sentiment>,
+    order: VecDeque<String>,
+}
+
+impl SentimentCache {
+    /// Returns the sentiment previously found for a text, if it is still remembered.
+    pub fn get(&self, text: &str) -> Option<Sentiment> {
+        self.sentiments.get(text).copied()
+    }
+
+    /// Remembers the sentiment of a text, evicting the oldest entry past `CACHE_CAPACITY`.
+    pub fn insert(&mut self, text: String, sentiment: Sentiment) {
+        if self.sentiments.insert(text.clone(), sentiment).is_some() {
+            return;
+        }
+        self.order.push_back(text);
+        if self.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sentiments.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_forgets_oldest_texts() {
+        let mut cache = SentimentCache::default();
+        for i in 0..=CACHE_CAPACITY {
+            cache.insert(format!("message {}", i), Sentiment::Positive);
+        }
+        cache.insert("message 1".to_string(), Sentiment::Negative);
+
+        assert_eq!(cache.get("message 0"), None);
+        assert_eq!(cache.get("message 1"), Some(Sentiment::Negative));
+        assert_eq!(cache.sentiments.len(), CACHE_CAPACITY);
+    }
+}
@@ -0,0 +1,252 @@
+// server.rs
+
+use crate::simulation::UIToSimulation;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// Number of events buffered per client before slow clients start skipping events.
+const EVENT_BUFFER: usize = 256;
+
+/// Maximum size of an HTTP request head or body.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// What the HTTP routes need to forward commands to the simulation.
+#[derive(Clone)]
+pub struct CommandRoutes {
+    /// Channel used to send commands to the simulation, like the UI does.
+    pub command_tx: Sender<UIToSimulation>,
+
+    /// Names of the agents that can receive messages, kept current by the simulation as
+    /// agents join and leave.
+    pub agent_names: Arc<RwLock<HashSet<String>>>,
+}
+
+/// Body of `POST /topic`.
+#[derive(Deserialize)]
+struct TopicRequest {
+    topic: String,
+}
+
+/// Body of `POST /message`.
+#[derive(Deserialize)]
+struct MessageRequest {
+    agent: String,
+    content: String,
+}
+
+/// Starts a server on localhost that streams simulation events over WebSocket and accepts
+/// commands over HTTP.
+///
+/// # Arguments
+/// * `runtime` - The runtime the server tasks are spawned on.
+/// * `port` - The local port to listen on.
+/// * `routes` - Where HTTP commands are forwarded.
+///
+/// # Returns
+/// * `Ok(broadcast::Sender<String>)` to publish serialized events to every client.
+/// * `Err(std::io::Error)` if the port could not be bound.
+pub fn spawn_server(
+    runtime: &Runtime,
+    port: u16,
+    routes: CommandRoutes,
+) -> Result<broadcast::Sender<String>, std::io::Error> {
+    let listener = runtime.block_on(TcpListener::bind(("127.0.0.1", port)))?;
+    let (event_tx, _) = broadcast::channel(EVENT_BUFFER);
+
+    let accept_tx = event_tx.clone();
+    runtime.spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let events = accept_tx.subscribe();
+            let routes = routes.clone();
+            tokio::spawn(async move {
+                if is_websocket_upgrade(&stream).await {
+                    stream_events(stream, events).await;
+                } else {
+                    handle_http(stream, &routes).await;
+                }
+            });
+        }
+    });
+
+    Ok(event_tx)
+}
+
+/// Peeks at the request head, without consuming it, to detect a WebSocket upgrade.
+async fn is_websocket_upgrade(stream: &TcpStream) -> bool {
+    let mut buf = vec![0; MAX_REQUEST_SIZE];
+    let Ok(n) = stream.peek(&mut buf).await else {
+        return false;
+    };
+    String::from_utf8_lossy(&buf[..n])
+        .to_ascii_lowercase()
+        .contains("upgrade: websocket")
+}
+
+/// Forwards events to a single WebSocket client until it disconnects.
+async fn stream_events(stream: TcpStream, mut events: broadcast::Receiver<String>) {
+    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(json) => {
+                    if socket.send(WsMessage::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                // The client fell behind; skip the events it missed
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.next() => match incoming {
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Reads a single HTTP request and answers it.
+async fn handle_http(mut stream: TcpStream, routes: &CommandRoutes) {
+    let (status, body) = match read_request(&mut stream).await {
+        Some((method, path, body)) => route(&method, &path, &body, routes),
+        None => ("400 Bad Request", "Malformed request".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+/// Reads the method, path, and body of an HTTP request.
+async fn read_request(stream: &mut TcpStream) -> Option<(String, String, String)> {
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+
+    // Read until the end of the head
+    let head_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if data.len() > MAX_REQUEST_SIZE {
+            return None;
+        }
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_SIZE {
+        return None;
+    }
+
+    // Read the rest of the body
+    while data.len() < head_end + content_length {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    let body = String::from_utf8_lossy(&data[head_end..head_end + content_length]).to_string();
+    Some((method, path, body))
+}
+
+/// Translates an HTTP request into a simulation command.
+///
+/// # Returns
+/// * The HTTP status line and a plain-text response body.
+fn route(method: &str, path: &str, body: &str, routes: &CommandRoutes) -> (&'static str, String) {
+    let command = match (method, path) {
+        ("POST", "/topic") => match serde_json::from_str::<TopicRequest>(body) {
+            Ok(request) => UIToSimulation::SetDiscussionTopic(request.topic),
+            Err(e) => return ("400 Bad Request", format!("Invalid body: {}", e)),
+        },
+        ("POST", "/message") => match serde_json::from_str::<MessageRequest>(body) {
+            Ok(request)
+                if !routes
+                    .agent_names
+                    .read()
+                    .is_ok_and(|names| names.contains(&request.agent)) =>
+            {
+                return (
+                    "404 Not Found",
+                    format!("Agent '{}' not found", request.agent),
+                );
+            }
+            Ok(request) => UIToSimulation::UserMessage(request.agent, request.content),
+            Err(e) => return ("400 Bad Request", format!("Invalid body: {}", e)),
+        },
+        (_, "/topic") | (_, "/message") => {
+            return ("405 Method Not Allowed", "Use POST".to_string());
+        }
+        _ => return ("404 Not Found", format!("No route for {}", path)),
+    };
+
+    match routes.command_tx.send(command) {
+        Ok(()) => ("202 Accepted", "Accepted".to_string()),
+        Err(_) => (
+            "503 Service Unavailable",
+            "Simulation has stopped".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_message_to_unknown_agent_is_not_found() {
+        let (command_tx, command_rx) = mpsc::channel();
+        let routes = CommandRoutes {
+            command_tx,
+            agent_names: Arc::new(RwLock::new(HashSet::from(["Alice".to_string()]))),
+        };
+
+        let body = r#"{"agent": "Bob", "content": "Hello!"}"#;
+        assert_eq!(route("POST", "/message", body, &routes).0, "404 Not Found");
+        assert!(command_rx.try_recv().is_err());
+
+        // Names follow the simulation's agents as they join
+        routes
+            .agent_names
+            .write()
+            .unwrap()
+            .insert("Bob".to_string());
+        assert_eq!(route("POST", "/message", body, &routes).0, "202 Accepted");
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(UIToSimulation::UserMessage(agent, _)) if agent == "Bob"
+        ));
+    }
+}
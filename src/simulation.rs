@@ -1,35 +1,147 @@
 // simulation.rs
-use crate::agent::Agent;
-use crate::config::Config;
+use crate::agent::{estimate_tokens, parse_goal_score, Agent};
+use crate::backend::{MockBackend, ModelBackend, OllamaBackend};
+use crate::cache::ResponseCache;
+use crate::config::{
+    AgentConfig, AgentKind, BackendKind, Config, PostProcessConfig, RoomConfig, SentimentMode,
+    StarterPolicy, WorldConfig,
+};
 use crate::conversation_manager::ConversationManager;
+use crate::error::Error;
+use crate::interest::relevance;
+use crate::memory::{parse_facts, GlobalMemory};
 use crate::message::Message;
-use crate::personality::get_personality_template;
+use crate::personality::{get_personality_template, TRAIT_NAMES};
+use crate::postprocess::postprocess;
+use crate::prompt;
+use crate::recall::MemoryIndex;
+use crate::scene::Scene;
+use crate::sentiment::{classify_keywords, classify_with_model, SentimentCache};
+use crate::server::{spawn_server, CommandRoutes};
 use crate::state::AgentState;
-use chrono::Utc;
-use serde_json::json;
-use std::collections::HashMap;
-use std::sync::mpsc::{Receiver, Sender};
+use crate::tool::{get_tool, latest_query};
+use crate::utils::{
+    load_profiles, save_conversations, save_profiles, save_snapshot, save_transcript, SavedSession,
+};
+use chrono::{Local, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
+use tokio::sync::broadcast;
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
+/// Number of recent messages the moderator reads before intervening
+const MODERATOR_CONTEXT_MESSAGES: usize = 10;
+
+/// Number of recent messages summarized into the shared memory.
+const MEMORY_CONTEXT_MESSAGES: usize = 20;
+
+/// Model used when the configuration does not name one.
+const DEFAULT_MODEL: &str = "llama3.2:latest";
+
+/// How long a health check may take before the server counts as unreachable.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often new responses are written to the response cache file.
+const CACHE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Enum representing commands from the UI to the simulation
 pub enum UIToSimulation {
-    Start,                       // Start the simulation
-    Pause,                       // Pause the simulation
-    Resume,                      // Resume the simulation
-    Stop,                        // Stop the simulation
-    SetDiscussionTopic(String),  // Set the discussion topic
-    UserMessage(String, String), // User sends a message to a specific agent
+    Start,                         // Start the simulation
+    Pause,                         // Pause the simulation
+    Resume,                        // Resume the simulation
+    Step,                          // Run a single tick while paused
+    Stop,                          // Stop the simulation
+    SetDiscussionTopic(String),    // Set the discussion topic
+    UserMessage(String, String),   // User sends a message to a specific agent
+    ExportTranscript(String),      // Export the transcript as Markdown to a path
+    ExportGraph(String),           // Export who talked to whom as DOT or JSON to a path
+    Interrupt,                     // Abort the generation currently in progress
+    RequestSnapshot,               // Ask for a snapshot of the simulation state
+    ListAgents,                    // List every agent with its state
+    RequestStats,                  // Ask for aggregate statistics about the run
+    Seek(u64),                     // Jump to a tick (replay only)
+    Focus(String),                 // Talk 1:1 with an agent while the others pause
+    Unfocus,                       // Return to the group conversation
+    SetTrait(String, String, f32), // Set a personality trait of an agent
+    WorldEvent(String),            // Something happens that every agent notices
+    Inspect(String),               // Ask for the last prompt sent for an agent
+    SelectRoom(Option<String>),    // Choose the room topic changes apply to (None for all)
+    RemoveAgent(String),           // Take an agent out of the simulation
+    Mute(String, bool),            // Silence an agent (true) or let it speak again (false)
+    SaveProfiles(String),          // Save the agents' names and personalities to a path
+    LoadProfiles(String),          // Apply the profiles saved at a path, adding missing agents
+    SaveScene(String),             // Save the cast, world settings and topic to a path
+    LoadScene(String),             // Replace the cast, world settings and topic from a path
 }
 
 /// Enum representing updates from the simulation to the UI
+#[derive(Debug, Clone, Serialize)]
 pub enum SimulationToUI {
-    TickUpdate(u64),                      // Update with the current tick
-    AgentUpdate(String, AgentState, f32), // Update agent's status and energy
-    MessageUpdate(Message),               // New message update
-    StateUpdate(String),                  // Update the simulation's state
+    TickUpdate(u64),                           // Update with the current tick
+    AgentUpdate(String, AgentState, f32, f32), // Update agent's status, energy and mood
+    MessageUpdate(Message),                    // New message update
+    StateUpdate(String),                       // Update the simulation's state
+    AgentJoined(String, String),               // An agent joined, with the model it uses
+    AgentLeft(String),                         // An agent left the simulation
+    Snapshot(SimulationSnapshot),              // Snapshot of the simulation state
+    TopicChanged(String),                      // The discussion topic changed
+    ClearMessages,                             // Clear the message log
+    Prompt(String, String),                    // Last prompt sent for an agent
+    WaitEstimate(String, f64),                 // Recent generation time of an agent, in seconds
+    AgentMuted(String, bool),                  // An agent was muted (true) or unmuted (false)
+}
+
+/// Serializable view of the simulation state at a given tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationSnapshot {
+    /// Current simulation tick.
+    pub tick: u64,
+
+    /// Current discussion topic, if any.
+    pub topic: Option<String>,
+
+    /// State of every agent, sorted by name.
+    pub agents: Vec<AgentSnapshot>,
+}
+
+/// Serializable view of a single agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSnapshot {
+    /// Agent's display name.
+    pub name: String,
+
+    /// Current state of the agent.
+    pub state: AgentState,
+
+    /// Current energy level of the agent.
+    pub energy: f32,
+
+    /// Rolling mood of the agent, from -1 (tense) to 1 (happy).
+    #[serde(default)]
+    pub mood: f32,
+
+    /// Name of the model used by the agent.
+    pub model: String,
+
+    /// Current (x, y) position of the agent.
+    pub position: (i32, i32),
+
+    /// Whether the agent is muted.
+    #[serde(default)]
+    pub muted: bool,
 }
 
 /// Main simulation struct
@@ -39,43 +151,332 @@ pub struct Simulation {
     current_tick: u64,
     running: bool,
     paused: bool,
-    ui_tx: Sender<SimulationToUI>,
+    step_pending: bool,
+    events: EventSink,
     sim_rx: Receiver<UIToSimulation>,
+    // Commands received over HTTP, on a channel of their own so the UI going away is noticed
+    server_rx: Option<Receiver<UIToSimulation>>,
+    // Names the HTTP routes accept messages for, shared with the server
+    agent_names: Arc<RwLock<HashSet<String>>>,
     discussion_topic: Option<String>,
     runtime: Runtime,
+    generation_slots: Arc<Semaphore>,
     conversation_manager: ConversationManager,
+    config: Config,
+    pending_commands: VecDeque<UIToSimulation>,
+    idle_ticks: u64,
+    flooded_ticks: u64,
+    // Agent turns since the last summary, or since the topic was set
+    turns_since_summary: usize,
+    end_reason: Option<String>,
+    rng: StdRng,
+    scheduled_messages: Vec<(u64, Message)>,
+    sentiment_cache: SentimentCache,
+    moderator: Option<Agent>,
+    observer: Option<Agent>,
+    conversations_started: usize,
+    focused_agent: Option<String>,
+    last_prompts: HashMap<String, String>,
+    global_memory: GlobalMemory,
+    last_spoke: HashMap<String, u64>,
+    // Agent that spoke alone on the latest ticks it spoke, with the number of such ticks
+    floor: Option<(String, i32)>,
+    backend: Arc<dyn ModelBackend>,
+    response_cache: Option<ResponseCache>,
+    next_cache_flush: Instant,
+    memory_index: MemoryIndex,
+    throughput: HashMap<String, Throughput>,
+    goal_progress: HashMap<String, u8>,
+    room_topics: HashMap<String, String>,
+    selected_room: Option<String>,
+    backend_unreachable: bool,
+    next_health_check: Instant,
+}
+
+/// Weight of the latest generation time in an agent's rolling latency.
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// Output size and generation time accumulated for throughput statistics.
+#[derive(Debug, Clone, Default)]
+struct Throughput {
+    tokens: usize,
+    seconds: f64,
+    responses: usize,
+    // Exponential moving average of generation times, so the estimate follows a model
+    // warming up or a server getting busier
+    rolling_seconds: f64,
+}
+
+impl Throughput {
+    /// Accounts for one generated response.
+    fn record(&mut self, tokens: usize, elapsed: Duration) {
+        let elapsed = elapsed.as_secs_f64();
+        self.rolling_seconds = if self.responses == 0 {
+            elapsed
+        } else {
+            self.rolling_seconds + LATENCY_SMOOTHING * (elapsed - self.rolling_seconds)
+        };
+        self.tokens += tokens;
+        self.seconds += elapsed;
+        self.responses += 1;
+    }
+
+    /// Adds another tally to this one.
+    fn add(&mut self, other: &Throughput) {
+        self.tokens += other.tokens;
+        self.seconds += other.seconds;
+        self.responses += other.responses;
+    }
+
+    /// Describes the average throughput, in estimated tokens per second.
+    fn describe(&self) -> String {
+        let rate = if self.seconds > 0.0 {
+            format!("{:.1} tokens/sec", self.tokens as f64 / self.seconds)
+        } else {
+            "n/a".to_string()
+        };
+        format!("{} over {} responses", rate, self.responses)
+    }
+}
+
+/// Sender of the messages describing world events.
+pub const WORLD_SENDER: &str = "World";
+
+/// Delivers simulation updates to the UI, to event stream clients, and to the event log
+struct EventSink {
+    ui_tx: Sender<SimulationToUI>,
+    event_tx: Option<broadcast::Sender<String>>,
+    event_log: Option<RefCell<BufWriter<File>>>,
+    log_tick: Cell<u64>,
+}
+
+impl EventSink {
+    /// Sends an update to the UI, to any connected event stream clients, and to the event log.
+    fn emit(&self, update: SimulationToUI) {
+        if let Some(event_tx) = &self.event_tx {
+            if let Ok(json) = serde_json::to_string(&update) {
+                // Sending only fails when no client is connected
+                let _ = event_tx.send(json);
+            }
+        }
+        if self.event_log.is_some() {
+            self.log(&update);
+        }
+        let _ = self.ui_tx.send(update);
+    }
+
+    /// Appends an update to the event log, flushing whenever a new tick begins.
+    fn log(&self, update: &SimulationToUI) {
+        let Some(event_log) = &self.event_log else {
+            return;
+        };
+        if let SimulationToUI::TickUpdate(tick) = update {
+            self.flush_log();
+            self.log_tick.set(*tick);
+        }
+        let entry = serde_json::json!({ "tick": self.log_tick.get(), "event": update });
+        let _ = writeln!(event_log.borrow_mut(), "{}", entry);
+    }
+
+    /// Writes buffered log entries to the event log file.
+    fn flush_log(&self) {
+        if let Some(event_log) = &self.event_log {
+            let _ = event_log.borrow_mut().flush();
+        }
+    }
+}
+
+/// Creates an agent from its configuration and the settings shared by every agent.
+///
+/// # Arguments
+/// * `config` - The simulation configuration.
+/// * `agent_config` - The configuration of the agent.
+/// * `model` - The model used unless the agent names its own.
+/// * `backend` - The backend serving the models.
+/// * `response_cache` - The cache shared by every agent, if enabled.
+/// * `memory` - The facts shared by every agent.
+///
+/// # Returns
+/// * The new agent.
+fn build_agent(
+    config: &Config,
+    agent_config: &AgentConfig,
+    model: &str,
+    backend: &Arc<dyn ModelBackend>,
+    response_cache: Option<ResponseCache>,
+    memory: &GlobalMemory,
+) -> Agent {
+    let personality = agent_config
+        .personality
+        .clone()
+        .unwrap_or_else(|| get_personality_template(&agent_config.personality_template));
+
+    let mut agent = Agent::new(
+        agent_config.name.clone(),
+        personality,
+        agent_config.initial_energy,
+        agent_config.initial_position,
+        model.to_string(),
+        backend.clone(),
+    );
+
+    // Per-agent model overrides the global one
+    if let Some(model) = &agent_config.model {
+        agent.set_model(model.clone());
+    }
+    agent.set_prompt_template(config.prompt_template.clone());
+    agent.opening_prompt_template = config.opening_prompt_template.clone();
+    agent.prompt_prefix = config.global_prompt_prefix.clone();
+    agent.prompt_suffix = config.global_prompt_suffix.clone();
+    agent.response_cache = response_cache;
+    agent.verbosity = agent_config.verbosity;
+    agent.max_tokens = agent_config
+        .max_tokens
+        .or(config.max_tokens)
+        .or(agent_config.verbosity.default_max_tokens());
+    agent.context_tokens = agent_config.context_tokens.or(config.context_tokens);
+    agent.memory = memory.clone();
+    agent.interests = agent_config.interests.clone();
+    agent.goal = agent_config.goal.clone();
+    agent.expertise = agent_config.expertise.clone();
+    agent.language = agent_config.language.clone();
+    if agent_config.kind == AgentKind::Tool {
+        // Tools answer every query addressed to them
+        agent.tool = agent_config.tool.as_deref().and_then(get_tool);
+        agent.talkativeness = 1.0;
+    }
+    if let Some(talkativeness) = agent_config.talkativeness {
+        agent.talkativeness = talkativeness.clamp(0.0, 1.0);
+    }
+    agent
+}
+
+/// Opens the event log for appending, creating it if needed.
+fn open_event_log(path: &str) -> std::io::Result<BufWriter<File>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(BufWriter::new(file))
 }
 
 impl Simulation {
     /// Initializes a new simulation with the given configuration and channels.
     pub fn new(
-        config: Config,
+        mut config: Config,
         ui_tx: Sender<SimulationToUI>,
         sim_rx: Receiver<UIToSimulation>,
     ) -> Self {
         // Create a Tokio runtime for async calls to Ollama
         let runtime = Runtime::new().expect("Failed to create Tokio runtime");
 
+        // Seed the RNG from the config so runs can be reproduced
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        // Generated agents join the listed ones, as if they had been configured explicitly
+        if let Some(procedural) = &config.procedural_agents {
+            let generated = procedural.generate(&config.world, &mut rng);
+            config.agents.extend(generated);
+        }
+
+        // Facts shared by every agent, read in their prompts
+        let global_memory = GlobalMemory::default();
+
         // Initialize agents based on configuration
         let mut agents = HashMap::new();
         let ollama_model_name = config.ollama_model.clone().unwrap_or_else(|| {
             eprintln!("Warning: Ollama model not found in config, using default.");
-            "llama3.2:latest".to_string() // Fallback to a default if not in config
+            DEFAULT_MODEL.to_string() // Fallback to a default if not in config
+        });
+
+        let backend: Arc<dyn ModelBackend> = match config.backend {
+            BackendKind::Ollama => Arc::new(OllamaBackend::default()),
+            BackendKind::Mock => Arc::new(MockBackend::new(config.mock_responses.clone())),
+        };
+
+        // A cache file that cannot be read is reported and left untouched, and the run uses
+        // an in-memory cache instead
+        let response_cache = config.cache.as_ref().map(|cache_config| {
+            ResponseCache::open(cache_config).unwrap_or_else(|e| {
+                let _ = ui_tx.send(SimulationToUI::StateUpdate(format!(
+                    "Failed to load the response cache: {}",
+                    e
+                )));
+                ResponseCache::default()
+            })
         });
 
         for agent_config in &config.agents {
             let id = Uuid::new_v4().to_string();
-            let personality = get_personality_template(&agent_config.personality_template);
+            let agent = build_agent(
+                &config,
+                agent_config,
+                &ollama_model_name,
+                &backend,
+                response_cache.clone(),
+                &global_memory,
+            );
+            agents.insert(id, agent);
+        }
+
+        // The moderator is kept apart from the agents so it never takes a regular turn
+        let moderator = config.moderator.as_ref().map(|moderator_config| {
+            let mut moderator = Agent::new(
+                moderator_config.name.clone(),
+                get_personality_template("neutral"),
+                100.0,
+                (0, 0),
+                moderator_config
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| ollama_model_name.clone()),
+                backend.clone(),
+            );
+            moderator.max_tokens = config.max_tokens;
+            moderator
+        });
 
-            let agent = Agent::new(
-                agent_config.name.clone(),
-                personality,
-                agent_config.initial_energy,
-                ollama_model_name.clone(), // Pass the model name from config
+        // Likewise for the observer, which is never heard by the agents either
+        let observer = config.observer.as_ref().map(|observer_config| {
+            let mut observer = Agent::new(
+                observer_config.name.clone(),
+                get_personality_template("neutral"),
+                100.0,
+                (0, 0),
+                observer_config
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| ollama_model_name.clone()),
+                backend.clone(),
             );
+            observer.max_tokens = config.max_tokens;
+            observer
+        });
 
-            agents.insert(id, agent);
+        let mut conversation_manager = ConversationManager::new(config.world.recipient_preference);
+        for agent in agents.values() {
+            conversation_manager.add_participant(&agent.name);
         }
+        let agent_names = Arc::new(RwLock::new(
+            agents.values().map(|agent| agent.name.clone()).collect(),
+        ));
+
+        // A log that cannot be opened is reported, and the simulation runs without it
+        let event_log = config
+            .event_log
+            .as_deref()
+            .and_then(|path| match open_event_log(path) {
+                Ok(writer) => Some(RefCell::new(writer)),
+                Err(e) => {
+                    let _ = ui_tx.send(SimulationToUI::StateUpdate(format!(
+                        "Failed to open event log {}: {}",
+                        path, e
+                    )));
+                    None
+                }
+            });
+
+        let room_topics = room_topics(&config.rooms);
 
         Self {
             agents,
@@ -83,42 +484,108 @@ impl Simulation {
             current_tick: 0,
             running: false,
             paused: false,
-            ui_tx,
+            step_pending: false,
+            events: EventSink {
+                ui_tx,
+                event_tx: None,
+                event_log,
+                log_tick: Cell::new(0),
+            },
             sim_rx,
+            server_rx: None,
+            agent_names,
             discussion_topic: None,
             runtime,
-            conversation_manager: ConversationManager::new(),
+            generation_slots: Arc::new(Semaphore::new(config.max_concurrent_requests.max(1))),
+            conversation_manager,
+            config,
+            pending_commands: VecDeque::new(),
+            idle_ticks: 0,
+            flooded_ticks: 0,
+            turns_since_summary: 0,
+            end_reason: None,
+            rng,
+            scheduled_messages: Vec::new(),
+            sentiment_cache: SentimentCache::default(),
+            moderator,
+            observer,
+            conversations_started: 0,
+            focused_agent: None,
+            last_prompts: HashMap::new(),
+            global_memory,
+            last_spoke: HashMap::new(),
+            floor: None,
+            backend,
+            response_cache,
+            next_cache_flush: Instant::now() + CACHE_FLUSH_INTERVAL,
+            memory_index: MemoryIndex::default(),
+            throughput: HashMap::new(),
+            goal_progress: HashMap::new(),
+            room_topics,
+            selected_room: None,
+            backend_unreachable: false,
+            next_health_check: Instant::now(),
+        }
+    }
+
+    /// Starts the WebSocket/HTTP server if a port is configured.
+    ///
+    /// Commands received over HTTP are applied like the UI's; messages are only accepted for
+    /// the agents in the simulation when the request arrives.
+    pub fn start_server(&mut self) {
+        let Some(port) = self.config.ws_port else {
+            return;
+        };
+
+        let (command_tx, server_rx) = mpsc::channel();
+        let routes = CommandRoutes {
+            command_tx,
+            agent_names: self.agent_names.clone(),
+        };
+        match spawn_server(&self.runtime, port, routes) {
+            Ok(event_tx) => {
+                self.events.event_tx = Some(event_tx);
+                self.server_rx = Some(server_rx);
+            }
+            Err(e) => self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Failed to start server on port {}: {}",
+                port, e
+            ))),
         }
     }
 
     /// Starts the simulation loop, listening for commands and processing the simulation.
     pub fn run(&mut self) {
         self.running = true;
+
+        // Let the UI know which model each agent uses
+        for agent in self.agents.values().chain(self.moderator.as_ref()) {
+            self.events.emit(SimulationToUI::AgentJoined(
+                agent.name.clone(),
+                agent.ollama_model.clone(),
+            ));
+        }
+
         // Wait for the start signal
-        while let Ok(command) = self.sim_rx.recv() {
+        loop {
+            let command = match self.try_recv_command() {
+                Ok(command) => command,
+                Err(TryRecvError::Empty) => {
+                    thread::sleep(Duration::from_millis(10));
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => break,
+            };
             match command {
                 UIToSimulation::Start => {
                     self.running = true;
                     break;
                 }
-                UIToSimulation::SetDiscussionTopic(topic) => {
-                    self.discussion_topic = Some(topic.clone());
-                    // Send a topic update to the UI
-                    let _ = self.ui_tx.send(SimulationToUI::StateUpdate(format!(
-                        "Discussion topic set: {}",
-                        topic
-                    )));
-                    // Start conversation immediately if the topic is set
-                    self.start_conversation(&topic);
-                }
-                UIToSimulation::UserMessage(recipient, content) => {
-                    self.handle_user_message(&recipient, &content);
-                }
                 UIToSimulation::Stop => {
                     self.running = false;
                     break;
                 }
-                _ => continue,
+                command => self.handle_command(command),
             }
         }
 
@@ -127,295 +594,3893 @@ impl Simulation {
         let tick_duration = Duration::from_millis(1000 / 10); // 10 ticks per second
 
         while self.running {
-            // Check UI commands
-            if let Ok(command) = self.sim_rx.try_recv() {
-                match command {
-                    UIToSimulation::Pause => self.paused = true,
-                    UIToSimulation::Resume => self.paused = false,
-                    UIToSimulation::Stop => self.running = false,
-                    UIToSimulation::SetDiscussionTopic(topic) => {
-                        self.discussion_topic = Some(topic.clone());
-                        self.start_conversation(&topic);
-                    }
-                    _ => {}
+            // Apply commands received while agents were generating
+            while let Some(command) = self.pending_commands.pop_front() {
+                if !self.running {
+                    break;
+                }
+                self.handle_command(command);
+            }
+
+            // Check UI commands, stopping if the UI has gone away
+            match self.try_recv_command() {
+                Ok(command) => self.handle_command(command),
+                Err(TryRecvError::Disconnected) => {
+                    self.running = false;
+                    continue;
                 }
+                Err(TryRecvError::Empty) => {}
             }
 
-            // If paused, wait
-            if self.paused {
+            // If paused, wait, unless a single step was requested
+            if self.paused && !self.step_pending {
                 thread::sleep(Duration::from_millis(100));
                 continue;
             }
 
-            // Check if it's time for a tick
+            // Check if it's time for a tick; a step runs right away
             let now = Instant::now();
-            if now.duration_since(last_tick_time) >= tick_duration {
+            if self.step_pending || now.duration_since(last_tick_time) >= tick_duration {
+                // Hold ticks, and with them generations, while the model server is unreachable
+                if !self.backend_ready() {
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+                self.step_pending = false;
                 self.tick();
                 last_tick_time = now;
+
+                if let Some(reason) = self.check_end_conditions() {
+                    self.end_reason = Some(reason);
+                    self.running = false;
+                }
+                if now >= self.next_cache_flush {
+                    self.flush_response_cache();
+                }
             } else {
                 // Wait a bit to avoid overloading the CPU
                 thread::sleep(Duration::from_millis(10));
             }
         }
 
+        // Work queued before the stop request is dropped, but replies already written are
+        // delivered and kept in the history
+        self.pending_commands.clear();
+        for message in self.release_scheduled(|_| true) {
+            self.record_message(&message);
+        }
+
         // Send a final state update to the UI
-        let _ = self.ui_tx.send(SimulationToUI::StateUpdate(
-            "Simulation stopped".to_string(),
-        ));
+        let mut status = match &self.end_reason {
+            Some(reason) => format!("Simulation ended: {}", reason),
+            None => "Simulation stopped".to_string(),
+        };
+        if self.config.autosave {
+            match self.save_session() {
+                Ok(path) => status.push_str(&format!(", session saved to {}", path.display())),
+                Err(e) => status.push_str(&format!(", failed to save session: {}", e)),
+            }
+        }
+        self.flush_response_cache();
+        self.events.emit(SimulationToUI::StateUpdate(status));
+        self.events.flush_log();
     }
 
-    /// Executes a tick in the simulation, updating agent states, messages, and energy levels.
-    fn tick(&mut self) {
-        self.current_tick += 1;
-        let _ = self
-            .ui_tx
-            .send(SimulationToUI::TickUpdate(self.current_tick));
+    /// Writes the responses cached since the last write to the cache file.
+    ///
+    /// A file that cannot be written is reported, and only costs the reuse on the next run.
+    fn flush_response_cache(&mut self) {
+        self.next_cache_flush = Instant::now() + CACHE_FLUSH_INTERVAL;
+        if let Some(Err(e)) = self.response_cache.as_ref().map(ResponseCache::flush) {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Failed to save the response cache: {}",
+                e
+            )));
+        }
+    }
 
-        // 1. Collect all received messages during this tick
-        for message in &self.messages {
-            // Add to global conversation history
-            self.conversation_manager.add_message(message.clone());
+    /// Receives the next command from the UI or, when the UI has none, from the HTTP server.
+    ///
+    /// # Returns
+    /// * `Err(TryRecvError::Disconnected)` only when the UI has gone away; the server stopping
+    ///   leaves the simulation running.
+    fn try_recv_command(&self) -> Result<UIToSimulation, TryRecvError> {
+        match self.sim_rx.try_recv() {
+            Err(TryRecvError::Empty) => self
+                .server_rx
+                .as_ref()
+                .and_then(|server_rx| server_rx.try_recv().ok())
+                .ok_or(TryRecvError::Empty),
+            result => result,
+        }
+    }
 
-            // For each agent (except the sender), collect what it "hears"
-            for (_, agent) in self.agents.iter_mut() {
-                if agent.name != message.sender {
-                    // The agent hears this message
-                    agent.next_prompt.push_str(&format!(
-                        "[{}→{}]: {}\n",
-                        message.sender,
-                        message.recipient,
-                        message.content.to_string().trim_matches('"')
-                    ));
-                }
+    /// Returns the reason the simulation should end, if any end condition is met.
+    fn check_end_conditions(&self) -> Option<String> {
+        let world = &self.config.world;
+        if world.max_ticks.is_some_and(|max| self.current_tick >= max) {
+            return Some(format!("reached {} ticks", self.current_tick));
+        }
+        if let Some(max) = world.max_messages {
+            let count = self.conversation_manager.message_count() + self.messages.len();
+            if count >= max {
+                return Some(format!("reached {} messages", count));
             }
-
-            // Notify the UI about the new message
-            let _ = self
-                .ui_tx
-                .send(SimulationToUI::MessageUpdate(message.clone()));
         }
+        if world
+            .max_idle_ticks
+            .is_some_and(|max| self.idle_ticks >= max)
+        {
+            return Some(format!("all agents idle for {} ticks", self.idle_ticks));
+        }
+        None
+    }
 
-        // 2. Make agents respond to the messages they heard
-        let mut new_messages = Vec::new();
-
-        for (_, agent) in self.agents.iter_mut() {
-            if !agent.next_prompt.is_empty() {
-                // The agent has heard messages and will respond
-                agent.state = AgentState::Thinking;
-
-                // Notify the UI about the state change
-                let _ = self.ui_tx.send(SimulationToUI::AgentUpdate(
-                    agent.name.clone(),
-                    agent.state.clone(),
-                    agent.energy,
-                ));
+    /// Saves every agent's conversation history and the transcript to the save directory,
+    /// and refreshes the snapshot.
+    ///
+    /// # Returns
+    /// * The path of the session file written.
+    fn save_session(&self) -> crate::error::Result<PathBuf> {
+        let mut agents: Vec<Agent> = self.agents.values().cloned().collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
 
-                // Determine the recipient (for now, we respond to the last message)
-                let recipient = if agent.next_prompt.contains("→") {
-                    agent
-                        .next_prompt
-                        .lines()
-                        .last()
-                        .and_then(|line| line.split('→').next())
-                        .unwrap_or("everyone")
-                        .trim_start_matches('[')
-                        .to_string()
-                } else {
-                    "everyone".to_string()
-                };
+        let dir = Path::new(&self.config.save_dir);
+        let path = save_conversations(&agents, &self.global_memory.facts(), dir)?;
+        save_transcript(&self.conversation_manager.messages(), dir)?;
+        self.save_snapshot()?;
+        Ok(path)
+    }
 
-                // Generate a response
-                if let Ok(response_text) = self
-                    .runtime
-                    .block_on(async { agent.generate_response_from_prompt().await })
-                {
-                    // Create a response message
-                    let response_message = Message {
-                        id: Uuid::new_v4().to_string(),
-                        timestamp: Utc::now(),
-                        sender: agent.name.clone(),
-                        recipient,
-                        content: json!(response_text),
-                    };
-
-                    // Add to the list of new messages
-                    new_messages.push(response_message.clone());
-
-                    // Notify the UI about the response
-                    let _ = self
-                        .ui_tx
-                        .send(SimulationToUI::MessageUpdate(response_message));
-
-                    // Update agent state
-                    agent.state = AgentState::Speaking;
-                    agent.energy -= 1.0;
-                }
+    /// Overwrites the snapshot file with the current agents, shared memory, and delayed
+    /// replies.
+    fn save_snapshot(&self) -> crate::error::Result<()> {
+        let mut agents: Vec<Agent> = self.agents.values().cloned().collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
 
-                // Reset the prompt for the next tick
-                agent.next_prompt.clear();
-            }
-        }
+        // Delays are saved relative to now, since a resumed session counts ticks from zero
+        let scheduled: Vec<(u64, Message)> = self
+            .scheduled_messages
+            .iter()
+            .map(|(due_tick, message)| {
+                (due_tick.saturating_sub(self.current_tick), message.clone())
+            })
+            .collect();
 
-        // Clear current messages and add new ones
-        self.messages.clear();
-        self.messages.extend(new_messages);
+        let path = Path::new(&self.config.snapshot.path);
+        save_snapshot(&agents, &self.global_memory.facts(), &scheduled, path)
+    }
 
-        // Update agents' energy levels
-        for (_, agent) in self.agents.iter_mut() {
-            agent.energy += 0.1;
-            if agent.energy > 100.0 {
-                agent.energy = 100.0;
-            }
+    /// Restores the agents and shared memory of a saved session.
+    ///
+    /// Agents are matched by name; saved agents missing from the configuration are skipped,
+    /// and configured agents missing from the session start fresh.
+    ///
+    /// # Arguments
+    /// * `session` - The session to restore, e.g. loaded from the snapshot.
+    ///
+    /// # Returns
+    /// * The number of agents restored.
+    pub fn restore(&mut self, session: &SavedSession) -> usize {
+        let mut restored = 0;
+        for saved in &session.agents {
+            let Some(agent) = self.agents.values_mut().find(|a| a.name == saved.name) else {
+                continue;
+            };
+            agent.energy = saved.energy.clamp(0.0, self.config.world.energy_cap);
+            agent.mood = saved.mood;
+            agent.personality = saved.personality.clone();
+            agent.conversation_history = saved.conversation_history.clone();
+            restored += 1;
 
-            let _ = self.ui_tx.send(SimulationToUI::AgentUpdate(
+            self.events.emit(SimulationToUI::AgentUpdate(
                 agent.name.clone(),
                 agent.state.clone(),
                 agent.energy,
+                agent.mood,
             ));
         }
+        self.global_memory.replace(session.global_memory.clone());
+        for (delay, message) in &session.scheduled_messages {
+            self.scheduled_messages
+                .push((self.current_tick + delay, message.clone()));
+        }
+
+        self.events.emit(SimulationToUI::StateUpdate(format!(
+            "Restored {} agent{} from the session saved {}",
+            restored,
+            if restored == 1 { "" } else { "s" },
+            session
+                .saved_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+        )));
+        restored
     }
 
-    /// Starts the conversation with a given topic.
-    fn start_conversation(&mut self, topic: &str) {
-        // Choose an agent to start the conversation
-        if let Some((_, starter)) = self.agents.iter().next() {
-            // Create an initial message
-            let initial_message = Message {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                sender: "System".to_string(),
-                recipient: starter.name.clone(),
-                content: json!(format!("Let's talk about {}. What do you think?", topic)),
-            };
+    /// Returns a serializable snapshot of the current simulation state.
+    pub fn snapshot(&self) -> SimulationSnapshot {
+        let mut agents: Vec<AgentSnapshot> = self
+            .agents
+            .values()
+            .map(|agent| AgentSnapshot {
+                name: agent.name.clone(),
+                state: agent.state.clone(),
+                energy: agent.energy,
+                mood: agent.mood,
+                model: agent.ollama_model.clone(),
+                position: agent.position,
+                muted: agent.muted,
+            })
+            .collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
 
-            // Add the message to the list
-            self.messages.push(initial_message.clone());
+        SimulationSnapshot {
+            tick: self.current_tick,
+            topic: self.discussion_topic.clone(),
+            agents,
+        }
+    }
 
-            // Send the message to the UI
-            let _ = self
-                .ui_tx
-                .send(SimulationToUI::MessageUpdate(initial_message));
-            let _ = self.ui_tx.send(SimulationToUI::StateUpdate(format!(
-                "Conversation started on topic: {}",
-                topic
-            )));
+    /// Applies a command received from the UI.
+    fn handle_command(&mut self, command: UIToSimulation) {
+        match command {
+            // Interrupts only matter while a generation is in flight
+            UIToSimulation::Start | UIToSimulation::Interrupt => {}
+            UIToSimulation::Pause => self.paused = true,
+            UIToSimulation::Resume => self.paused = false,
+            UIToSimulation::Step if self.paused => self.step_pending = true,
+            UIToSimulation::Step => {
+                self.events.emit(SimulationToUI::StateUpdate(
+                    "Step is only available while paused".to_string(),
+                ));
+            }
+            UIToSimulation::Stop => self.running = false,
+            UIToSimulation::SetDiscussionTopic(topic) if self.selected_room.is_some() => {
+                let room = self.selected_room.clone().unwrap_or_default();
+                self.set_room_topic(&room, &topic);
+            }
+            UIToSimulation::SetDiscussionTopic(topic) => {
+                // Agents already discussing something are told about the shift
+                self.turns_since_summary = 0;
+                if let Some(previous) = self.discussion_topic.replace(topic.clone()) {
+                    let message = Message::new(
+                        "System",
+                        "everyone",
+                        format!(
+                            "The discussion topic has changed from {} to {}.",
+                            previous, topic
+                        ),
+                    )
+                    .at_tick(self.current_tick);
+                    self.events
+                        .emit(SimulationToUI::MessageUpdate(message.clone()));
+                    self.messages.push(message);
+                }
+                self.events
+                    .emit(SimulationToUI::TopicChanged(topic.clone()));
+                // Send a topic update to the UI
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Discussion topic set: {}",
+                    topic
+                )));
+                // Start conversation immediately if the topic is set
+                self.start_conversation(&topic);
+            }
+            UIToSimulation::UserMessage(recipient, content) => {
+                self.handle_user_message(&recipient, &content);
+            }
+            UIToSimulation::RequestSnapshot => {
+                self.events.emit(SimulationToUI::Snapshot(self.snapshot()));
+            }
+            UIToSimulation::ListAgents => self.list_agents(),
+            UIToSimulation::Focus(name) => {
+                if !self.agents.values().any(|a| a.name == name) {
+                    self.events.emit(SimulationToUI::StateUpdate(format!(
+                        "Agent '{}' not found",
+                        name
+                    )));
+                    return;
+                }
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Talking 1:1 with {}",
+                    name
+                )));
+                self.focused_agent = Some(name);
+            }
+            UIToSimulation::WorldEvent(event) => self.world_event(&event),
+            UIToSimulation::Inspect(name) => self.inspect(&name),
+            UIToSimulation::RemoveAgent(name) => self.remove_agent(&name),
+            UIToSimulation::Mute(name, muted) => self.set_muted(&name, muted),
+            UIToSimulation::SelectRoom(room) => {
+                let status = match &room {
+                    Some(room) => format!("Topic changes now apply to room {}", room),
+                    None => "Topic changes now apply to every room".to_string(),
+                };
+                self.selected_room = room;
+                self.events.emit(SimulationToUI::StateUpdate(status));
+            }
+            UIToSimulation::SetTrait(name, trait_name, value) => {
+                self.set_trait(&name, &trait_name, value);
+            }
+            UIToSimulation::Unfocus => {
+                self.focused_agent = None;
+                self.events.emit(SimulationToUI::StateUpdate(
+                    "Back to the group conversation".to_string(),
+                ));
+            }
+            UIToSimulation::Seek(_) => {
+                self.events.emit(SimulationToUI::StateUpdate(
+                    "Seeking is only available during replay".to_string(),
+                ));
+            }
+            UIToSimulation::RequestStats => {
+                self.events.emit(SimulationToUI::MessageUpdate(Message::new(
+                    "System",
+                    "User",
+                    self.stats(),
+                )));
+            }
+            UIToSimulation::ExportGraph(path) => {
+                let graph = self.conversation_manager.interaction_graph();
+                let contents = if path.ends_with(".json") {
+                    serde_json::to_string_pretty(&graph).map_err(Error::from)
+                } else {
+                    Ok(graph.to_dot())
+                };
+                let status = match contents
+                    .and_then(|contents| std::fs::write(&path, contents).map_err(Error::from))
+                {
+                    Ok(()) => format!("Conversation graph exported to {}", path),
+                    Err(e) => format!("Failed to export conversation graph: {}", e),
+                };
+                self.events.emit(SimulationToUI::StateUpdate(status));
+            }
+            UIToSimulation::SaveProfiles(path) => {
+                let mut agents: Vec<Agent> = self.agents.values().cloned().collect();
+                agents.sort_by(|a, b| a.name.cmp(&b.name));
+                let status = match save_profiles(&agents, Path::new(&path)) {
+                    Ok(()) => format!("Saved {} profiles to {}", agents.len(), path),
+                    Err(e) => format!("Failed to save profiles: {}", e),
+                };
+                self.events.emit(SimulationToUI::StateUpdate(status));
+            }
+            UIToSimulation::LoadProfiles(path) => self.load_profiles(&path),
+            UIToSimulation::SaveScene(path) => {
+                let path = Path::new(&path);
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let status = match self.scene(&name).save(path) {
+                    Ok(()) => format!("Scene saved to {}", path.display()),
+                    Err(e) => format!("Failed to save scene: {}", e),
+                };
+                self.events.emit(SimulationToUI::StateUpdate(status));
+            }
+            UIToSimulation::LoadScene(path) => self.load_scene(&path),
+            UIToSimulation::ExportTranscript(path) => {
+                let status = match std::fs::write(&path, self.conversation_manager.to_markdown()) {
+                    Ok(()) => format!("Transcript exported to {}", path),
+                    Err(e) => format!("Failed to export transcript: {}", e),
+                };
+                self.events.emit(SimulationToUI::StateUpdate(status));
+            }
         }
     }
 
-    /// Handles user messages and passes them to the relevant agent.
-    fn handle_user_message(&mut self, recipient: &str, content: &str) {
-        // Create a user message
-        let user_message = Message {
-            id: Uuid::new_v4().to_string(),
-            timestamp: Utc::now(),
-            sender: "User".to_string(),
-            recipient: recipient.to_string(),
-            content: json!(content),
-        };
+    /// Executes a tick in the simulation, updating agent states, messages, and energy levels.
+    fn tick(&mut self) {
+        self.current_tick += 1;
+        self.events
+            .emit(SimulationToUI::TickUpdate(self.current_tick));
 
-        // Notify the UI about the user message
-        let _ = self
-            .ui_tx
-            .send(SimulationToUI::MessageUpdate(user_message.clone()));
+        // 1. Collect all received messages during this tick, oldest first so agents hear
+        // them in the order they were sent
+        let mut delivered = std::mem::take(&mut self.messages);
+        delivered.sort_by_key(|message| message.timestamp);
+        for message in &delivered {
+            // Add to global conversation history
+            self.record_message(message);
 
-        // Add to the conversation history
-        self.conversation_manager.add_message(user_message.clone());
+            // The tone of the message sways the mood of its sender and of everyone hearing it
+            let sentiment = message
+                .sentiment
+                .unwrap_or_else(|| classify_keywords(&message.content.text));
 
-        // Add the message to the recipient agent's next prompt for immediate processing
-        if let Some(agent) = self.agents.values_mut().find(|a| a.name == recipient) {
-            agent
-                .next_prompt
-                .push_str(&format!("[User→{}]: {}\n", recipient, content));
+            // For each agent (except the sender), collect what it "hears"
+            for (_, agent) in self.agents.iter_mut() {
+                let room = message.room.as_deref();
+                if agent.name == message.sender {
+                    agent.update_mood(sentiment);
+                } else if can_hear(&self.config.rooms, &agent.name, room) {
+                    agent.update_mood(sentiment);
+                    // The agent hears this message, and answers in the room it was sent in
+                    agent.next_prompt.push_str(&format!(
+                        "[{}→{}]: {}\n",
+                        message.sender, message.recipient, message.content
+                    ));
+                    if message.room.is_some() {
+                        agent.room = message.room.clone();
+                    }
+                }
+            }
+        }
+        self.messages = delivered;
 
-            // Process the response immediately
-            agent.state = AgentState::Thinking;
-            let _ = self.ui_tx.send(SimulationToUI::AgentUpdate(
-                agent.name.clone(),
-                agent.state.clone(),
-                agent.energy,
-            ));
+        // 2. Make agents respond to the messages they heard
+        let mut new_messages = Vec::new();
+        let mut spoke = false;
+        let mut speakers = Vec::new();
+        let mut turns = Vec::new();
+        let mut generations = Vec::new();
 
-            // Store the agent's name for later use
-            let agent_name = agent.name.clone();
+        // Agents take their turns in name order, so runs are reproducible
+        let mut ids: Vec<(String, String)> = self
+            .agents
+            .iter()
+            .map(|(id, agent)| (agent.name.clone(), id.clone()))
+            .collect();
+        ids.sort();
+        for (_, id) in ids {
+            // A stop request during this tick means no further agent gets to speak, and in
+            // focus mode the group waits while the user talks with a single agent
+            if !self.running || self.focused_agent.is_some() {
+                break;
+            }
 
-            // Generate a response
-            let response_result = self
-                .runtime
-                .block_on(async { agent.generate_response_from_prompt().await });
+            // Experts on the topic speak up more readily, and agents holding the floor less so
+            let streak = self.monologue_streak(&self.agents[&id].name);
+            let speaking_probability = {
+                let agent = &self.agents[&id];
+                agent.speaking_probability(self.topic_for(agent))
+                    * self.config.world.monologue.probability_decay.powi(streak)
+            };
 
-            // Release the agent lock once we're done
-            if let Ok(response_text) = response_result {
-                let response_message = Message {
-                    id: Uuid::new_v4().to_string(),
-                    timestamp: Utc::now(),
-                    sender: agent_name.clone(),
-                    recipient: "User".to_string(),
-                    content: json!(response_text),
-                };
+            let agent = self.agents.get_mut(&id).unwrap();
+            if agent.next_prompt.is_empty() {
+                continue;
+            }
 
-                // Notify the UI about the agent's response
-                let _ = self
-                    .ui_tx
-                    .send(SimulationToUI::MessageUpdate(response_message));
-
-                // Update the state of other agents
-                for (_, other_agent) in self.agents.iter_mut() {
-                    if other_agent.name != agent_name {
-                        other_agent
-                            .next_prompt
-                            .push_str(&format!("[{}→User]: {}\n", agent_name, response_text));
-                    }
+            // Muted agents keep what they hear, to catch up once unmuted
+            if agent.muted {
+                continue;
+            }
+
+            // Tools only answer queries addressed to them, and ignore the rest of the chatter
+            if agent.tool.is_some() && !agent.next_prompt.contains(&format!("→{}]", agent.name)) {
+                agent.archive_prompt();
+                continue;
+            }
+
+            // An agent that just spoke waits its turn, keeping what it heard for later
+            if let Some(cooldown) = self.config.world.speak_cooldown_ticks {
+                let last_spoke = self.last_spoke.get(&agent.name);
+                if last_spoke.is_some_and(|tick| self.current_tick - tick <= cooldown) {
+                    continue;
                 }
+            }
 
-                // Update the agent's state with the new energy level
-                if let Some(agent) = self.agents.values_mut().find(|a| a.name == agent_name) {
-                    agent.state = AgentState::Speaking;
-                    agent.energy -= 1.0;
-                    let _ = self.ui_tx.send(SimulationToUI::AgentUpdate(
+            // Quieter agents may stay silent; they keep what they heard to respond later
+            if self.rng.gen::<f32>() >= speaking_probability {
+                continue;
+            }
+
+            // Off-topic chatter is heard but not answered, unless addressed to the agent
+            if let Some(threshold) = self.config.world.relevance_threshold {
+                let addressed = agent.next_prompt.contains(&format!("→{}]", agent.name));
+                let mut interests: Vec<&str> = agent.interests.iter().map(String::as_str).collect();
+                interests.extend(self.discussion_topic.as_deref());
+                if !addressed && relevance(&agent.next_prompt, &interests) < threshold {
+                    agent.archive_prompt();
+                    agent.state = AgentState::Listening;
+                    self.events.emit(SimulationToUI::AgentUpdate(
                         agent.name.clone(),
                         agent.state.clone(),
                         agent.energy,
+                        agent.mood,
                     ));
+                    continue;
                 }
             }
 
-            // Clear the prompt for the next turn
-            if let Some(agent) = self.agents.values_mut().find(|a| a.name == agent_name) {
-                agent.next_prompt.clear();
-            }
-        } else {
-            let _ = self.ui_tx.send(SimulationToUI::StateUpdate(format!(
-                "Agent '{}' not found.",
-                recipient
+            // The agent has heard messages and will respond
+            agent.state = AgentState::Thinking;
+
+            // Notify the UI about the state change
+            self.events.emit(SimulationToUI::AgentUpdate(
+                agent.name.clone(),
+                agent.state.clone(),
+                agent.energy,
+                agent.mood,
+            ));
+
+            // Tools reply to whoever asked; others address a preferred partner, or else respond
+            // to the last message
+            let preferred = self
+                .conversation_manager
+                .choose_recipient(&agent.name, &mut self.rng);
+            let asker = agent
+                .tool
+                .as_ref()
+                .and_then(|_| latest_query(&agent.next_prompt, &agent.name))
+                .map(|(sender, _)| sender.to_string());
+            let recipient = if let Some(asker) = asker {
+                asker
+            } else if preferred != "everyone" {
+                preferred
+            } else if agent.next_prompt.contains("→") {
+                agent
+                    .next_prompt
+                    .lines()
+                    .last()
+                    .and_then(|line| line.split('→').next())
+                    .unwrap_or("everyone")
+                    .trim_start_matches('[')
+                    .to_string()
+            } else {
+                "everyone".to_string()
+            };
+
+            // Generate a response, shorter the longer the agent has held the floor
+            let mut speaker = agent.clone();
+            speaker.max_tokens = self.monologue_budget(speaker.max_tokens, streak);
+            generations.push(self.prepare_turn(&speaker));
+            turns.push((id, recipient, speaker));
+        }
+
+        // Everyone speaking this tick generates at once, as far as the request slots allow
+        let mut results = if self.running && !generations.is_empty() {
+            self.await_all(generations).map(Vec::into_iter)
+        } else {
+            None
+        };
+        for (id, recipient, speaker) in turns {
+            let generated = results.as_mut().and_then(Iterator::next);
+            let response = self.finish_turn(&speaker.name, generated);
+            let agent = self.agents.get_mut(&id).unwrap();
+
+            // What the agent heard becomes part of its memory, whatever the outcome
+            agent.archive_prompt();
+
+            match response {
+                Some(Ok(response_text)) => {
+                    // An agent naming someone at the start of its reply is talking to them
+                    let mut recipient = recipient;
+                    if self.config.world.vocative_addressing {
+                        let names = self.agents.values().map(|a| a.name.as_str());
+                        if let Some(addressed) = addressed_agent(&response_text, names) {
+                            if addressed != speaker.name {
+                                recipient = addressed.to_string();
+                            }
+                        }
+                    }
+                    let agent = self.agents.get_mut(&id).unwrap();
+                    agent.remember(format!("[{}→{}]: {}", agent.name, recipient, response_text));
+                    agent.record_response(&response_text);
+                    agent.opening = false;
+
+                    // Create a response message
+                    let mut response_message =
+                        Message::new(&agent.name, &recipient, response_text.as_str())
+                            .at_tick(self.current_tick)
+                            .in_room(speaker.room.clone());
+                    self.tag_sentiment(&speaker, &mut response_message);
+                    let agent = self.agents.get_mut(&id).unwrap();
+
+                    // Hold the reply back for a few ticks if jitter is configured
+                    let delay = self.config.world.reply_delay_ticks.map_or(0, |range| {
+                        reply_delay(range, agent.personality.conscientiousness, &mut self.rng)
+                    });
+                    // The agent speaks when its reply is delivered, and pays for it right away
+                    agent.energy -= 1.0;
+                    spoke = true;
+                    speakers.push(agent.name.clone());
+                    self.turns_since_summary += 1;
+                    self.last_spoke
+                        .insert(agent.name.clone(), self.current_tick);
+                    if delay > 0 {
+                        self.scheduled_messages
+                            .push((self.current_tick + delay, response_message));
+                    } else {
+                        // Add to the list of new messages
+                        new_messages.push(self.deliver(response_message));
+                    }
+                }
+                Some(Err(e)) => {
+                    // An empty or repeated reply skips the agent's turn without costing energy
+                    if matches!(e, Error::EmptyResponse | Error::RepeatedResponse) {
+                        agent.state = AgentState::Idle;
+                    }
+                    self.events
+                        .emit(SimulationToUI::StateUpdate(describe_failure(
+                            &agent.name,
+                            &e,
+                        )));
+                }
+                None => {
+                    // Interrupted: discard the partial output and go back to Idle
+                    agent.state = AgentState::Idle;
+                    self.events.emit(SimulationToUI::StateUpdate(format!(
+                        "{} was interrupted",
+                        agent.name
+                    )));
+                }
+            }
+        }
+
+        // Keep count of the ticks an agent speaks alone, until another agent speaks
+        self.track_floor(&speakers);
+
+        // 3. Let the moderator steer the discussion on its cadence
+        if let Some(message) = self.moderate() {
+            self.events
+                .emit(SimulationToUI::MessageUpdate(message.clone()));
+            new_messages.push(message);
+        }
+
+        // Summarize the discussion for the user on the observer's cadence
+        self.observe();
+
+        // Remind agents of what was said once enough turns have gone by on the topic
+        self.summarize();
+
+        // Distill the recent messages into the shared memory on its cadence
+        self.synthesize_memory();
+
+        // Score how close agents are to their goals on its cadence
+        self.evaluate_goals();
+
+        // Let what agents heard shape their personalities on its cadence
+        self.drift_personalities();
+
+        // Release delayed replies that are now due
+        let current_tick = self.current_tick;
+        new_messages.extend(self.release_scheduled(|due_tick| due_tick <= current_tick));
+
+        // Track how long nobody has spoken, once the conversation has started
+        if !spoke {
+            if self.conversation_manager.message_count() > 0 {
+                self.idle_ticks += 1;
+            }
+        } else {
+            self.idle_ticks = 0;
+        }
+
+        // Pause a discussion that keeps flooding the model server
+        self.guard_against_flood(new_messages.len());
+
+        // Clear current messages and add new ones, in the order they were sent
+        new_messages.sort_by_key(|message| message.timestamp);
+        self.messages.clear();
+        self.messages.extend(new_messages);
+
+        // Update agents' energy levels
+        for (_, agent) in self.agents.iter_mut() {
+            agent.energy = next_energy(
+                agent.energy,
+                agent.state == AgentState::Idle,
+                &self.config.world,
+            );
+
+            // Nobody is still thinking or speaking once the tick is over
+            if matches!(agent.state, AgentState::Thinking | AgentState::Speaking) {
+                agent.state = AgentState::Idle;
+            }
+
+            self.events.emit(SimulationToUI::AgentUpdate(
+                agent.name.clone(),
+                agent.state.clone(),
+                agent.energy,
+                agent.mood,
+            ));
+        }
+
+        // Keep the snapshot fresh, so a crash loses at most one interval
+        let interval = self.config.snapshot.interval;
+        if self.config.autosave && interval > 0 && self.current_tick.is_multiple_of(interval) {
+            if let Err(e) = self.save_snapshot() {
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Failed to save the snapshot: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    /// Shows an agent's reply to the UI and marks its sender as speaking.
+    ///
+    /// # Returns
+    /// * The message, to be heard by the agents on the next tick.
+    fn deliver(&mut self, message: Message) -> Message {
+        if let Some(agent) = self.agents.values_mut().find(|a| a.name == message.sender) {
+            agent.state = AgentState::Speaking;
+            self.events.emit(SimulationToUI::AgentUpdate(
+                agent.name.clone(),
+                agent.state.clone(),
+                agent.energy,
+                agent.mood,
+            ));
+        }
+        self.events
+            .emit(SimulationToUI::MessageUpdate(message.clone()));
+        message
+    }
+
+    /// Delivers the delayed replies whose due tick matches a condition.
+    ///
+    /// # Arguments
+    /// * `is_due` - Tells from its due tick whether a reply is released.
+    ///
+    /// # Returns
+    /// * The released replies, in the order they were scheduled.
+    fn release_scheduled(&mut self, is_due: impl Fn(u64) -> bool) -> Vec<Message> {
+        let (due, pending): (Vec<_>, Vec<_>) = self
+            .scheduled_messages
+            .drain(..)
+            .partition(|(due_tick, _)| is_due(*due_tick));
+        self.scheduled_messages = pending;
+        due.into_iter()
+            .map(|(_, mut message)| {
+                // A delayed reply is sent when released, after what was said in the meantime
+                message.timestamp = Utc::now();
+                self.deliver(message)
+            })
+            .collect()
+    }
+
+    /// Sends one system message per agent describing its personality, state, and energy.
+    fn list_agents(&self) {
+        let mut agents: Vec<&Agent> = self.agents.values().collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for agent in agents {
+            let template = self
+                .config
+                .agents
+                .iter()
+                .find(|c| c.name == agent.name)
+                .map_or("unknown", |c| c.personality_template.as_str());
+            self.events.emit(SimulationToUI::MessageUpdate(Message::new(
+                "System",
+                "User",
+                format!(
+                    "{} ({}) - {} - energy {:.1}",
+                    agent.name, template, agent.state, agent.energy
+                ),
+            )));
+        }
+    }
+
+    /// Summarizes the run so far: ticks, message counts, response length, energies, generation
+    /// throughput, and goal progress.
+    fn stats(&self) -> String {
+        let mut lines = vec![
+            format!("Ticks elapsed: {}", self.current_tick),
+            format!(
+                "Total messages: {}",
+                self.conversation_manager.message_count()
+            ),
+        ];
+
+        lines.push("Messages per sender:".to_string());
+        for (sender, count) in self.conversation_manager.messages_per_sender() {
+            lines.push(format!("  {}: {}", sender, count));
+        }
+
+        if !self.config.rooms.is_empty() {
+            lines.push("Messages per room:".to_string());
+            for room in &self.config.rooms {
+                let count = self.conversation_manager.messages_in_room(&room.name).len();
+                lines.push(format!("  {}: {}", room.name, count));
+            }
+        }
+
+        lines.push(match self.conversation_manager.average_response_length() {
+            Some(length) => format!("Average response length: {:.1} characters", length),
+            None => "Average response length: n/a".to_string(),
+        });
+
+        let mut agents: Vec<&Agent> = self.agents.values().collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+        lines.push("Energy:".to_string());
+        for agent in &agents {
+            lines.push(format!("  {}: {:.1}", agent.name, agent.energy));
+        }
+
+        // Tokens are estimated from the response length, as for the context budget
+        let mut per_model: BTreeMap<&str, Throughput> = BTreeMap::new();
+        lines.push("Throughput per agent:".to_string());
+        for agent in &agents {
+            let Some(throughput) = self.throughput.get(&agent.name) else {
+                continue;
+            };
+            lines.push(format!("  {}: {}", agent.name, throughput.describe()));
+            per_model
+                .entry(&agent.ollama_model)
+                .or_default()
+                .add(throughput);
+        }
+        lines.push("Throughput per model:".to_string());
+        for (model, throughput) in per_model {
+            lines.push(format!("  {}: {}", model, throughput.describe()));
+        }
+
+        if agents.iter().any(|a| a.goal.is_some()) {
+            lines.push("Goal progress:".to_string());
+            for agent in &agents {
+                let Some(goal) = &agent.goal else {
+                    continue;
+                };
+                let progress = self
+                    .goal_progress
+                    .get(&agent.name)
+                    .map_or("not evaluated yet".to_string(), |score| {
+                        format!("{}/10", score)
+                    });
+                lines.push(format!("  {}: {} ({})", agent.name, progress, goal));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Returns how many ticks in a row an agent has spoken alone, for curbing monologues.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the agent.
+    ///
+    /// # Returns
+    /// * The length of the agent's streak, or 0 if it does not hold the floor or monologues
+    ///   are not curbed.
+    fn monologue_streak(&self, name: &str) -> i32 {
+        match &self.floor {
+            Some((holder, streak)) if self.config.world.monologue.enabled && holder == name => {
+                *streak
+            }
+            _ => 0,
+        }
+    }
+
+    /// Shortens an agent's response budget for each tick of its monologue streak.
+    ///
+    /// # Arguments
+    /// * `max_tokens` - The agent's own budget; unlimited ones decay from `default_tokens`.
+    /// * `streak` - Length of the agent's streak.
+    ///
+    /// # Returns
+    /// * The budget for the agent's next reply, never below `min_tokens` unless its own budget
+    ///   already is.
+    fn monologue_budget(&self, max_tokens: Option<u32>, streak: i32) -> Option<u32> {
+        if streak == 0 {
+            return max_tokens;
+        }
+        let monologue = &self.config.world.monologue;
+        let tokens = max_tokens.unwrap_or(monologue.default_tokens);
+        let decayed = tokens as f32 * monologue.token_decay.powi(streak);
+        Some((decayed.round() as u32).max(monologue.min_tokens.min(tokens)))
+    }
+
+    /// Updates who holds the floor from the agents that spoke during a tick.
+    ///
+    /// Silent ticks leave the streak untouched, so an agent pausing between replies still
+    /// counts as keeping the floor; any other agent speaking resets it.
+    ///
+    /// # Arguments
+    /// * `speakers` - Names of the agents that spoke during the tick.
+    fn track_floor(&mut self, speakers: &[String]) {
+        match speakers {
+            [] => {}
+            [speaker] => match &mut self.floor {
+                Some((holder, streak)) if holder == speaker => *streak += 1,
+                _ => self.floor = Some((speaker.clone(), 1)),
+            },
+            _ => self.floor = None,
+        }
+    }
+
+    /// Pauses the simulation when too many messages were produced for too many ticks in a row.
+    ///
+    /// # Arguments
+    /// * `new_messages` - Number of messages produced by the tick that just ran.
+    fn guard_against_flood(&mut self, new_messages: usize) {
+        let guard = &self.config.world.flood_guard;
+        if !guard.enabled {
+            return;
+        }
+        if new_messages <= guard.max_messages_per_tick {
+            self.flooded_ticks = 0;
+            return;
+        }
+
+        self.flooded_ticks += 1;
+        if self.flooded_ticks >= guard.ticks {
+            let warning = format!(
+                "Paused: more than {} messages per tick for {} ticks in a row. Resume to continue",
+                guard.max_messages_per_tick, self.flooded_ticks
+            );
+            self.flooded_ticks = 0;
+            self.paused = true;
+            self.events.emit(SimulationToUI::StateUpdate(warning));
+        }
+    }
+
+    /// Returns the topic an agent discusses: its room's, when it has one, or the global one.
+    fn topic_for(&self, agent: &Agent) -> Option<&str> {
+        agent
+            .room
+            .as_ref()
+            .and_then(|room| self.room_topics.get(room))
+            .or(self.discussion_topic.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Generates an agent's response on the runtime while still listening for UI commands.
+    ///
+    /// Commands are handled as described in `await_all`. Responses are cleaned up by the
+    /// configured post-processing steps, and an empty response is retried once. With a
+    /// `repetition_threshold`, a response too close to one of the agent's latest ones is
+    /// retried once with a nudge, then reported as repeated.
+    ///
+    /// # Returns
+    /// * `Some(Ok(String))` containing the response text.
+    /// * `Some(Err(Error))` if the response could not be generated.
+    /// * `None` if the generation was interrupted and its output discarded.
+    fn generate_response(&mut self, agent: &Agent) -> Option<Result<String, Error>> {
+        let turn = self.prepare_turn(agent);
+        let generated = self
+            .await_all(vec![turn])
+            .and_then(|mut results| results.pop());
+        self.finish_turn(&agent.name, generated)
+    }
+
+    /// Gets an agent ready to respond, and returns the task generating its response.
+    ///
+    /// Memories are recalled and the context is fitted once, so retries send the same prompt.
+    /// Each request of the task waits for one of the request slots, so the tasks of several
+    /// agents can run side by side.
+    fn prepare_turn(
+        &mut self,
+        agent: &Agent,
+    ) -> impl Future<Output = Result<Generated, Error>> + Send + 'static {
+        // Tools answer the latest query themselves, without the model
+        let tool_reply = agent.tool.as_ref().map(|tool| {
+            let query = latest_query(&agent.next_prompt, &agent.name).map_or("", |(_, text)| text);
+            tool.respond(query)
+        });
+
+        let mut agent = agent.clone();
+        let topic = self.topic_for(&agent).map(str::to_string);
+        if tool_reply.is_none() {
+            agent.recalled = self.recall(&agent);
+
+            // The context is fitted once, so retries send the same history and recent messages
+            let budget = agent.context_tokens;
+            if let (Some(budget), true) = (budget, agent.trim_context()) {
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Trimmed {}'s oldest context to fit {} tokens",
+                    agent.name, budget
+                )));
+            }
+            if self.config.debug {
+                self.last_prompts
+                    .insert(agent.name.clone(), agent.full_prompt(topic.as_deref()));
+            }
+        }
+
+        let steps = self.config.postprocess.clone();
+        let repetition_threshold = self.config.world.repetition_threshold;
+        let slots = self.generation_slots.clone();
+        async move {
+            let generated = match tool_reply {
+                Some(reply) => Generated {
+                    response: Ok(reply),
+                    attempts: Vec::new(),
+                },
+                None => respond(agent, topic, steps, repetition_threshold, slots).await,
+            };
+            Ok(generated)
+        }
+    }
+
+    /// Records the throughput of a finished turn and returns its response.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the agent whose turn it was.
+    /// * `generated` - The outcome of the turn's task, or `None` if it was interrupted.
+    ///
+    /// # Returns
+    /// * The response, as described in `generate_response`.
+    fn finish_turn(
+        &mut self,
+        name: &str,
+        generated: Option<Result<Generated, Error>>,
+    ) -> Option<Result<String, Error>> {
+        let generated = match generated? {
+            Ok(generated) => generated,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if !generated.attempts.is_empty() {
+            let throughput = self.throughput.entry(name.to_string()).or_default();
+            for (tokens, elapsed) in generated.attempts {
+                throughput.record(tokens, elapsed);
+            }
+
+            // Lets the UI tell how long the agent's next turn will likely take
+            let estimate = throughput.rolling_seconds;
+            self.events
+                .emit(SimulationToUI::WaitEstimate(name.to_string(), estimate));
+        }
+
+        // Check the server again before the next tick instead of failing every generation
+        if matches!(generated.response, Err(Error::OllamaConnection(_))) {
+            self.next_health_check = Instant::now();
+        }
+        Some(generated.response)
+    }
+
+    /// Checks that the model server is reachable, at most every `health_check_interval_secs`.
+    ///
+    /// The UI is told when the server goes away and when it comes back, so generation resumes
+    /// on its own once a later check succeeds.
+    ///
+    /// # Returns
+    /// * `true` if the server was reachable at the last check.
+    fn backend_ready(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_health_check {
+            return !self.backend_unreachable;
+        }
+        self.next_health_check = now + Duration::from_secs(self.config.health_check_interval_secs);
+
+        // A server that accepts the connection but never answers is as good as gone
+        let check = limit_concurrency(self.generation_slots.clone(), self.backend.health_check());
+        let reachable = self
+            .runtime
+            .block_on(async { tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check).await })
+            .is_ok_and(|result| result.is_ok());
+        if reachable == self.backend_unreachable {
+            let status = if reachable {
+                "Ollama is reachable again, resuming"
+            } else {
+                "Ollama unreachable, waiting…"
+            };
+            self.events
+                .emit(SimulationToUI::StateUpdate(status.to_string()));
+        }
+        self.backend_unreachable = !reachable;
+        reachable
+    }
+
+    /// Adds a message to the conversation history and, when recall is enabled, to the memory
+    /// index.
+    fn record_message(&mut self, message: &Message) {
+        self.conversation_manager.add_message(message.clone());
+
+        let Some(model) = self.config.embeddings.as_ref().map(|e| e.model.clone()) else {
+            return;
+        };
+        let backend = self.backend.clone();
+        let text = message.content.text.clone();
+        let embedding = self.await_generation(async move { backend.embed(&model, &text).await });
+        match embedding {
+            // Interrupted or stopped; the message is simply not recallable
+            None => {}
+            Some(Ok(vector)) => self.memory_index.insert(
+                message.id.clone(),
+                vector,
+                format!(
+                    "[{}→{}]: {}",
+                    message.sender, message.recipient, message.content
+                ),
+            ),
+            Some(Err(e)) => self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Failed to index message for recall: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Recalls the past messages most relevant to what an agent is about to answer.
+    ///
+    /// The query is embedded through the same slots, timeout, and interrupts as generations.
+    ///
+    /// # Returns
+    /// * Up to `top_k` message lines not already among the agent's recent messages, or none
+    ///   when recall is disabled or the query could not be embedded.
+    fn recall(&mut self, agent: &Agent) -> Vec<String> {
+        let Some((model, top_k)) = self
+            .config
+            .embeddings
+            .as_ref()
+            .map(|e| (e.model.clone(), e.top_k))
+        else {
+            return Vec::new();
+        };
+        if agent.next_prompt.is_empty() {
+            return Vec::new();
+        }
+        let backend = self.backend.clone();
+        let text = agent.next_prompt.clone();
+        match self.await_generation(async move { backend.embed(&model, &text).await }) {
+            Some(Ok(query)) => self
+                .memory_index
+                .most_similar(&query, top_k, |line| agent.next_prompt.contains(line)),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Runs a request to the model server on the runtime, once one of the request slots is
+    /// free, while still listening for UI commands.
+    ///
+    /// See `await_all` for how commands received meanwhile are handled.
+    fn await_generation<F, T>(&mut self, generation: F) -> Option<Result<T, Error>>
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let generation = limit_concurrency(self.generation_slots.clone(), generation);
+        self.await_all(vec![generation])
+            .and_then(|mut results| results.pop())
+    }
+
+    /// Runs tasks side by side on the runtime while still listening for UI commands.
+    ///
+    /// Commands other than `Interrupt` and `Stop` received meanwhile are queued and applied
+    /// once the current tick is done. `Interrupt` aborts every task, and `Stop` also stops the
+    /// loop. Tasks still running past `generation_timeout_secs` are aborted and reported as
+    /// timed out, while those already done keep their results.
+    ///
+    /// # Returns
+    /// * The result of each task, in order.
+    /// * `None` if the tasks were interrupted and their output discarded.
+    fn await_all<F, T>(&mut self, tasks: Vec<F>) -> Option<Vec<Result<T, Error>>>
+    where
+        F: Future<Output = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .map(|task| self.runtime.spawn(task))
+            .collect();
+        let abort_all = || handles.iter().for_each(|handle| handle.abort());
+        let deadline = self
+            .config
+            .world
+            .generation_timeout_secs
+            .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        while !handles.iter().all(|handle| handle.is_finished()) {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            match self.try_recv_command() {
+                Ok(UIToSimulation::Interrupt) => {
+                    abort_all();
+                    return None;
+                }
+                Ok(UIToSimulation::Stop) => {
+                    // Stop accepting new work right away; the rest of the tick is skipped
+                    abort_all();
+                    self.running = false;
+                    return None;
+                }
+                Ok(command) => self.pending_commands.push_back(command),
+                Err(TryRecvError::Disconnected) => {
+                    // Nobody is left to read the response
+                    abort_all();
+                    self.running = false;
+                    return None;
+                }
+                Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+
+        let results = handles
+            .into_iter()
+            .map(|handle| {
+                if !handle.is_finished() {
+                    handle.abort();
+                    return Err(Error::Timeout);
+                }
+                match self.runtime.block_on(handle) {
+                    Ok(result) => result,
+                    Err(e) => Err(Error::Generation(e.to_string())),
+                }
+            })
+            .collect();
+        Some(results)
+    }
+
+    /// Tags an agent's message with its sentiment.
+    ///
+    /// Model classifications go through the same slots, timeout, and interrupts as
+    /// generations, and are reused for identical texts; keywords are used when the model
+    /// fails.
+    fn tag_sentiment(&mut self, speaker: &Agent, message: &mut Message) {
+        let text = message.content.text.clone();
+        let sentiment = match self.config.sentiment {
+            SentimentMode::Off => return,
+            SentimentMode::Keywords => classify_keywords(&text),
+            SentimentMode::Model => match self.sentiment_cache.get(&text) {
+                Some(sentiment) => sentiment,
+                None => {
+                    let backend = speaker.backend.clone();
+                    let model = speaker.ollama_model.clone();
+                    let prompt_text = text.clone();
+                    let classified = self.await_generation(async move {
+                        classify_with_model(backend.as_ref(), &model, &prompt_text).await
+                    });
+                    match classified {
+                        Some(Ok(sentiment)) => {
+                            self.sentiment_cache.insert(text, sentiment);
+                            sentiment
+                        }
+                        _ => classify_keywords(&text),
+                    }
+                }
+            },
+        };
+        message.sentiment = Some(sentiment);
+    }
+
+    /// Generates the moderator's intervention when one is due.
+    ///
+    /// The moderator speaks every `moderator_interval` ticks once the discussion has started,
+    /// addressing everyone without taking any agent's turn, except in focus mode.
+    ///
+    /// # Returns
+    /// * `Some(Message)` containing the intervention.
+    /// * `None` if no intervention is due or it could not be generated.
+    fn moderate(&mut self) -> Option<Message> {
+        let interval = self.config.world.moderator_interval;
+        if interval == 0 || !self.current_tick.is_multiple_of(interval) || !self.running {
+            return None;
+        }
+        // The moderator waits while the user talks with a single agent
+        if self.focused_agent.is_some() {
+            return None;
+        }
+        let moderator = self.moderator.clone()?;
+
+        let history = self.conversation_manager.messages();
+        if history.is_empty() {
+            return None;
+        }
+        let recent = history
+            .iter()
+            .rev()
+            .take(MODERATOR_CONTEXT_MESSAGES)
+            .rev()
+            .map(|m| format!("[{}→{}]: {}", m.sender, m.recipient, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let topic = self.discussion_topic.clone();
+        let speaker = moderator.clone();
+        let response = self.await_generation(async move {
+            speaker.generate_moderation(topic.as_deref(), &recent).await
+        });
+
+        match response {
+            Some(Ok(text)) => {
+                Some(Message::new(&moderator.name, "everyone", text).at_tick(self.current_tick))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sends the observer's summary of the discussion to the user when one is due.
+    ///
+    /// The summary goes straight to the UI: it is never delivered to the agents, recorded in
+    /// the history, or counted as anyone's turn.
+    fn observe(&mut self) {
+        let (Some(observer), Some(observer_config)) = (&self.observer, &self.config.observer)
+        else {
+            return;
+        };
+        let interval = observer_config.interval;
+        if interval == 0 || !self.current_tick.is_multiple_of(interval) || !self.running {
+            return;
+        }
+
+        let history = self.conversation_manager.messages();
+        if history.is_empty() {
+            return;
+        }
+        let recent = history
+            .iter()
+            .rev()
+            .take(MODERATOR_CONTEXT_MESSAGES)
+            .rev()
+            .map(|m| format!("[{}→{}]: {}", m.sender, m.recipient, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let observer = observer.clone();
+        let template = observer_config.prompt.clone();
+        let topic = self.discussion_topic.clone();
+        let name = observer.name.clone();
+        let response = self.await_generation(async move {
+            observer
+                .generate_observation(&template, topic.as_deref(), &recent)
+                .await
+        });
+
+        if let Some(Ok(text)) = response {
+            let message = Message::new(&name, "User", text).at_tick(self.current_tick);
+            self.events.emit(SimulationToUI::MessageUpdate(message));
+        }
+    }
+
+    /// Summarizes the latest turns into every agent's history every `summary.turns` turns.
+    ///
+    /// The first persona by name writes the summary with its model, which is also shown to the
+    /// user when `post_to_user` is set; the count starts over even if the generation fails.
+    fn summarize(&mut self) {
+        let summary = &self.config.world.summary;
+        if !summary.enabled
+            || summary.turns == 0
+            || self.turns_since_summary < summary.turns
+            || !self.running
+        {
+            return;
+        }
+        self.turns_since_summary = 0;
+        let Some(writer) = self
+            .agents
+            .values()
+            .filter(|a| a.tool.is_none())
+            .min_by(|a, b| a.name.cmp(&b.name))
+            .cloned()
+        else {
+            return;
+        };
+
+        let recent = self
+            .conversation_manager
+            .messages()
+            .iter()
+            .rev()
+            .take(summary.turns)
+            .rev()
+            .map(|m| format!("[{}→{}]: {}", m.sender, m.recipient, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let template = summary.prompt.clone();
+        let post_to_user = summary.post_to_user;
+        let topic = self.discussion_topic.clone();
+        let response = self.await_generation(async move {
+            writer
+                .generate_observation(&template, topic.as_deref(), &recent)
+                .await
+        });
+        let Some(Ok(text)) = response else {
+            return;
+        };
+
+        // Kept in the history rather than heard, so nobody feels bound to answer it
+        for agent in self.agents.values_mut() {
+            agent.remember(format!("[Summary]: {}", text));
+        }
+        if post_to_user {
+            let message = Message::new("System", "User", format!("Summary: {}", text))
+                .at_tick(self.current_tick);
+            self.events.emit(SimulationToUI::MessageUpdate(message));
+        }
+    }
+
+    /// Updates the shared memory from the recent messages every `memory_interval` ticks.
+    ///
+    /// The first agent by name writes the update with its model; the previous facts are kept
+    /// if the generation fails.
+    fn synthesize_memory(&mut self) {
+        let Some(interval) = self.config.world.memory_interval else {
+            return;
+        };
+        if interval == 0 || !self.current_tick.is_multiple_of(interval) || !self.running {
+            return;
+        }
+        let Some(writer) = self
+            .agents
+            .values()
+            .min_by(|a, b| a.name.cmp(&b.name))
+            .cloned()
+        else {
+            return;
+        };
+
+        let history = self.conversation_manager.messages();
+        if history.is_empty() {
+            return;
+        }
+        let recent = history
+            .iter()
+            .rev()
+            .take(MEMORY_CONTEXT_MESSAGES)
+            .rev()
+            .map(|m| format!("[{}→{}]: {}", m.sender, m.recipient, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let topic = self.discussion_topic.clone();
+        let response = self.await_generation(async move {
+            writer
+                .generate_memory_update(topic.as_deref(), &recent)
+                .await
+        });
+        if let Some(Ok(reply)) = response {
+            let facts = parse_facts(&reply);
+            if !facts.is_empty() {
+                self.global_memory.replace(facts);
+            }
+        }
+    }
+
+    /// Scores how close each agent with a goal is to it, every `goal_check_interval` ticks.
+    ///
+    /// Each agent assesses itself with its own model from the recent messages; the previous
+    /// score is kept if the generation fails or its reply holds no score.
+    fn evaluate_goals(&mut self) {
+        let Some(interval) = self.config.world.goal_check_interval else {
+            return;
+        };
+        if interval == 0 || !self.current_tick.is_multiple_of(interval) || !self.running {
+            return;
+        }
+
+        let history = self.conversation_manager.messages();
+        if history.is_empty() {
+            return;
+        }
+        let recent = history
+            .iter()
+            .rev()
+            .take(MEMORY_CONTEXT_MESSAGES)
+            .rev()
+            .map(|m| format!("[{}→{}]: {}", m.sender, m.recipient, m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut agents: Vec<Agent> = self
+            .agents
+            .values()
+            .filter(|a| a.goal.is_some())
+            .cloned()
+            .collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for agent in agents {
+            let name = agent.name.clone();
+            let goal = agent.goal.clone().unwrap_or_default();
+            let recent = recent.clone();
+            let response = self.await_generation(async move {
+                agent.generate_goal_assessment(&goal, &recent).await
+            });
+            if let Some(score) = response
+                .and_then(Result::ok)
+                .as_deref()
+                .and_then(parse_goal_score)
+            {
+                self.goal_progress.insert(name, score);
+            }
+            if !self.running {
+                return;
+            }
+        }
+    }
+
+    /// Drifts each agent's traits with the tone of the messages it heard over the last
+    /// `personality_drift.interval` ticks.
+    ///
+    /// Tones come from the tagged sentiment of messages, or from their keywords when untagged.
+    fn drift_personalities(&mut self) {
+        let drift = &self.config.world.personality_drift;
+        if !drift.enabled
+            || drift.interval == 0
+            || !self.current_tick.is_multiple_of(drift.interval)
+        {
+            return;
+        }
+
+        let since = self.current_tick.saturating_sub(drift.interval);
+        let recent: Vec<&Message> = self
+            .conversation_manager
+            .messages()
+            .into_iter()
+            .filter(|m| m.tick > since)
+            .collect();
+
+        for agent in self.agents.values_mut() {
+            let tones: Vec<f32> = recent
+                .iter()
+                .filter(|m| {
+                    m.sender != agent.name
+                        && (m.recipient == agent.name || m.recipient == "everyone")
+                })
+                .map(|m| {
+                    m.sentiment
+                        .unwrap_or_else(|| classify_keywords(&m.content.text))
+                        .score()
+                })
+                .collect();
+            if tones.is_empty() {
+                continue;
+            }
+            let mood = tones.iter().sum::<f32>() / tones.len() as f32;
+            agent.personality.drift(mood, drift.rate);
+        }
+    }
+
+    /// Picks the agent opening a conversation according to the configured policy.
+    ///
+    /// # Returns
+    /// * The name of the starting agent, or `None` if there are no agents.
+    fn choose_starter(&self) -> Option<String> {
+        self.choose_starter_in(None)
+    }
+
+    /// Picks the agent opening a conversation among the agents of a room.
+    ///
+    /// # Arguments
+    /// * `room` - The room the conversation happens in, or `None` for every agent.
+    ///
+    /// # Returns
+    /// * The name of the starting agent, or `None` if there are no agents.
+    fn choose_starter_in(&self, room: Option<&RoomConfig>) -> Option<String> {
+        let mut agents: Vec<&Agent> = self
+            .agents
+            .values()
+            .filter(|a| {
+                a.tool.is_none()
+                    && !a.muted
+                    && room.is_none_or(|room| room.agents.contains(&a.name))
+            })
+            .collect();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let world = &self.config.world;
+        if let Some(name) = &world.starting_agent {
+            if agents.iter().any(|a| &a.name == name) {
+                return Some(name.clone());
+            }
+        }
+
+        let starter = match world.starter {
+            StarterPolicy::First => agents.first().copied(),
+            StarterPolicy::Extraverted => agents.iter().copied().reduce(|best, agent| {
+                if agent.personality.extraversion > best.personality.extraversion {
+                    agent
+                } else {
+                    best
+                }
+            }),
+            StarterPolicy::Rotate => agents
+                .get(self.conversations_started % agents.len().max(1))
+                .copied(),
+        };
+        starter.map(|agent| agent.name.clone())
+    }
+
+    /// Starts the conversation with a given topic.
+    fn start_conversation(&mut self, topic: &str) {
+        // Choose an agent to start the conversation
+        if let Some(starter) = self.choose_starter() {
+            self.conversations_started += 1;
+
+            // Scenario openings reach their recipients before anyone speaks
+            for opening in self.config.world.opening_messages.clone() {
+                let content = prompt::render(&opening.content, &[("topic", topic)]);
+                let message =
+                    Message::new("System", &opening.recipient, content).at_tick(self.current_tick);
+                for agent in self.agents.values_mut() {
+                    if opening.recipient == "everyone" || agent.name == opening.recipient {
+                        agent.next_prompt.push_str(&format!(
+                            "[System→{}]: {}\n",
+                            message.recipient, message.content
+                        ));
+                    }
+                }
+                self.record_message(&message);
+                self.events.emit(SimulationToUI::MessageUpdate(message));
+            }
+
+            // Each room opens its own discussion, on its own topic when it has one
+            if self.config.rooms.is_empty() {
+                self.open_discussion(&starter, None, topic);
+            } else {
+                for room in self.config.rooms.clone() {
+                    if let Some(starter) = self.choose_starter_in(Some(&room)) {
+                        let topic = self
+                            .room_topics
+                            .get(&room.name)
+                            .cloned()
+                            .unwrap_or_else(|| topic.to_string());
+                        self.open_discussion(&starter, Some(room.name), &topic);
+                    }
+                }
+            }
+
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Conversation started on topic: {}",
+                topic
+            )));
+        }
+    }
+
+    /// Invites an agent to open the discussion of a topic.
+    ///
+    /// # Arguments
+    /// * `starter` - Name of the agent speaking first.
+    /// * `room` - The room the discussion happens in, or `None` for every agent.
+    /// * `topic` - The topic to discuss.
+    fn open_discussion(&mut self, starter: &str, room: Option<String>, topic: &str) {
+        // The starter's first turn sets the stage rather than reacting
+        if let Some(agent) = self.agents.values_mut().find(|a| a.name == starter) {
+            agent.opening = true;
+        }
+
+        // Create an initial message
+        let initial_message = Message::new(
+            "System",
+            starter,
+            format!("Let's talk about {}. What do you think?", topic),
+        )
+        .at_tick(self.current_tick)
+        .in_room(room);
+
+        // Add the message to the list
+        self.messages.push(initial_message.clone());
+
+        // Send the message to the UI
+        self.events
+            .emit(SimulationToUI::MessageUpdate(initial_message));
+    }
+
+    /// Changes the topic of a room and opens a discussion of it among the room's agents.
+    fn set_room_topic(&mut self, room: &str, topic: &str) {
+        let Some(config) = self.config.rooms.iter().find(|r| r.name == room).cloned() else {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Unknown room: {}",
+                room
+            )));
+            return;
+        };
+
+        // Agents already discussing something in the room are told about the shift
+        if let Some(previous) = self.room_topics.insert(room.to_string(), topic.to_string()) {
+            let message = Message::new(
+                "System",
+                "everyone",
+                format!(
+                    "The discussion topic has changed from {} to {}.",
+                    previous, topic
+                ),
+            )
+            .at_tick(self.current_tick)
+            .in_room(Some(room.to_string()));
+            self.events
+                .emit(SimulationToUI::MessageUpdate(message.clone()));
+            self.messages.push(message);
+        }
+        self.events.emit(SimulationToUI::StateUpdate(format!(
+            "Topic of room {} set: {}",
+            room, topic
+        )));
+
+        if let Some(starter) = self.choose_starter_in(Some(&config)) {
+            self.open_discussion(&starter, Some(room.to_string()), topic);
+        }
+    }
+
+    /// Takes an agent out of the simulation; what it said stays in the history.
+    fn remove_agent(&mut self, name: &str) {
+        let Some(id) = self
+            .agents
+            .iter()
+            .find(|(_, agent)| agent.name == name)
+            .map(|(id, _)| id.clone())
+        else {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Agent '{}' not found",
+                name
+            )));
+            return;
+        };
+
+        self.agents.remove(&id);
+        if let Ok(mut names) = self.agent_names.write() {
+            names.remove(name);
+        }
+        self.conversation_manager.remove_participant(name);
+        if self.focused_agent.as_deref() == Some(name) {
+            self.focused_agent = None;
+        }
+
+        // Replies the agent already wrote are never delivered, and its turn state goes with it
+        self.scheduled_messages
+            .retain(|(_, message)| message.sender != name);
+        self.last_spoke.remove(name);
+        self.last_prompts.remove(name);
+        if self
+            .floor
+            .as_ref()
+            .is_some_and(|(holder, _)| holder == name)
+        {
+            self.floor = None;
+        }
+        self.events
+            .emit(SimulationToUI::AgentLeft(name.to_string()));
+    }
+
+    /// Applies the profiles saved in a file: agents with a matching name take on the saved
+    /// personality, and the others join the simulation.
+    ///
+    /// Names are trimmed and checked like configured ones; profiles with an empty, reserved
+    /// or repeated name, or the moderator's or observer's, are rejected and reported.
+    ///
+    /// # Arguments
+    /// * `path` - The profile file, written by the `profiles save` command.
+    fn load_profiles(&mut self, path: &str) {
+        let profiles = match load_profiles(Path::new(path)) {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Failed to load profiles: {}",
+                    e
+                )));
+                return;
+            }
+        };
+
+        let (mut updated, mut added) = (0, 0);
+        let mut rejected = Vec::new();
+        let mut seen: Vec<String> = Vec::new();
+        for mut profile in profiles {
+            profile.name = profile.name.trim().to_string();
+            let check = match self.config.check_agent_name(&profile.name) {
+                Err(e) => Err(e.to_string()),
+                Ok(()) if seen.contains(&profile.name) => {
+                    Err(format!("'{}' appears more than once", profile.name))
+                }
+                Ok(()) => Ok(()),
+            };
+            if let Err(reason) = check {
+                rejected.push(reason);
+                continue;
+            }
+            seen.push(profile.name.clone());
+
+            if let Some(agent) = self.agents.values_mut().find(|a| a.name == profile.name) {
+                agent.personality = profile.personality;
+                updated += 1;
+                continue;
+            }
+
+            // New agents start like configured ones, with the saved personality
+            let agent_config = AgentConfig {
+                name: profile.name.clone(),
+                personality_template: "custom".to_string(),
+                initial_energy: self.config.world.energy_cap.min(100.0),
+                initial_position: (0, 0),
+                model: None,
+                talkativeness: None,
+                max_tokens: None,
+                context_tokens: None,
+                verbosity: Default::default(),
+                avatar: None,
+                color: None,
+                goal: None,
+                interests: Vec::new(),
+                expertise: Vec::new(),
+                language: None,
+                kind: AgentKind::Persona,
+                tool: None,
+                personality: Some(profile.personality),
+            };
+            self.add_agent(agent_config);
+            added += 1;
+        }
+
+        let mut status = format!(
+            "Loaded profiles from {}: {} updated, {} added",
+            path, updated, added
+        );
+        if !rejected.is_empty() {
+            status.push_str(&format!(
+                ", {} rejected ({})",
+                rejected.len(),
+                rejected.join("; ")
+            ));
+        }
+        self.events.emit(SimulationToUI::StateUpdate(status));
+    }
+
+    /// Adds an agent to the simulation, as if it had been configured from the start.
+    ///
+    /// # Arguments
+    /// * `agent_config` - The configuration of the new agent.
+    fn add_agent(&mut self, agent_config: AgentConfig) {
+        let model = self
+            .config
+            .ollama_model
+            .clone()
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        let agent = build_agent(
+            &self.config,
+            &agent_config,
+            &model,
+            &self.backend,
+            self.response_cache.clone(),
+            &self.global_memory,
+        );
+
+        self.conversation_manager.add_participant(&agent.name);
+        if let Ok(mut names) = self.agent_names.write() {
+            names.insert(agent.name.clone());
+        }
+        self.events.emit(SimulationToUI::AgentJoined(
+            agent.name.clone(),
+            agent.ollama_model.clone(),
+        ));
+        self.events.emit(SimulationToUI::AgentUpdate(
+            agent.name.clone(),
+            agent.state.clone(),
+            agent.energy,
+            agent.mood,
+        ));
+        self.agents.insert(Uuid::new_v4().to_string(), agent);
+        self.config.agents.push(agent_config);
+    }
+
+    /// Bundles the current cast, world settings, and topic into a scene.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the scene.
+    ///
+    /// # Returns
+    /// * The scene, with every agent's current personality.
+    fn scene(&self, name: &str) -> Scene {
+        let agents = self
+            .config
+            .agents
+            .iter()
+            .filter_map(|agent_config| {
+                let agent = self.agents.values().find(|a| a.name == agent_config.name)?;
+                Some(AgentConfig {
+                    personality: Some(agent.personality.clone()),
+                    ..agent_config.clone()
+                })
+            })
+            .collect();
+        Scene {
+            name: name.to_string(),
+            topic: self.discussion_topic.clone(),
+            agents,
+            world: Some(self.config.world.clone()),
+        }
+    }
+
+    /// Replaces the cast and world settings with those of a saved scene, then sets its topic.
+    ///
+    /// The scene is validated like a configuration first, so an invalid one changes nothing.
+    ///
+    /// # Arguments
+    /// * `path` - The scene file, written by the `scene save` command or by hand.
+    fn load_scene(&mut self, path: &str) {
+        let scene = match Scene::load(Path::new(path)) {
+            Ok(scene) => scene,
+            Err(e) => {
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "Failed to load scene: {}",
+                    e
+                )));
+                return;
+            }
+        };
+
+        // The scene's agents replace any generated ones, so only its own names can clash
+        let mut candidate = self.config.clone();
+        candidate.agents = scene.agents.clone();
+        candidate.procedural_agents = None;
+        if let Some(world) = &scene.world {
+            candidate.world = world.clone();
+        }
+        candidate.trim_names();
+        if let Err(e) = candidate.validate() {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Invalid scene {}: {}",
+                path, e
+            )));
+            return;
+        }
+
+        let names: Vec<String> = self.agents.values().map(|a| a.name.clone()).collect();
+        for name in names {
+            self.remove_agent(&name);
+        }
+        self.config.world = candidate.world;
+        self.config.agents.clear();
+        let count = candidate.agents.len();
+        for agent_config in candidate.agents {
+            self.add_agent(agent_config);
+        }
+
+        // Nothing said or measured for the previous cast carries over to the new one
+        self.scheduled_messages.clear();
+        self.messages.clear();
+        self.last_spoke.clear();
+        self.goal_progress.clear();
+        self.throughput.clear();
+        self.room_topics = room_topics(&self.config.rooms);
+        self.memory_index = MemoryIndex::default();
+        self.floor = None;
+        self.idle_ticks = 0;
+        self.flooded_ticks = 0;
+
+        let name = if scene.name.is_empty() {
+            path
+        } else {
+            &scene.name
+        };
+        self.events.emit(SimulationToUI::StateUpdate(format!(
+            "Loaded scene '{}' with {} agent{}",
+            name,
+            count,
+            if count == 1 { "" } else { "s" }
+        )));
+        if let Some(topic) = scene.topic {
+            self.handle_command(UIToSimulation::SetDiscussionTopic(topic));
+        }
+    }
+
+    /// Mutes or unmutes an agent, keeping what it hears meanwhile.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the agent.
+    /// * `muted` - Whether the agent should stay silent.
+    fn set_muted(&mut self, name: &str, muted: bool) {
+        let Some(agent) = self.agents.values_mut().find(|a| a.name == name) else {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Agent '{}' not found",
+                name
+            )));
+            return;
+        };
+
+        agent.muted = muted;
+        let status = if muted {
+            format!("{} is muted", name)
+        } else if agent.next_prompt.is_empty() {
+            format!("{} is unmuted", name)
+        } else {
+            format!(
+                "{} is unmuted and will answer what it heard meanwhile",
+                name
+            )
+        };
+        self.events
+            .emit(SimulationToUI::AgentMuted(name.to_string(), muted));
+        self.events.emit(SimulationToUI::StateUpdate(status));
+    }
+
+    /// Sends the UI the last prompt generated for an agent.
+    ///
+    /// Prompts are only kept in debug mode.
+    fn inspect(&self, name: &str) {
+        let update = if !self.config.debug {
+            SimulationToUI::StateUpdate(
+                "Enable debug in config.json to inspect prompts".to_string(),
+            )
+        } else if let Some(prompt) = self.last_prompts.get(name) {
+            // Current traits first, since they may have drifted since the prompt was sent
+            let traits = self
+                .agents
+                .values()
+                .find(|a| a.name == name)
+                .map(|a| format!("Current traits: {}\n\n", a.personality.describe()))
+                .unwrap_or_default();
+            SimulationToUI::Prompt(name.to_string(), format!("{}{}", traits, prompt))
+        } else {
+            SimulationToUI::StateUpdate(format!("No prompt sent for {} yet", name))
+        };
+        self.events.emit(update);
+    }
+
+    /// Makes every agent notice an event, without changing the discussion topic.
+    ///
+    /// # Arguments
+    /// * `event` - Description of what happens (e.g. "an alarm sounds").
+    fn world_event(&mut self, event: &str) {
+        let message = Message::new(WORLD_SENDER, "everyone", event).at_tick(self.current_tick);
+
+        // Framed apart from the conversation so agents react to it rather than reply to it
+        for agent in self.agents.values_mut() {
+            agent
+                .next_prompt
+                .push_str(&format!("An event occurs: {}\n", event));
+        }
+
+        self.record_message(&message);
+        self.events.emit(SimulationToUI::MessageUpdate(message));
+    }
+
+    /// Changes a personality trait of a live agent, clamped to 0-1.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the agent.
+    /// * `trait_name` - One of the Big Five traits.
+    /// * `value` - The new value of the trait.
+    fn set_trait(&mut self, name: &str, trait_name: &str, value: f32) {
+        let status = match self.agents.values_mut().find(|a| a.name == name) {
+            None => format!("Agent '{}' not found", name),
+            Some(agent) => match agent.personality.trait_mut(trait_name) {
+                None => format!(
+                    "Unknown trait '{}'. Use one of: {}",
+                    trait_name,
+                    TRAIT_NAMES.join(", ")
+                ),
+                Some(current) => {
+                    let previous = *current;
+                    *current = value.clamp(0.0, 1.0);
+                    format!(
+                        "{}'s {} changed from {:.2} to {:.2}",
+                        name, trait_name, previous, *current
+                    )
+                }
+            },
+        };
+        self.events.emit(SimulationToUI::StateUpdate(status));
+    }
+
+    /// Handles user messages and passes them to the relevant agent.
+    fn handle_user_message(&mut self, recipient: &str, content: &str) {
+        // Create a user message
+        let user_message = Message::new("User", recipient, content).at_tick(self.current_tick);
+
+        // Notify the UI about the user message
+        self.events
+            .emit(SimulationToUI::MessageUpdate(user_message.clone()));
+
+        // Add to the conversation history
+        self.record_message(&user_message);
+
+        // Add the message to the recipient agent's next prompt for immediate processing
+        if let Some(agent) = self.agents.values_mut().find(|a| a.name == recipient) {
+            agent
+                .next_prompt
+                .push_str(&format!("[User→{}]: {}\n", recipient, content));
+
+            // A muted agent answers once unmuted
+            if agent.muted {
+                self.events.emit(SimulationToUI::StateUpdate(format!(
+                    "{} is muted and will answer once unmuted",
+                    recipient
+                )));
+                return;
+            }
+
+            // Process the response immediately
+            agent.state = AgentState::Thinking;
+            self.events.emit(SimulationToUI::AgentUpdate(
+                agent.name.clone(),
+                agent.state.clone(),
+                agent.energy,
+                agent.mood,
+            ));
+
+            // Store the agent's name for later use
+            let agent_name = agent.name.clone();
+
+            // Generate a response
+            let speaker = agent.clone();
+            let response_result = self.generate_response(&speaker);
+            let skipped = matches!(
+                response_result,
+                None | Some(Err(Error::EmptyResponse | Error::RepeatedResponse))
+            );
+
+            // Release the agent lock once we're done
+            match response_result {
+                Some(Ok(response_text)) => {
+                    let mut response_message =
+                        Message::new(&agent_name, "User", response_text.as_str())
+                            .at_tick(self.current_tick)
+                            .in_room(speaker.room.clone());
+                    self.tag_sentiment(&speaker, &mut response_message);
+
+                    // Keep the reply in the history, like replies given during a tick
+                    self.record_message(&response_message);
+
+                    // Notify the UI about the agent's response
+                    self.events
+                        .emit(SimulationToUI::MessageUpdate(response_message));
+
+                    // Update the state of other agents, unless the exchange is a 1:1 one
+                    let room = speaker.room.as_deref();
+                    for (_, other_agent) in self.agents.iter_mut() {
+                        if other_agent.name != agent_name
+                            && self.focused_agent.is_none()
+                            && can_hear(&self.config.rooms, &other_agent.name, room)
+                        {
+                            other_agent
+                                .next_prompt
+                                .push_str(&format!("[{}→User]: {}\n", agent_name, response_text));
+                        }
+                    }
+
+                    // Update the agent's state with the new energy level
+                    if let Some(agent) = self.agents.values_mut().find(|a| a.name == agent_name) {
+                        agent.archive_prompt();
+                        agent.remember(format!("[{}→User]: {}", agent_name, response_text));
+                        agent.record_response(&response_text);
+                        agent.opening = false;
+                        agent.state = AgentState::Speaking;
+                        agent.energy -= 1.0;
+                        self.events.emit(SimulationToUI::AgentUpdate(
+                            agent.name.clone(),
+                            agent.state.clone(),
+                            agent.energy,
+                            agent.mood,
+                        ));
+                    }
+                }
+                Some(Err(e)) => {
+                    self.events
+                        .emit(SimulationToUI::StateUpdate(describe_failure(
+                            &agent_name,
+                            &e,
+                        )));
+                }
+                None => {}
+            }
+
+            // Clear the prompt for the next turn, keeping what was heard in memory
+            if let Some(agent) = self.agents.values_mut().find(|a| a.name == agent_name) {
+                agent.archive_prompt();
+                if skipped {
+                    agent.state = AgentState::Idle;
+                    self.events.emit(SimulationToUI::AgentUpdate(
+                        agent.name.clone(),
+                        agent.state.clone(),
+                        agent.energy,
+                        agent.mood,
+                    ));
+                }
+            }
+        } else {
+            self.events.emit(SimulationToUI::StateUpdate(format!(
+                "Agent '{}' not found.",
+                recipient
             )));
         }
     }
-}
+}
+
+/// Explains why an agent failed to respond, with a hint on how to fix it when possible.
+fn describe_failure(agent: &str, error: &Error) -> String {
+    match error {
+        Error::ModelNotFound(model) => format!(
+            "{} cannot respond: model '{}' is not installed (try `ollama pull {}`)",
+            agent, model, model
+        ),
+        Error::OllamaConnection(_) => format!(
+            "{} cannot respond: Ollama is unreachable (is `ollama serve` running?)",
+            agent
+        ),
+        Error::Timeout => format!("{} took too long to respond", agent),
+        Error::EmptyResponse => format!("{} gave an empty response and skips its turn", agent),
+        Error::RepeatedResponse => format!("{} kept repeating itself and skips its turn", agent),
+        e => format!("{} failed to respond: {}", agent, e),
+    }
+}
+
+/// Tells whether an agent hears messages sent in a room.
+///
+/// # Arguments
+/// * `rooms` - The configured rooms.
+/// * `agent` - Name of the agent.
+/// * `room` - The room the message was sent in, or `None` if it was sent to every room.
+fn can_hear(rooms: &[RoomConfig], agent: &str, room: Option<&str>) -> bool {
+    let Some(room) = room else {
+        return true;
+    };
+    rooms
+        .iter()
+        .any(|r| r.name == room && r.agents.iter().any(|a| a == agent))
+}
+
+/// What an agent's turn produced: its response, and the output size and duration of each
+/// generation it took.
+struct Generated {
+    response: Result<String, Error>,
+    attempts: Vec<(usize, Duration)>,
+}
+
+/// Generates an agent's response, cleaned up by the post-processing steps.
+///
+/// An empty response is retried once. With a `repetition_threshold`, a response too close to
+/// one of the agent's latest ones is retried once with a nudge, then reported as repeated.
+///
+/// # Arguments
+/// * `agent` - The agent, with its memories recalled and its context fitted.
+/// * `topic` - The topic of the agent's room, if any.
+/// * `steps` - The post-processing steps.
+/// * `repetition_threshold` - Similarity from which a response counts as repeated.
+/// * `slots` - The model server's request slots.
+async fn respond(
+    agent: Agent,
+    topic: Option<String>,
+    steps: PostProcessConfig,
+    repetition_threshold: Option<f32>,
+    slots: Arc<Semaphore>,
+) -> Generated {
+    let mut attempts = Vec::new();
+    let topic = topic.as_deref();
+
+    // Models occasionally return nothing; ask once more before skipping the turn
+    let is_empty =
+        |response: &Result<String, Error>| matches!(response, Ok(text) if text.trim().is_empty());
+    let mut response = generate_once(&agent, topic, &steps, &slots, &mut attempts).await;
+    if is_empty(&response) {
+        response = generate_once(&agent, topic, &steps, &slots, &mut attempts).await;
+    }
+    if is_empty(&response) {
+        return Generated {
+            response: Err(Error::EmptyResponse),
+            attempts,
+        };
+    }
+
+    // An agent echoing one of its latest responses is asked once more not to, before
+    // skipping the turn
+    if let Some(threshold) = repetition_threshold {
+        let repeats = |response: &Result<String, Error>| matches!(response, Ok(text) if agent.repeats(text, threshold));
+        if repeats(&response) {
+            let mut nudged = agent.clone();
+            nudged.prompt_suffix = if nudged.prompt_suffix.is_empty() {
+                prompt::REPETITION_NUDGE.to_string()
+            } else {
+                format!("{}\n\n{}", nudged.prompt_suffix, prompt::REPETITION_NUDGE)
+            };
+            response = generate_once(&nudged, topic, &steps, &slots, &mut attempts).await;
+            if repeats(&response) || is_empty(&response) {
+                response = Err(Error::RepeatedResponse);
+            }
+        }
+    }
+    Generated { response, attempts }
+}
+
+/// Runs a single generation for an agent once a request slot is free, noting its output size
+/// and duration and cleaning up the response.
+async fn generate_once(
+    agent: &Agent,
+    topic: Option<&str>,
+    steps: &PostProcessConfig,
+    slots: &Arc<Semaphore>,
+    attempts: &mut Vec<(usize, Duration)>,
+) -> Result<String, Error> {
+    let (text, elapsed) = limit_concurrency(slots.clone(), async {
+        let started = Instant::now();
+        let text = agent.generate_response_from_prompt(topic).await?;
+        Ok((text, started.elapsed()))
+    })
+    .await?;
+    attempts.push((estimate_tokens(&text), elapsed));
+    Ok(postprocess(steps, &text, &agent.name))
+}
+
+/// Holds a generation until one of the model server's request slots is free.
+///
+/// The slot is released when the generation ends or is aborted, letting the next one in.
+async fn limit_concurrency<F, T>(slots: Arc<Semaphore>, generation: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let _slot = slots
+        .acquire_owned()
+        .await
+        .map_err(|e| Error::Generation(e.to_string()))?;
+    generation.await
+}
+
+/// Computes an agent's energy at the end of a tick.
+///
+/// Agents regain `energy_regen` per tick, except idle ones when `idle_decay` is enabled, which
+/// lose that much instead. The result is kept between 0 and `energy_cap`.
+///
+/// # Arguments
+/// * `energy` - The agent's current energy.
+/// * `idle` - Whether the agent did nothing this tick.
+/// * `world` - The world settings holding the energy parameters.
+fn next_energy(energy: f32, idle: bool, world: &WorldConfig) -> f32 {
+    let change = match world.idle_decay {
+        Some(decay) if idle => -decay,
+        _ => world.energy_regen,
+    };
+    (energy + change).clamp(0.0, world.energy_cap)
+}
+
+/// Picks how many ticks an agent waits before its reply is delivered.
+///
+/// More conscientious agents lean toward the upper end of the range, taking longer to reply.
+fn reply_delay((min, max): (u64, u64), conscientiousness: f32, rng: &mut StdRng) -> u64 {
+    if max <= min {
+        return min;
+    }
+    let weight = ((rng.gen::<f32>() + conscientiousness) / 2.0).clamp(0.0, 1.0);
+    min + ((max - min) as f32 * weight).round() as u64
+}
+
+/// Finds the agent a reply is addressed to by its opening "@Name" or "Name,".
+///
+/// # Arguments
+/// * `text` - The generated reply.
+/// * `names` - The names of the agents in the simulation.
+///
+/// # Returns
+/// * The longest matching name, or `None` when the reply does not open with one.
+fn addressed_agent<'a>(text: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let text = text.trim_start();
+    let (text, mention) = match text.strip_prefix('@') {
+        Some(rest) => (rest, true),
+        None => (text, false),
+    };
+
+    names
+        .filter(|name| match text.strip_prefix(*name) {
+            // "@Bob" only needs the name to end; "Bob" must be followed by a comma
+            Some(rest) if mention => !rest.starts_with(|c: char| c.is_alphanumeric()),
+            Some(rest) => rest.starts_with(','),
+            None => false,
+        })
+        .max_by_key(|name| name.len())
+}
+
+/// Collects the topics set on rooms in the configuration.
+///
+/// # Arguments
+/// * `rooms` - The configured rooms.
+///
+/// # Returns
+/// * The topic of each room that has one, keyed by room name.
+fn room_topics(rooms: &[RoomConfig]) -> HashMap<String, String> {
+    rooms
+        .iter()
+        .filter_map(|room| Some((room.name.clone(), room.topic.clone()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::MOOD_SMOOTHING;
+    use crate::backend::{EmbeddingFuture, GenerationFuture, HealthFuture};
+    use crate::config::{EmbeddingsConfig, OpeningMessage};
+    use crate::state::Mood;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn setup_simulation() -> (Simulation, Sender<UIToSimulation>, Receiver<SimulationToUI>) {
+        setup_simulation_with(mock_config())
+    }
+
+    /// Returns the default configuration on the mock backend, without autosave.
+    fn mock_config() -> Config {
+        let mut config = Config::default();
+        config.backend = BackendKind::Mock;
+        config.autosave = false;
+        config
+    }
+
+    fn setup_simulation_with(
+        config: Config,
+    ) -> (Simulation, Sender<UIToSimulation>, Receiver<SimulationToUI>) {
+        let (ui_tx, ui_rx) = mpsc::channel();
+        let (sim_tx, sim_rx) = mpsc::channel();
+        let simulation = Simulation::new(config, ui_tx, sim_rx);
+        (simulation, sim_tx, ui_rx)
+    }
+
+    /// Runs a seeded simulation on the mock backend for a set number of ticks on a topic.
+    ///
+    /// # Arguments
+    /// * `config` - The configuration, usually from `mock_config`; the seed and tick limit are
+    ///   set here.
+    /// * `topic` - The discussion topic set before starting.
+    /// * `ticks` - Number of ticks to run.
+    ///
+    /// # Returns
+    /// * The stopped simulation, with its conversation manager and agents left for assertions.
+    fn run_for_ticks(mut config: Config, topic: &str, ticks: u64) -> Simulation {
+        config.seed = config.seed.or(Some(7));
+        config.world.max_ticks = Some(ticks);
+        let (mut simulation, sim_tx, _ui_rx) = setup_simulation_with(config);
+
+        sim_tx
+            .send(UIToSimulation::SetDiscussionTopic(topic.to_string()))
+            .unwrap();
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        simulation.run();
+        simulation
+    }
+
+    #[test]
+    fn test_message_count_grows_with_ticks() {
+        let short = run_for_ticks(mock_config(), "cities", 3);
+        let long = run_for_ticks(mock_config(), "cities", 8);
+
+        let short_count = short.conversation_manager.messages().len();
+        assert!(short_count > 0);
+        assert!(long.conversation_manager.messages().len() > short_count);
+    }
+
+    #[test]
+    fn test_speaking_costs_energy() {
+        let simulation = run_for_ticks(mock_config(), "cities", 4);
+
+        assert!(!simulation.last_spoke.is_empty());
+        for agent in simulation.agents.values() {
+            if simulation.last_spoke.contains_key(&agent.name) {
+                assert!(agent.energy < 100.0, "{} spoke for free", agent.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_history_stays_bounded() {
+        let simulation = run_for_ticks(mock_config(), "cities", 12);
+
+        for agent in simulation.agents.values() {
+            assert!(agent.conversation_history.len() <= crate::agent::MAX_HISTORY);
+        }
+        assert!(simulation
+            .agents
+            .values()
+            .any(|a| a.conversation_history.len() == crate::agent::MAX_HISTORY));
+    }
+
+    #[test]
+    fn test_tick_updates() {
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation();
+        sim_tx.send(UIToSimulation::Start).unwrap();
+
+        thread::spawn(move || {
+            simulation.run();
+        });
+
+        // Skip the agent registrations sent before the first tick
+        let response = loop {
+            match ui_rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(SimulationToUI::AgentJoined(_, _)) => continue,
+                other => break other,
+            }
+        };
+        assert!(matches!(response, Ok(SimulationToUI::TickUpdate(_))));
+    }
+
+    #[test]
+    fn test_http_commands_do_not_keep_simulation_alive() {
+        let (mut simulation, sim_tx, _ui_rx) = setup_simulation();
+        let (server_tx, server_rx) = mpsc::channel();
+        simulation.server_rx = Some(server_rx);
+
+        // The names the server accepts messages for follow agents leaving and joining
+        simulation.remove_agent("Bob");
+        simulation.add_agent(AgentConfig {
+            name: "Dave".to_string(),
+            ..simulation.config.agents[0].clone()
+        });
+        let mut names: Vec<String> = simulation
+            .agent_names
+            .read()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Alice", "Charlie", "Dave"]);
+
+        server_tx
+            .send(UIToSimulation::UserMessage(
+                "Dave".to_string(),
+                "Hi".to_string(),
+            ))
+            .unwrap();
+        let Ok(UIToSimulation::UserMessage(name, _)) = simulation.try_recv_command() else {
+            panic!("HTTP command not received");
+        };
+        assert_eq!(name, "Dave");
+
+        // The UI going away stops the simulation even though the server is still up
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        drop(sim_tx);
+        simulation.run();
+        assert!(!simulation.running);
+        drop(server_tx);
+    }
+
+    #[test]
+    fn test_step_runs_single_tick_while_paused() {
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation();
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        sim_tx.send(UIToSimulation::Pause).unwrap();
+        sim_tx.send(UIToSimulation::Step).unwrap();
+
+        let handle = thread::spawn(move || {
+            simulation.run();
+        });
+
+        // The step ticks once, then the simulation stays paused
+        thread::sleep(Duration::from_millis(500));
+        sim_tx.send(UIToSimulation::Stop).unwrap();
+        handle.join().unwrap();
+
+        let ticks: Vec<u64> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::TickUpdate(tick) => Some(tick),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ticks, [1]);
+    }
+
+    #[test]
+    fn test_max_ticks_ends_simulation() {
+        let mut config = mock_config();
+        config.world.max_ticks = Some(3);
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation_with(config);
+
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        simulation.run();
+
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        let last_tick = updates.iter().rev().find_map(|update| match update {
+            SimulationToUI::TickUpdate(tick) => Some(*tick),
+            _ => None,
+        });
+        assert_eq!(last_tick, Some(3));
+        assert!(matches!(
+            updates.last(),
+            Some(SimulationToUI::StateUpdate(status)) if status.starts_with("Simulation ended")
+        ));
+    }
+
+    #[test]
+    fn test_idle_ticks_count_once_conversation_started() {
+        let mut config = mock_config();
+        config.world.max_idle_ticks = Some(2);
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+
+        for _ in 0..3 {
+            simulation.tick();
+        }
+        assert_eq!(simulation.check_end_conditions(), None);
+
+        simulation.record_message(&Message::new("User", "Alice", "Hello?"));
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 0.0;
+        }
+        simulation.tick();
+        simulation.tick();
+        assert_eq!(
+            simulation.check_end_conditions().as_deref(),
+            Some("all agents idle for 2 ticks")
+        );
+    }
+
+    #[test]
+    fn test_final_status_names_saved_session_file() {
+        let dir = std::env::temp_dir().join(format!("protopolis-test-{}", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.autosave = true;
+        config.save_dir = dir.to_string_lossy().into_owned();
+        config.snapshot.path = dir.join("autosave.json").to_string_lossy().into_owned();
+        config.world.max_ticks = Some(1);
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation_with(config);
+
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        simulation.run();
+        let status = ui_rx.try_iter().last();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let Some(SimulationToUI::StateUpdate(status)) = status else {
+            panic!("no final status");
+        };
+        let saved = status.split(", session saved to ").nth(1).unwrap();
+        assert!(Path::new(saved).starts_with(&dir));
+        assert!(saved.ends_with(".json"));
+    }
+
+    #[test]
+    fn test_moderator_speaks_on_its_cadence() {
+        let mut config = mock_config();
+        config.world.max_ticks = Some(5);
+        config.world.moderator_interval = 2;
+        config.moderator = Some(crate::config::ModeratorConfig::default());
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation_with(config);
+
+        sim_tx
+            .send(UIToSimulation::SetDiscussionTopic("cities".to_string()))
+            .unwrap();
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        simulation.run();
+
+        let interventions = ui_rx
+            .try_iter()
+            .filter(|update| {
+                matches!(update, SimulationToUI::MessageUpdate(m) if m.sender == "Moderator")
+            })
+            .count();
+        assert_eq!(interventions, 2);
+    }
+
+    #[test]
+    fn test_observer_summarizes_without_being_heard() {
+        let mut config = mock_config();
+        config.observer = Some(crate::config::ObserverConfig {
+            interval: 2,
+            ..Default::default()
+        });
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        simulation.record_message(&Message::new("Bob", "Alice", "Hi"));
+
+        simulation.tick();
+        simulation.tick();
+        let summaries: Vec<Message> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::MessageUpdate(m) if m.sender == "Observer" => Some(m),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].recipient, "User");
+        assert!(simulation.messages.iter().all(|m| m.sender != "Observer"));
+        assert!(simulation.agents.values().all(|a| a.next_prompt.is_empty()));
+        assert!(simulation
+            .conversation_manager
+            .messages()
+            .iter()
+            .all(|m| m.sender != "Observer"));
+    }
+
+    #[test]
+    fn test_ui_receives_messages_in_timestamp_order() {
+        let mut config = mock_config();
+        config.seed = Some(7);
+        config.world.max_ticks = Some(8);
+        config.world.moderator_interval = 3;
+        config.world.reply_delay_ticks = Some((0, 2));
+        config.moderator = Some(crate::config::ModeratorConfig::default());
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation_with(config);
+
+        sim_tx
+            .send(UIToSimulation::SetDiscussionTopic("cities".to_string()))
+            .unwrap();
+        sim_tx.send(UIToSimulation::Start).unwrap();
+        simulation.run();
+
+        let messages: Vec<Message> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::MessageUpdate(message) => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert!(messages.len() > 5);
+        assert!(messages
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        let mut ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), messages.len());
+    }
+
+    #[test]
+    fn test_wait_estimate_follows_recent_generations() {
+        let mut throughput = Throughput::default();
+        throughput.record(10, Duration::from_secs(2));
+        assert_eq!(throughput.rolling_seconds, 2.0);
+        throughput.record(10, Duration::from_secs(4));
+        assert!((throughput.rolling_seconds - (2.0 + LATENCY_SMOOTHING * 2.0)).abs() < 1e-9);
+
+        let mut config = mock_config();
+        config.seed = Some(7);
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        simulation
+            .messages
+            .push(Message::new("User", "everyone", "Hello all"));
+        simulation.tick();
+        assert!(ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::WaitEstimate(_, seconds) if seconds >= 0.0
+        )));
+    }
+
+    #[test]
+    fn test_user_message_gets_reply_addressed_to_user() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+
+        simulation.handle_user_message("Alice", "Hello Alice");
+
+        let replies: Vec<Message> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::MessageUpdate(m) if m.sender == "Alice" => Some(m),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].recipient, "User");
+
+        let history = simulation.conversation_manager.messages();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].id, replies[0].id);
+    }
+
+    #[test]
+    fn test_user_message_to_missing_agent_is_reported() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+
+        simulation.handle_user_message("Nobody", "Hello?");
+
+        assert!(ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::StateUpdate(status) if status == "Agent 'Nobody' not found."
+        )));
+    }
+
+    #[test]
+    fn test_focus_on_missing_agent_is_rejected() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+
+        simulation.handle_command(UIToSimulation::Focus("Nobody".to_string()));
+
+        assert_eq!(simulation.focused_agent, None);
+        assert!(ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::StateUpdate(status) if status == "Agent 'Nobody' not found"
+        )));
+    }
+
+    #[test]
+    fn test_user_message_reply_reaches_other_agents() {
+        let mut config = mock_config();
+        config.mock_responses = vec!["Hi there".to_string()];
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+
+        simulation.handle_user_message("Alice", "Hello Alice");
+
+        for agent in simulation.agents.values() {
+            if agent.name == "Alice" {
+                assert!(agent.next_prompt.is_empty());
+            } else {
+                assert_eq!(agent.next_prompt, "[Alice→User]: Hi there\n");
+            }
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::mpsc;
-    use std::time::Duration;
+    #[test]
+    fn test_starter_policy_is_deterministic() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
 
-    fn setup_simulation() -> (Simulation, Sender<UIToSimulation>, Receiver<SimulationToUI>) {
-        let config = Config::default(); // Ensure you have a default implementation for testing
-        let (ui_tx, ui_rx) = mpsc::channel();
-        let (sim_tx, sim_rx) = mpsc::channel();
-        let simulation = Simulation::new(config, ui_tx, sim_rx);
-        (simulation, sim_tx, ui_rx)
+        assert_eq!(simulation.choose_starter().as_deref(), Some("Alice"));
+
+        simulation.config.world.starter = StarterPolicy::Rotate;
+        simulation.start_conversation("cities");
+        assert_eq!(simulation.choose_starter().as_deref(), Some("Bob"));
+
+        simulation.config.world.starting_agent = Some("Charlie".to_string());
+        assert_eq!(simulation.choose_starter().as_deref(), Some("Charlie"));
     }
 
     #[test]
-    fn test_tick_updates() {
-        let (mut simulation, sim_tx, ui_rx) = setup_simulation();
-        sim_tx.send(UIToSimulation::Start).unwrap();
+    fn test_opening_messages_reach_their_recipients() {
+        let mut config = mock_config();
+        config.world.opening_messages = vec![
+            OpeningMessage {
+                recipient: "Alice".to_string(),
+                content: "You argue for {topic}.".to_string(),
+            },
+            OpeningMessage {
+                recipient: "Bob".to_string(),
+                content: "You argue against {topic}.".to_string(),
+            },
+        ];
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
 
-        thread::spawn(move || {
-            simulation.run();
+        simulation.start_conversation("trams");
+
+        let prompt_of = |name: &str| {
+            let agent = simulation.agents.values().find(|a| a.name == name).unwrap();
+            agent.next_prompt.clone()
+        };
+        assert_eq!(prompt_of("Alice"), "[System→Alice]: You argue for trams.\n");
+        assert_eq!(prompt_of("Bob"), "[System→Bob]: You argue against trams.\n");
+        assert!(prompt_of("Charlie").is_empty());
+    }
+
+    #[test]
+    fn test_set_trait_clamps_and_reports() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+
+        simulation.set_trait("Alice", "neuroticism", 1.5);
+        simulation.set_trait("Alice", "grumpiness", 0.5);
+
+        let alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        assert_eq!(alice.personality.neuroticism, 1.0);
+        let statuses: Vec<String> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::StateUpdate(status) => Some(status),
+                _ => None,
+            })
+            .collect();
+        assert!(statuses[0].starts_with("Alice's neuroticism changed from"));
+        assert!(statuses[1].starts_with("Unknown trait 'grumpiness'"));
+    }
+
+    #[test]
+    fn test_event_log_writes_one_json_object_per_line() {
+        let path = std::env::temp_dir().join(format!("protopolis-events-{}.jsonl", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.event_log = Some(path.to_string_lossy().to_string());
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+
+        simulation.start_conversation("cities");
+        simulation.tick();
+        simulation.events.flush_log();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let entries: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries[0]["tick"], 0);
+        assert!(entries[0]["event"]["MessageUpdate"].is_object());
+        assert!(entries
+            .iter()
+            .any(|entry| entry["tick"] == 1 && entry["event"]["TickUpdate"] == 1));
+    }
+
+    #[test]
+    fn test_world_event_reaches_every_agent() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+        simulation.discussion_topic = Some("cities".to_string());
+
+        simulation.handle_command(UIToSimulation::WorldEvent("an alarm sounds".to_string()));
+
+        for agent in simulation.agents.values() {
+            assert_eq!(agent.next_prompt, "An event occurs: an alarm sounds\n");
+        }
+        assert_eq!(simulation.discussion_topic.as_deref(), Some("cities"));
+        assert!(ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::MessageUpdate(m) if m.sender == WORLD_SENDER
+        )));
+    }
+
+    #[test]
+    fn test_inspect_returns_last_prompt_in_debug_mode() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+        simulation.config.debug = true;
+        simulation.handle_user_message("Alice", "Hello there");
+        ui_rx.try_iter().for_each(drop);
+
+        simulation.inspect("Alice");
+
+        let prompt = ui_rx.try_iter().find_map(|update| match update {
+            SimulationToUI::Prompt(name, prompt) if name == "Alice" => Some(prompt),
+            _ => None,
         });
+        assert!(prompt.is_some_and(|p| p.contains("[User→Alice]: Hello there")));
+    }
 
-        let response = ui_rx.recv_timeout(Duration::from_secs(1));
-        assert!(matches!(response, Ok(SimulationToUI::TickUpdate(_))));
+    #[test]
+    fn test_memory_is_synthesized_and_shared() {
+        let mut config = mock_config();
+        config.mock_responses = vec!["- Trams are quiet\n- Bob lives downtown".to_string()];
+        config.world.memory_interval = Some(1);
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        simulation.current_tick = 1;
+        simulation.conversation_manager.add_message(Message::new(
+            "Alice",
+            "Bob",
+            "Trams are quiet",
+        ));
+
+        simulation.synthesize_memory();
+
+        assert_eq!(simulation.global_memory.facts().len(), 2);
+        let bob = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Bob")
+            .unwrap();
+        assert!(bob.build_prompt(None).contains("- Bob lives downtown"));
+    }
+
+    #[test]
+    fn test_speak_cooldown_keeps_agent_silent() {
+        let mut config = mock_config();
+        config.world.speak_cooldown_ticks = Some(2);
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 1.0;
+        }
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.next_prompt = "[Bob→Alice]: Hi\n".to_string();
+        simulation.last_spoke.insert("Alice".to_string(), 0);
+
+        simulation.tick();
+        simulation.tick();
+        let spoke_early = ui_rx.try_iter().any(
+            |update| matches!(update, SimulationToUI::MessageUpdate(m) if m.sender == "Alice"),
+        );
+        assert!(!spoke_early);
+
+        simulation.tick();
+        assert_eq!(simulation.last_spoke.get("Alice"), Some(&3));
+    }
+
+    #[test]
+    fn test_agents_speak_in_name_order() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        simulation.running = true;
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 1.0;
+            agent.next_prompt = "[User→everyone]: Hi\n".to_string();
+        }
+
+        simulation.tick();
+        let speakers: Vec<&str> = simulation
+            .messages
+            .iter()
+            .map(|m| m.sender.as_str())
+            .collect();
+        assert_eq!(speakers, ["Alice", "Bob", "Charlie"]);
+    }
+
+    #[test]
+    fn test_removed_agent_leaves_roster() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+
+        simulation.remove_agent("Bob");
+        assert!(simulation.agents.values().all(|a| a.name != "Bob"));
+        assert_ne!(
+            simulation
+                .conversation_manager
+                .choose_recipient("Alice", &mut simulation.rng),
+            "Bob"
+        );
+        assert!(ui_rx
+            .try_iter()
+            .any(|update| matches!(update, SimulationToUI::AgentLeft(name) if name == "Bob")));
+    }
+
+    #[test]
+    fn test_removed_agent_scheduled_reply_is_dropped() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+        simulation.running = true;
+        simulation
+            .scheduled_messages
+            .push((1, Message::new("Bob", "Alice", "As I was saying")));
+        simulation.last_spoke.insert("Bob".to_string(), 0);
+        simulation
+            .last_prompts
+            .insert("Bob".to_string(), "prompt".to_string());
+
+        simulation.remove_agent("Bob");
+        assert!(simulation.scheduled_messages.is_empty());
+        assert!(!simulation.last_spoke.contains_key("Bob"));
+        assert!(!simulation.last_prompts.contains_key("Bob"));
+
+        simulation.tick();
+        assert!(!ui_rx
+            .try_iter()
+            .any(|update| matches!(update, SimulationToUI::MessageUpdate(m) if m.sender == "Bob")));
+        assert!(simulation
+            .conversation_manager
+            .messages()
+            .iter()
+            .all(|m| m.sender != "Bob"));
+    }
+
+    #[test]
+    fn test_muted_agent_catches_up_once_unmuted() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+        simulation.running = true;
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 1.0;
+        }
+        simulation.handle_command(UIToSimulation::Mute("Alice".to_string(), true));
+        assert!(ui_rx.try_iter().any(
+            |update| matches!(update, SimulationToUI::AgentMuted(name, true) if name == "Alice")
+        ));
+
+        simulation
+            .messages
+            .push(Message::new("Bob", "Alice", "What do you think?"));
+        simulation.tick();
+        assert!(simulation.messages.iter().all(|m| m.sender != "Alice"));
+        let alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        assert!(alice.next_prompt.contains("What do you think?"));
+
+        simulation.handle_command(UIToSimulation::Mute("Alice".to_string(), false));
+        simulation.messages.clear();
+        simulation.tick();
+        assert!(simulation.messages.iter().any(|m| m.sender == "Alice"));
+    }
+
+    #[test]
+    fn test_summary_is_shared_after_enough_turns() {
+        let mut config = mock_config();
+        config.world.summary.enabled = true;
+        config.world.summary.turns = 4;
+        let simulation = run_for_ticks(config, "cities", 6);
+
+        let summaries = |agent: &Agent| {
+            agent
+                .conversation_history
+                .iter()
+                .filter(|entry| entry.starts_with("[Summary]: "))
+                .count()
+        };
+        assert!(simulation.agents.values().all(|agent| summaries(agent) > 0));
+        assert!(simulation.turns_since_summary < 4);
+    }
+
+    #[test]
+    fn test_generations_wait_for_a_free_slot() {
+        let runtime = Runtime::new().unwrap();
+        let slots = Arc::new(Semaphore::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..6)
+            .map(|_| {
+                let (running, peak) = (running.clone(), peak.clone());
+                runtime.spawn(limit_concurrency(slots.clone(), async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    for _ in 0..10 {
+                        tokio::task::yield_now().await;
+                    }
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(String::new())
+                }))
+            })
+            .collect();
+        for handle in handles {
+            runtime.block_on(handle).unwrap().unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_monologue_is_curbed_until_another_agent_speaks() {
+        let mut config = mock_config();
+        config.world.monologue.enabled = true;
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+
+        let alice = ["Alice".to_string()];
+        simulation.track_floor(&alice);
+        simulation.track_floor(&[]);
+        simulation.track_floor(&alice);
+        assert_eq!(simulation.monologue_streak("Alice"), 2);
+        assert_eq!(simulation.monologue_streak("Bob"), 0);
+        simulation.track_floor(&["Bob".to_string()]);
+        assert_eq!(simulation.monologue_streak("Alice"), 0);
+        assert_eq!(simulation.monologue_streak("Bob"), 1);
+        simulation.track_floor(&["Alice".to_string(), "Bob".to_string()]);
+        assert_eq!(simulation.monologue_streak("Bob"), 0);
+
+        // Budgets shrink by the decay, from the default one when the agent has none
+        assert_eq!(simulation.monologue_budget(None, 0), None);
+        assert_eq!(simulation.monologue_budget(None, 1), Some(105));
+        assert_eq!(simulation.monologue_budget(Some(100), 2), Some(49));
+        assert_eq!(simulation.monologue_budget(Some(100), 10), Some(16));
+        assert_eq!(simulation.monologue_budget(Some(10), 3), Some(10));
+
+        // Holding the floor silences Alice, while Charlie answers and takes it over
+        simulation.config.world.monologue.probability_decay = 0.0;
+        simulation.floor = Some(("Alice".to_string(), 1));
+        simulation.running = true;
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 1.0;
+        }
+        simulation
+            .messages
+            .push(Message::new("Bob", "everyone", "Anyone else?"));
+        simulation.tick();
+        assert!(simulation.messages.iter().all(|m| m.sender != "Alice"));
+        assert_eq!(simulation.monologue_streak("Charlie"), 1);
+    }
+
+    #[test]
+    fn test_flood_pauses_simulation() {
+        let mut config = mock_config();
+        config.world.flood_guard.max_messages_per_tick = 2;
+        config.world.flood_guard.ticks = 2;
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+
+        simulation.guard_against_flood(3);
+        assert!(!simulation.paused);
+        simulation.guard_against_flood(2);
+        simulation.guard_against_flood(3);
+        assert!(!simulation.paused);
+        simulation.guard_against_flood(3);
+        assert!(simulation.paused);
+        assert!(ui_rx.try_iter().any(
+            |update| matches!(update, SimulationToUI::StateUpdate(status) if status.starts_with("Paused"))
+        ));
+    }
+
+    #[test]
+    fn test_every_agent_speaking_does_not_trip_default_flood_guard() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        simulation.running = true;
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 1.0;
+        }
+
+        // A healthy discussion where the whole cast answers every tick keeps running
+        for _ in 0..simulation.config.world.flood_guard.ticks * 2 {
+            for agent in simulation.agents.values_mut() {
+                agent.next_prompt.push_str("[User→everyone]: Go on!\n");
+            }
+            simulation.tick();
+            assert_eq!(simulation.messages.len(), simulation.agents.len());
+        }
+        assert!(!simulation.paused);
+        assert_eq!(simulation.flooded_ticks, 0);
+    }
+
+    #[test]
+    fn test_starter_opens_with_its_own_prompt() {
+        let mut config = mock_config();
+        config.world.starting_agent = Some("Alice".to_string());
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+
+        simulation.start_conversation("cities");
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.talkativeness = 1.0;
+        assert!(alice
+            .build_prompt(Some("cities"))
+            .contains("You are opening the discussion on cities."));
+
+        simulation.tick();
+        simulation.tick();
+        let alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        assert!(!alice.opening);
+        assert!(!alice
+            .build_prompt(Some("cities"))
+            .contains("You are opening the discussion"));
+    }
+
+    #[test]
+    fn test_stats_report_throughput() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        simulation.running = true;
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.talkativeness = 1.0;
+        alice.next_prompt = "[Bob→Alice]: Hi\n".to_string();
+
+        simulation.tick();
+        let stats = simulation.stats();
+        let alice_line = stats
+            .lines()
+            .skip_while(|line| *line != "Throughput per agent:")
+            .find(|line| line.starts_with("  Alice: "))
+            .unwrap();
+        assert!(alice_line.ends_with("over 1 responses"));
+        assert!(stats.contains("Throughput per model:\n  llama3.2:latest: "));
+    }
+
+    #[test]
+    fn test_generation_waits_for_unreachable_backend() {
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation();
+        let backend = Arc::new(MockBackend::default());
+        simulation.backend = backend.clone();
+
+        backend.set_reachable(false);
+        assert!(!simulation.backend_ready());
+        // Later calls wait for the poll interval instead of checking again
+        backend.set_reachable(true);
+        assert!(!simulation.backend_ready());
+
+        simulation.next_health_check = Instant::now();
+        assert!(simulation.backend_ready());
+
+        let statuses: Vec<String> = ui_rx
+            .try_iter()
+            .filter_map(|update| match update {
+                SimulationToUI::StateUpdate(status) => Some(status),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                "Ollama unreachable, waiting…",
+                "Ollama is reachable again, resuming"
+            ]
+        );
+    }
+
+    /// Backend whose generations take a while, counting how many run at once.
+    #[derive(Debug, Default)]
+    struct SlowBackend {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl ModelBackend for SlowBackend {
+        fn generate<'a>(
+            &'a self,
+            _model: &'a str,
+            _prompt: &'a str,
+            _max_tokens: Option<u32>,
+        ) -> GenerationFuture<'a> {
+            Box::pin(async move {
+                let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok("Slow and steady".to_string())
+            })
+        }
+
+        fn embed<'a>(&'a self, _model: &'a str, _text: &'a str) -> EmbeddingFuture<'a> {
+            Box::pin(async move { Ok(Vec::new()) })
+        }
+
+        fn health_check(&self) -> HealthFuture<'_> {
+            Box::pin(async move { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn test_agents_generate_together_up_to_request_limit() {
+        for limit in [1, 2] {
+            let mut config = mock_config();
+            config.max_concurrent_requests = limit;
+            let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+            let backend = Arc::new(SlowBackend::default());
+            simulation.running = true;
+            for agent in simulation.agents.values_mut() {
+                agent.backend = backend.clone();
+                agent.talkativeness = 1.0;
+                agent.next_prompt.push_str("[User→everyone]: Thoughts?\n");
+            }
+
+            simulation.tick();
+
+            assert_eq!(simulation.messages.len(), 3);
+            assert_eq!(backend.peak.load(Ordering::SeqCst), limit);
+        }
+    }
+
+    #[test]
+    fn test_hanging_health_check_counts_as_unreachable() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        let backend = Arc::new(MockBackend::default());
+        backend.set_stalled(true);
+        simulation.backend = backend;
+
+        let started = Instant::now();
+        assert!(!simulation.backend_ready());
+        assert!(started.elapsed() < HEALTH_CHECK_TIMEOUT * 2);
+        assert!(simulation.backend_unreachable);
+    }
+
+    #[test]
+    fn test_repeated_response_is_retried_then_skipped() {
+        let mut config = mock_config();
+        config.world.repetition_threshold = Some(0.9);
+        config.mock_responses = vec![
+            "Trains are the future.".to_string(),
+            "trains are the  future!".to_string(),
+            "Buses are cheaper.".to_string(),
+        ];
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.talkativeness = 1.0;
+        alice.record_response("Trains are the future.");
+        alice.next_prompt = "[Bob→Alice]: Hi\n".to_string();
+
+        // Both the response and the nudged retry echo what Alice already said
+        simulation.tick();
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        assert!(!updates.iter().any(
+            |update| matches!(update, SimulationToUI::MessageUpdate(m) if m.sender == "Alice")
+        ));
+        assert!(updates.iter().any(|update| matches!(
+            update,
+            SimulationToUI::StateUpdate(status) if status.contains("repeating itself")
+        )));
+        assert_eq!(simulation.throughput["Alice"].responses, 2);
+
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.next_prompt = "[Bob→Alice]: Anything new?\n".to_string();
+        simulation.tick();
+        let alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        assert_eq!(
+            alice.recent_responses.back().map(String::as_str),
+            Some("Buses are cheaper.")
+        );
+    }
+
+    #[test]
+    fn test_empty_response_skips_turn() {
+        let mut config = mock_config();
+        config.mock_responses = vec!["".to_string()];
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        let alice = simulation
+            .agents
+            .values_mut()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        alice.talkativeness = 1.0;
+        alice.next_prompt = "[Bob→Alice]: Hi\n".to_string();
+        let energy = alice.energy;
+
+        simulation.tick();
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        assert!(!updates.iter().any(
+            |update| matches!(update, SimulationToUI::MessageUpdate(m) if m.sender == "Alice")
+        ));
+        assert!(updates.iter().any(|update| matches!(
+            update,
+            SimulationToUI::StateUpdate(status) if status.contains("empty response")
+        )));
+
+        let alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap();
+        assert_eq!(alice.state, AgentState::Idle);
+        assert!(alice.energy >= energy);
+        // The empty response was asked for twice
+        assert_eq!(simulation.throughput["Alice"].responses, 2);
+    }
+
+    #[test]
+    fn test_goal_progress_is_evaluated() {
+        let mut config = mock_config();
+        config.mock_responses = vec!["7/10, the others are warming up to it".to_string()];
+        config.world.goal_check_interval = Some(2);
+        config.agents[0].goal = Some("convince others to adopt solar power".to_string());
+        let name = config.agents[0].name.clone();
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        simulation.record_message(&Message::new("Bob", "Alice", "Solar panels are costly"));
+
+        simulation.current_tick = 1;
+        simulation.evaluate_goals();
+        assert!(simulation.goal_progress.is_empty());
+        assert!(simulation.stats().contains(&format!(
+            "Goal progress:\n  {}: not evaluated yet (convince others to adopt solar power)",
+            name
+        )));
+
+        simulation.current_tick = 2;
+        simulation.evaluate_goals();
+        assert_eq!(simulation.goal_progress.get(&name), Some(&7));
+    }
+
+    #[test]
+    fn test_personality_drifts_with_conflict() {
+        let mut config = mock_config();
+        config.world.personality_drift.enabled = true;
+        config.world.personality_drift.rate = 0.1;
+        config.world.personality_drift.interval = 5;
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        let before = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap()
+            .personality
+            .clone();
+
+        for tick in [1, 3] {
+            simulation.record_message(
+                &Message::new("Bob", "Alice", "That is wrong, I disagree").at_tick(tick),
+            );
+        }
+        simulation.current_tick = 5;
+        simulation.drift_personalities();
+
+        let after = &simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap()
+            .personality;
+        assert!((after.neuroticism - (before.neuroticism + 0.1).min(1.0)).abs() < 1e-6);
+        assert!((after.agreeableness - (before.agreeableness - 0.1).max(0.0)).abs() < 1e-6);
+        assert_eq!(after.openness, before.openness);
+    }
+
+    #[test]
+    fn test_mood_follows_tone_of_exchanges() {
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        simulation.running = true;
+        simulation
+            .messages
+            .push(Message::new("Bob", "everyone", "That is wrong, I disagree"));
+        simulation.tick();
+
+        let mood = |name: &str| {
+            simulation
+                .agents
+                .values()
+                .find(|a| a.name == name)
+                .unwrap()
+                .mood
+        };
+        assert!((mood("Bob") + MOOD_SMOOTHING).abs() < 1e-6);
+        assert!((mood("Alice") + MOOD_SMOOTHING).abs() < 1e-6);
+        assert_eq!(Mood::from_value(mood("Alice")), Mood::Tense);
+    }
+
+    #[test]
+    fn test_snapshot_restores_agents() {
+        let dir = std::env::temp_dir().join(format!("protopolis-test-{}", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.autosave = true;
+        config.snapshot.path = dir.join("autosave.json").to_string_lossy().into_owned();
+        config.snapshot.interval = 2;
+        // Seeded so the talkativeness rolls, and with them the history, are the same every run
+        config.seed = Some(7);
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config.clone());
+        simulation.running = true;
+        simulation.open_discussion("Alice", None, "Urban transport");
+        simulation.tick();
+        assert!(!dir.join("autosave.json").exists());
+        simulation.tick();
+
+        let session = crate::utils::load_conversations(&dir.join("autosave.json")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(session.is_recent(1));
+
+        let (mut resumed, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        assert_eq!(resumed.restore(&session), 3);
+        let alice = |sim: &Simulation| sim.agents.values().find(|a| a.name == "Alice").cloned();
+        let (before, after) = (alice(&simulation).unwrap(), alice(&resumed).unwrap());
+        assert!(!after.conversation_history.is_empty());
+        assert_eq!(after.conversation_history, before.conversation_history);
+        assert_eq!(after.energy, before.energy);
+    }
+
+    #[test]
+    fn test_snapshot_restores_known_facts() {
+        let dir = std::env::temp_dir().join(format!("protopolis-test-{}", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.snapshot.path = dir.join("autosave.json").to_string_lossy().into_owned();
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config.clone());
+        let facts = vec!["Buses are cheap".to_string(), "Trams are fast".to_string()];
+        simulation.global_memory.replace(facts.clone());
+
+        // A reply overdue while the simulation was stopped is saved as due right away
+        simulation.current_tick = 5;
+        simulation
+            .scheduled_messages
+            .push((3, Message::new("Alice", "Bob", "Better late")));
+        simulation.save_snapshot().unwrap();
+        let session = crate::utils::load_conversations(&dir.join("autosave.json")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(session.scheduled_messages[0].0, 0);
+        let (mut resumed, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        resumed.restore(&session);
+
+        assert_eq!(resumed.global_memory.facts(), facts);
+        let alice = resumed.agents.values().find(|a| a.name == "Alice").unwrap();
+        assert!(alice
+            .build_prompt(None)
+            .contains("Known facts:\n- Buses are cheap\n- Trams are fast\n"));
+    }
+
+    #[test]
+    fn test_delayed_reply_speaks_on_delivery_and_survives_stop() {
+        let dir = std::env::temp_dir().join(format!("protopolis-test-{}", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.snapshot.path = dir.join("autosave.json").to_string_lossy().into_owned();
+        let (mut simulation, sim_tx, ui_rx) = setup_simulation_with(config.clone());
+        simulation.current_tick = 4;
+        simulation
+            .scheduled_messages
+            .push((6, Message::new("Alice", "Bob", "Sorry for the wait")));
+
+        // Pending replies are saved in the snapshot and rescheduled when it is restored
+        simulation.save_snapshot().unwrap();
+        let session = crate::utils::load_conversations(&dir.join("autosave.json")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (mut resumed, _resumed_tx, _resumed_rx) = setup_simulation_with(config);
+        resumed.restore(&session);
+        assert_eq!(resumed.scheduled_messages.len(), 1);
+        assert_eq!(resumed.scheduled_messages[0].0, 2);
+
+        // Alice is only shown speaking once her reply goes out, which stopping forces
+        simulation.running = true;
+        simulation.tick();
+        assert!(!ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::AgentUpdate(name, AgentState::Speaking, _, _) if name == "Alice"
+        )));
+        sim_tx.send(UIToSimulation::Stop).unwrap();
+        simulation.run();
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        let speaking = updates.iter().position(|update| {
+            matches!(update, SimulationToUI::AgentUpdate(name, AgentState::Speaking, _, _) if name == "Alice")
+        });
+        let delivered = updates.iter().position(|update| {
+            matches!(update, SimulationToUI::MessageUpdate(m) if m.content.text == "Sorry for the wait")
+        });
+        assert!(speaking.is_some() && speaking < delivered);
+        assert!(simulation.scheduled_messages.is_empty());
+        assert!(simulation
+            .conversation_manager
+            .messages()
+            .iter()
+            .any(|m| m.content.text == "Sorry for the wait"));
+    }
+
+    #[test]
+    fn test_profiles_round_trip_and_add_missing_agents() {
+        let path =
+            std::env::temp_dir().join(format!("protopolis-profiles-{}.json", Uuid::new_v4()));
+        let path_str = path.to_string_lossy().into_owned();
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation();
+        simulation.set_trait("Alice", "openness", 0.9);
+        simulation.handle_command(UIToSimulation::SaveProfiles(path_str.clone()));
+
+        // A different cast picks up Alice's drifted traits and gains Bob and Charlie
+        let mut config = mock_config();
+        config.agents.truncate(1);
+        config.agents[0].personality_template = "cautious".to_string();
+        let (mut other, _sim_tx, ui_rx) = setup_simulation_with(config);
+        other.handle_command(UIToSimulation::LoadProfiles(path_str));
+        std::fs::remove_file(&path).unwrap();
+
+        let personality = |sim: &Simulation, name: &str| {
+            sim.agents
+                .values()
+                .find(|a| a.name == name)
+                .map(|a| a.personality.clone())
+        };
+        assert_eq!(
+            personality(&other, "Alice"),
+            personality(&simulation, "Alice")
+        );
+        assert_eq!(personality(&other, "Bob"), personality(&simulation, "Bob"));
+        assert_eq!(other.agents.len(), 3);
+        assert!(ui_rx.try_iter().any(
+            |update| matches!(update, SimulationToUI::AgentJoined(name, _) if name == "Charlie")
+        ));
+    }
+
+    #[test]
+    fn test_profiles_with_invalid_names_are_rejected() {
+        let path =
+            std::env::temp_dir().join(format!("protopolis-profiles-{}.json", Uuid::new_v4()));
+        let mut config = mock_config();
+        config.moderator = Some(Default::default());
+        let (mut simulation, _sim_tx, ui_rx) = setup_simulation_with(config);
+        let personality = simulation
+            .agents
+            .values()
+            .next()
+            .unwrap()
+            .personality
+            .clone();
+        let profiles: Vec<serde_json::Value> = [" Dave ", "Dave", "system", " ", "Moderator"]
+            .iter()
+            .map(|name| serde_json::json!({ "name": name, "personality": personality }))
+            .collect();
+        std::fs::write(&path, serde_json::to_string(&profiles).unwrap()).unwrap();
+        simulation.handle_command(UIToSimulation::LoadProfiles(
+            path.to_string_lossy().into_owned(),
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(simulation.agents.len(), 4);
+        assert!(simulation.agents.values().any(|a| a.name == "Dave"));
+        let status = ui_rx
+            .try_iter()
+            .find_map(|update| match update {
+                SimulationToUI::StateUpdate(status) if status.starts_with("Loaded") => Some(status),
+                _ => None,
+            })
+            .unwrap();
+        assert!(status.ends_with(
+            "1 added, 4 rejected ('Dave' appears more than once; invalid configuration: agent \
+             name 'system' is reserved; invalid configuration: agent names must not be empty; \
+             invalid configuration: agent name 'Moderator' is used by the moderator)"
+        ));
+    }
+
+    #[test]
+    fn test_scene_replaces_cast_world_and_topic() {
+        let path = std::env::temp_dir().join(format!("protopolis-scene-{}.json", Uuid::new_v4()));
+        let path_str = path.to_string_lossy().into_owned();
+        let mut config = mock_config();
+        config.world.speak_cooldown_ticks = Some(3);
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.set_trait("Bob", "neuroticism", 0.9);
+        simulation.discussion_topic = Some("Urban transport".to_string());
+        simulation.handle_command(UIToSimulation::SaveScene(path_str.clone()));
+
+        let mut config = mock_config();
+        config.agents.truncate(1);
+        config.agents[0].name = "Zed".to_string();
+        let (mut other, _sim_tx, ui_rx) = setup_simulation_with(config);
+        other.handle_command(UIToSimulation::LoadScene(path_str.clone()));
+
+        let mut names: Vec<&str> = other.agents.values().map(|a| a.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["Alice", "Bob", "Charlie"]);
+        let bob = other.agents.values().find(|a| a.name == "Bob").unwrap();
+        assert_eq!(bob.personality.neuroticism, 0.9);
+        assert_eq!(other.config.world.speak_cooldown_ticks, Some(3));
+        assert_eq!(other.discussion_topic.as_deref(), Some("Urban transport"));
+        let updates: Vec<SimulationToUI> = ui_rx.try_iter().collect();
+        assert!(updates
+            .iter()
+            .any(|update| matches!(update, SimulationToUI::AgentLeft(name) if name == "Zed")));
+
+        // A reply still on its way from the previous cast is dropped with the rest of its state
+        other.running = true;
+        let late = Message::new("Alice", "everyone", "Where was I?");
+        other
+            .scheduled_messages
+            .push((other.current_tick + 1, late));
+        other
+            .messages
+            .push(Message::new("Bob", "everyone", "Unheard"));
+        other
+            .last_spoke
+            .insert("Alice".to_string(), other.current_tick);
+        other.goal_progress.insert("Alice".to_string(), 7);
+        other
+            .throughput
+            .insert("Alice".to_string(), Throughput::default());
+        other
+            .room_topics
+            .insert("Lobby".to_string(), "Old topic".to_string());
+        other.handle_command(UIToSimulation::LoadScene(path_str.clone()));
+        assert!(other.scheduled_messages.is_empty());
+        assert!(other.messages.iter().all(|m| m.content.text != "Unheard"));
+        assert!(other.last_spoke.is_empty());
+        assert!(other.goal_progress.is_empty());
+        assert!(other.throughput.is_empty());
+        assert!(other.room_topics.is_empty());
+        for agent in other.agents.values_mut() {
+            agent.talkativeness = 0.0;
+        }
+        other.tick();
+        other.tick();
+        assert!(other
+            .conversation_manager
+            .messages()
+            .iter()
+            .all(|m| m.content.text != "Where was I?"));
+
+        // An invalid scene leaves the simulation as it was
+        let mut scene = Scene::load(&path).unwrap();
+        scene.agents[0].name = String::new();
+        scene.save(&path).unwrap();
+        other.handle_command(UIToSimulation::LoadScene(path_str));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(other.agents.len(), 3);
+        assert!(ui_rx.try_iter().any(|update| matches!(
+            update,
+            SimulationToUI::StateUpdate(status) if status.starts_with("Invalid scene")
+        )));
+    }
+
+    #[test]
+    fn test_tool_agent_answers_queries_addressed_to_it() {
+        let mut config = mock_config();
+        let mut calc = config.agents[0].clone();
+        calc.name = "Calc".to_string();
+        calc.kind = AgentKind::Tool;
+        config.agents.push(calc.clone());
+        assert!(config.validate().is_err());
+        config.agents.last_mut().unwrap().tool = Some("calculator".to_string());
+        assert!(config.validate().is_ok());
+
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+        assert_ne!(simulation.choose_starter().as_deref(), Some("Calc"));
+        simulation.messages.push(Message::new(
+            "Alice",
+            "everyone",
+            "Shall we split 90 euros?",
+        ));
+        simulation.tick();
+        assert!(simulation.messages.iter().all(|m| m.sender != "Calc"));
+
+        simulation.messages = vec![Message::new("Alice", "Calc", "what is 90 / 4?")];
+        simulation.tick();
+        let reply = simulation
+            .messages
+            .iter()
+            .find(|m| m.sender == "Calc")
+            .unwrap();
+        assert_eq!(reply.recipient, "Alice");
+        assert_eq!(reply.content.text, "90 / 4 = 22.5");
+    }
+
+    #[test]
+    fn test_rooms_scope_discussions() {
+        let mut config = mock_config();
+        config.rooms = vec![
+            RoomConfig {
+                name: "lab".to_string(),
+                agents: vec!["Alice".to_string(), "Bob".to_string()],
+                topic: Some("fusion".to_string()),
+            },
+            RoomConfig {
+                name: "cafe".to_string(),
+                agents: vec!["Charlie".to_string()],
+                topic: None,
+            },
+        ];
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        simulation.running = true;
+
+        simulation.start_conversation("the weather");
+        let openings: Vec<(Option<&str>, &str)> = simulation
+            .messages
+            .iter()
+            .map(|m| (m.room.as_deref(), m.content.text.as_str()))
+            .collect();
+        assert!(openings.contains(&(Some("lab"), "Let's talk about fusion. What do you think?")));
+        assert!(openings.contains(&(
+            Some("cafe"),
+            "Let's talk about the weather. What do you think?"
+        )));
+
+        simulation.messages =
+            vec![Message::new("Alice", "everyone", "Tokamaks!").in_room(Some("lab".to_string()))];
+        for agent in simulation.agents.values_mut() {
+            agent.talkativeness = 0.0;
+        }
+        simulation.tick();
+        let heard = |name: &str| {
+            let agent = simulation.agents.values().find(|a| a.name == name).unwrap();
+            (agent.next_prompt.contains("Tokamaks!"), agent.room.clone())
+        };
+        assert_eq!(heard("Bob"), (true, Some("lab".to_string())));
+        assert_eq!(heard("Charlie"), (false, None));
+        assert_eq!(
+            simulation
+                .conversation_manager
+                .messages_in_room("lab")
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_recall_finds_relevant_past_messages() {
+        let mut config = mock_config();
+        config.embeddings = Some(EmbeddingsConfig::default());
+        let (mut simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+        for text in [
+            "Trams are quiet and clean",
+            "I love cooking pasta",
+            "Buses are cheap",
+        ] {
+            simulation.record_message(&Message::new("Bob", "everyone", text));
+        }
+
+        let mut alice = simulation
+            .agents
+            .values()
+            .find(|a| a.name == "Alice")
+            .unwrap()
+            .clone();
+        alice.next_prompt = "[Charlie→Alice]: Are trams quiet?\n".to_string();
+        let recalled = simulation.recall(&alice);
+
+        assert_eq!(recalled.len(), 3);
+        assert_eq!(recalled[0], "[Bob→everyone]: Trams are quiet and clean");
+    }
+
+    #[test]
+    fn test_procedural_agents_join_listed_agents() {
+        let mut config = mock_config();
+        config.seed = Some(7);
+        config.procedural_agents = Some(crate::config::ProceduralAgents {
+            count: 20,
+            name_prefix: "Agent".to_string(),
+            randomize_personality: true,
+        });
+        let (simulation, _sim_tx, _ui_rx) = setup_simulation_with(config);
+
+        assert_eq!(simulation.agents.len(), 23);
+        assert!(simulation.agents.values().any(|a| a.name == "Agent-20"));
+    }
+
+    #[test]
+    fn test_energy_is_clamped() {
+        let mut world = Config::default().world;
+        assert_eq!(next_energy(99.95, false, &world), 100.0);
+        assert_eq!(next_energy(50.0, true, &world), 50.1);
+
+        world.idle_decay = Some(0.5);
+        assert_eq!(next_energy(0.2, true, &world), 0.0);
+        assert_eq!(next_energy(50.0, true, &world), 49.5);
+        assert_eq!(next_energy(50.0, false, &world), 50.1);
+    }
+
+    #[test]
+    fn test_addressed_agent_matches_leading_name() {
+        let names = ["Bob", "Bobby", "Alice"];
+        let addressed = |text| addressed_agent(text, names.iter().copied());
+
+        assert_eq!(addressed("Bob, what do you think?"), Some("Bob"));
+        assert_eq!(addressed("  @Bobby I disagree."), Some("Bobby"));
+        assert_eq!(addressed("@Alice: fair point"), Some("Alice"));
+        assert_eq!(addressed("Bob thinks otherwise."), None);
+        assert_eq!(addressed("@Alicia, hello"), None);
+        assert_eq!(addressed("I agree with Alice, though."), None);
     }
 }
@@ -20,6 +20,45 @@ pub enum AgentState {
     Resting,
 }
 
+/// Mood thresholds: a rolling mood above the first is happy, below the second is tense.
+const HAPPY_MOOD: f32 = 0.25;
+const TENSE_MOOD: f32 = -0.25;
+
+/// Overall mood of an agent, read from the rolling tone of its recent exchanges.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Mood {
+    /// Recent exchanges have mostly been positive.
+    Happy,
+
+    /// Recent exchanges have been mixed or neutral.
+    Neutral,
+
+    /// Recent exchanges have mostly been negative.
+    Tense,
+}
+
+impl Mood {
+    /// Classifies a rolling mood value between -1 and 1.
+    pub fn from_value(value: f32) -> Self {
+        if value > HAPPY_MOOD {
+            Mood::Happy
+        } else if value < TENSE_MOOD {
+            Mood::Tense
+        } else {
+            Mood::Neutral
+        }
+    }
+
+    /// Glyph shown next to the agent in the agents panel.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Mood::Happy => "☺",
+            Mood::Neutral => "·",
+            Mood::Tense => "☹",
+        }
+    }
+}
+
 impl fmt::Display for AgentState {
     /// Converts an `AgentState` into a human-readable string.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -0,0 +1,176 @@
+// strings.rs
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Language whose strings are built in, used when a text is missing from a language file.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Directory holding one `<language>.json` file per translation, next to the configuration.
+const LANGUAGE_DIR: &str = "lang";
+
+/// Translations shipped with the program, used when no file next to the configuration
+/// overrides them.
+const BUILT_IN_LANGUAGES: [(&str, &str); 1] = [("fr", include_str!("../lang/fr.json"))];
+
+/// User-facing text of the UI, in the configured language.
+///
+/// Language files only need the keys they translate; the others keep their English text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Strings {
+    /// Title of the status bar.
+    pub status_title: String,
+
+    /// Title of the messages panel.
+    pub messages_title: String,
+
+    /// Title of the messages panel when the split view has too many columns to show.
+    pub messages_unsplit_title: String,
+
+    /// Title of the agents panel.
+    pub agents_title: String,
+
+    /// Title of the input field.
+    pub input_title: String,
+
+    /// Title of the help overlay.
+    pub commands_title: String,
+
+    /// Last line of the help overlay.
+    pub commands_footer: String,
+
+    /// Label preceding the current tick in the status bar.
+    pub tick_label: String,
+
+    /// Label preceding the discussion topic in the status bar.
+    pub topic_label: String,
+
+    /// Shown as the topic before one is set.
+    pub no_topic: String,
+
+    /// Status shown before the simulation starts.
+    pub waiting_to_start: String,
+
+    /// Status shown when a command needs the simulation after it has stopped.
+    pub simulation_stopped: String,
+
+    /// Status shown for input that matches no command.
+    pub unrecognized_command: String,
+
+    /// Status shown for a prefix matching several commands, where `{command}` is the prefix
+    /// and `{candidates}` lists the commands.
+    pub ambiguous_command: String,
+
+    /// Shown instead of the layout in a terminal below the minimum size, where `{width}` and
+    /// `{height}` are the minimum.
+    pub terminal_too_small: String,
+
+    /// System messages shown at startup, where `{commands}` lists the available commands.
+    pub welcome_messages: Vec<String>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self {
+            status_title: "Status".to_string(),
+            messages_title: "Messages".to_string(),
+            messages_unsplit_title: "Messages (too many conversations to split)".to_string(),
+            agents_title: "Agents".to_string(),
+            input_title: "Input".to_string(),
+            commands_title: "Commands".to_string(),
+            commands_footer: "PageUp/PageDown and Home/End scroll the messages.".to_string(),
+            tick_label: "Tick".to_string(),
+            topic_label: "Topic".to_string(),
+            no_topic: "none".to_string(),
+            waiting_to_start: "Waiting to start".to_string(),
+            simulation_stopped: "Simulation has stopped".to_string(),
+            unrecognized_command:
+                "Unrecognized command. Type 'help' (or press ?) to list commands.".to_string(),
+            ambiguous_command: "Ambiguous command '{command}': {candidates}".to_string(),
+            terminal_too_small: "Please enlarge your terminal (at least {width}x{height})"
+                .to_string(),
+            welcome_messages: vec![
+                "Welcome to Protopolis! Type commands below to interact.".to_string(),
+                "Available commands: {commands} (help or ? for details)".to_string(),
+            ],
+        }
+    }
+}
+
+impl Strings {
+    /// Loads the strings of a language from `lang/<language>.json` in the configuration's
+    /// directory, falling back to the translations built into the program.
+    ///
+    /// # Arguments
+    /// * `language` - The language code, e.g. "fr".
+    /// * `config_dir` - Directory of the configuration file, whatever the working directory.
+    ///
+    /// # Returns
+    /// * `Ok(Strings)` with the translated texts, English filling any gaps; English alone
+    ///   needs no file.
+    /// * `Err(Error)` if the language has no file and is not built in, or its file is invalid.
+    pub fn load(language: &str, config_dir: &Path) -> Result<Self> {
+        let path = language_file(config_dir, language);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if language == DEFAULT_LANGUAGE {
+                    return Ok(Self::default());
+                }
+                match BUILT_IN_LANGUAGES
+                    .iter()
+                    .find(|(code, _)| *code == language)
+                {
+                    Some((_, contents)) => Ok(serde_json::from_str(contents)?),
+                    None => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Returns the path of a language's file.
+fn language_file(config_dir: &Path, language: &str) -> PathBuf {
+    config_dir
+        .join(LANGUAGE_DIR)
+        .join(format!("{}.json", language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_language_falls_back_to_english() {
+        let strings: Strings =
+            serde_json::from_str(r#"{"input_title": "Saisie", "no_topic": "aucun"}"#).unwrap();
+        assert_eq!(strings.input_title, "Saisie");
+        assert_eq!(strings.no_topic, "aucun");
+        assert_eq!(strings.agents_title, "Agents");
+        assert_eq!(strings.welcome_messages.len(), 2);
+
+        // Built-in translations do not depend on the working directory
+        let elsewhere = std::env::temp_dir();
+        assert!(Strings::load(DEFAULT_LANGUAGE, &elsewhere).is_ok());
+        assert_eq!(
+            Strings::load("fr", &elsewhere).unwrap().input_title,
+            "Saisie"
+        );
+        assert!(Strings::load("xx-missing", &elsewhere).is_err());
+    }
+
+    #[test]
+    fn test_language_file_next_to_config_overrides_built_in() {
+        let dir = std::env::temp_dir().join(format!("protopolis-lang-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(LANGUAGE_DIR)).unwrap();
+        std::fs::write(language_file(&dir, "fr"), r#"{"input_title": "Entrée"}"#).unwrap();
+
+        let strings = Strings::load("fr", &dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(strings.input_title, "Entrée");
+    }
+}
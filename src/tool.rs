@@ -0,0 +1,188 @@
+// tool.rs
+
+use std::fmt::Debug;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+/// Names of the built-in tools, as accepted by `get_tool`.
+pub const TOOL_NAMES: [&str; 1] = ["calculator"];
+
+/// Characters that may appear in an arithmetic expression.
+const EXPRESSION_CHARS: &str = "0123456789.+-*/() ";
+
+/// A deterministic agent answering queries without calling the model.
+pub trait Tool: Debug + Send + Sync {
+    /// Answers a query addressed to the tool.
+    ///
+    /// # Arguments
+    /// * `input` - The text of the message the tool is answering.
+    ///
+    /// # Returns
+    /// * The tool's reply.
+    fn respond(&self, input: &str) -> String;
+}
+
+/// Looks up a built-in tool by name.
+///
+/// # Arguments
+/// * `name` - One of `TOOL_NAMES`.
+///
+/// # Returns
+/// * The tool, or `None` if there is no tool by that name.
+pub fn get_tool(name: &str) -> Option<Arc<dyn Tool>> {
+    match name {
+        "calculator" => Some(Arc::new(Calculator)),
+        _ => None,
+    }
+}
+
+/// Finds the latest query a tool heard, preferring messages addressed to it.
+///
+/// # Arguments
+/// * `heard` - The messages the tool heard, one "[sender→recipient]: text" line each.
+/// * `name` - The tool agent's name.
+///
+/// # Returns
+/// * The sender and text of the query, or `None` if nothing was heard.
+pub fn latest_query<'a>(heard: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let addressed = format!("→{}]", name);
+    let line = heard
+        .lines()
+        .rev()
+        .find(|line| line.contains(&addressed))
+        .or_else(|| heard.lines().last())?;
+    let (header, text) = line.split_once("]: ")?;
+    let sender = header.trim_start_matches('[').split('→').next()?;
+    Some((sender, text))
+}
+
+/// Evaluates the arithmetic in a message, e.g. "what is 12 * (3 + 4)?".
+#[derive(Debug, Default)]
+pub struct Calculator;
+
+impl Tool for Calculator {
+    fn respond(&self, input: &str) -> String {
+        let Some(expression) = find_expression(input) else {
+            return "Send me an expression to compute, e.g. 12 * (3 + 4)".to_string();
+        };
+        match evaluate(expression) {
+            Some(value) => format!("{} = {}", expression, (value * 1e10).round() / 1e10),
+            None => format!("I cannot compute {}", expression),
+        }
+    }
+}
+
+/// Extracts the longest stretch of a text that looks like arithmetic.
+fn find_expression(text: &str) -> Option<&str> {
+    text.split(|c: char| !EXPRESSION_CHARS.contains(c))
+        .map(str::trim)
+        .filter(|run| run.contains(|c: char| c.is_ascii_digit()))
+        .max_by_key(|run| run.len())
+}
+
+/// Evaluates an arithmetic expression with `+ - * /` and parentheses.
+///
+/// # Returns
+/// * The value, or `None` if the expression is malformed or divides by zero.
+fn evaluate(expression: &str) -> Option<f64> {
+    let mut chars = expression.chars().peekable();
+    let value = parse_sum(&mut chars)?;
+    skip_spaces(&mut chars);
+    chars.peek().is_none().then_some(value)
+}
+
+/// Parses terms joined by `+` and `-`.
+fn parse_sum(chars: &mut Peekable<Chars>) -> Option<f64> {
+    let mut value = parse_product(chars)?;
+    loop {
+        skip_spaces(chars);
+        match chars.peek() {
+            Some('+') => {
+                chars.next();
+                value += parse_product(chars)?;
+            }
+            Some('-') => {
+                chars.next();
+                value -= parse_product(chars)?;
+            }
+            _ => return Some(value),
+        }
+    }
+}
+
+/// Parses factors joined by `*` and `/`.
+fn parse_product(chars: &mut Peekable<Chars>) -> Option<f64> {
+    let mut value = parse_factor(chars)?;
+    loop {
+        skip_spaces(chars);
+        match chars.peek() {
+            Some('*') => {
+                chars.next();
+                value *= parse_factor(chars)?;
+            }
+            Some('/') => {
+                chars.next();
+                let divisor = parse_factor(chars)?;
+                if divisor == 0.0 {
+                    return None;
+                }
+                value /= divisor;
+            }
+            _ => return Some(value),
+        }
+    }
+}
+
+/// Parses a number, a negated factor, or a parenthesized expression.
+fn parse_factor(chars: &mut Peekable<Chars>) -> Option<f64> {
+    skip_spaces(chars);
+    match chars.peek()? {
+        '-' => {
+            chars.next();
+            parse_factor(chars).map(|value| -value)
+        }
+        '(' => {
+            chars.next();
+            let value = parse_sum(chars)?;
+            skip_spaces(chars);
+            (chars.next()? == ')').then_some(value)
+        }
+        _ => {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_digit() && c != '.' {
+                    break;
+                }
+                number.push(c);
+                chars.next();
+            }
+            number.parse().ok()
+        }
+    }
+}
+
+/// Advances past any spaces.
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.next_if_eq(&' ').is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculator_answers_arithmetic() {
+        let calculator = get_tool("calculator").unwrap();
+        assert_eq!(
+            calculator.respond("Calc, what is 12 * (3 + 4)?"),
+            "12 * (3 + 4) = 84"
+        );
+        assert_eq!(calculator.respond("and 0.1 + 0.2"), "0.1 + 0.2 = 0.3");
+        assert_eq!(calculator.respond("1 / 0"), "I cannot compute 1 / 0");
+        assert!(calculator.respond("hello").starts_with("Send me"));
+
+        let heard = "[Alice→everyone]: 5 + 5\n[Bob→Calc]: 2 * 3\n";
+        assert_eq!(latest_query(heard, "Calc"), Some(("Bob", "2 * 3")));
+    }
+}
@@ -1,6 +1,12 @@
+use crate::config::{Config, UiConfig};
+use crate::conversation_manager::pair_key;
 use crate::message::Message;
-use crate::simulation::{SimulationToUI, UIToSimulation};
-use crate::state::AgentState;
+use crate::prompt;
+use crate::sentiment::Sentiment;
+use crate::simulation::{SimulationSnapshot, SimulationToUI, UIToSimulation, WORLD_SENDER};
+use crate::state::{AgentState, Mood};
+use crate::strings::Strings;
+use chrono::{DateTime, Local, Utc};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     execute,
@@ -11,14 +17,20 @@ use ratatui::prelude::CrosstermBackend;
 use ratatui::widgets::{Padding, Scrollbar, ScrollbarOrientation, ScrollbarState};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
-use std::collections::{HashMap, VecDeque};
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
 use std::io::{self, stdout, Stdout};
-use std::sync::mpsc::{Receiver, Sender};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 use std::time::{Duration, Instant};
 
 // Map of colors for agents
@@ -33,82 +45,572 @@ const COLORS: [Color; 8] = [
     Color::LightGreen,
 ];
 
+// Okabe-Ito palette, distinguishable under the common forms of color blindness
+const COLORBLIND_COLORS: [Color; 8] = [
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(240, 228, 66),
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(153, 153, 153),
+];
+
+// Gutter in the sender's color marking the rows of a message
+const MESSAGE_GUTTER: &str = "▎ ";
+
+// Indentation of message content under its header
+const MESSAGE_INDENT: usize = 2;
+
+// Frames of the activity spinner, advanced on every UI tick
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// A command typed in the input field
+struct Command {
+    usage: &'static str,
+    /// Short forms typed instead of the name
+    aliases: &'static [&'static str],
+    description: &'static str,
+    /// Whether the command still works once the simulation has stopped
+    local: bool,
+}
+
+impl Command {
+    /// The word the command starts with
+    fn name(&self) -> &'static str {
+        self.usage.split(' ').next().unwrap_or(self.usage)
+    }
+
+    /// Whether the usage lists arguments after the name
+    fn takes_arguments(&self) -> bool {
+        self.usage.contains(' ')
+    }
+
+    /// The usage followed by the aliases, as listed in the help overlay
+    fn usage_with_aliases(&self) -> String {
+        if self.aliases.is_empty() {
+            self.usage.to_string()
+        } else {
+            format!("{} ({})", self.usage, self.aliases.join(", "))
+        }
+    }
+}
+
+/// Find the command a typed word stands for: its name, one of its aliases, or a prefix of a
+/// single command's name
+///
+/// # Arguments
+/// * `word` - The first word typed in the input field.
+///
+/// # Returns
+/// * `Ok(name)` with the command's name, or the word itself when it matches no command.
+/// * `Err(candidates)` with the names of the commands an ambiguous prefix matches.
+fn resolve_command(word: &str) -> Result<&str, Vec<&'static str>> {
+    if let Some(command) = COMMANDS
+        .iter()
+        .find(|c| c.name() == word || c.aliases.contains(&word))
+    {
+        return Ok(command.name());
+    }
+    if word.is_empty() {
+        return Ok(word);
+    }
+
+    let candidates: Vec<&'static str> = COMMANDS
+        .iter()
+        .map(Command::name)
+        .filter(|name| name.starts_with(word))
+        .collect();
+    match candidates.as_slice() {
+        [] => Ok(word),
+        [name] => Ok(name),
+        _ => Err(candidates),
+    }
+}
+
+// Every command of the input field; the help overlay and welcome message are built from it
+const COMMANDS: [Command; 31] = [
+    Command {
+        usage: "start",
+        aliases: &["s"],
+        description: "Start the simulation",
+        local: false,
+    },
+    Command {
+        usage: "pause",
+        aliases: &["p"],
+        description: "Pause the simulation",
+        local: false,
+    },
+    Command {
+        usage: "resume",
+        aliases: &["r"],
+        description: "Resume the simulation",
+        local: false,
+    },
+    Command {
+        usage: "step",
+        aliases: &[],
+        description: "Run a single tick while paused",
+        local: false,
+    },
+    Command {
+        usage: "stop",
+        aliases: &[],
+        description: "Stop the simulation",
+        local: false,
+    },
+    Command {
+        usage: "interrupt",
+        aliases: &[],
+        description: "Abort the responses agents are currently generating",
+        local: false,
+    },
+    Command {
+        usage: "list",
+        aliases: &[],
+        description: "List the agents with their personality, state, and energy",
+        local: false,
+    },
+    Command {
+        usage: "split",
+        aliases: &[],
+        description: "Toggle one message column per conversation pair (←/→ to switch column)",
+        local: true,
+    },
+    Command {
+        usage: "time",
+        aliases: &[],
+        description: "Toggle message times between clock time and elapsed time",
+        local: true,
+    },
+    Command {
+        usage: "stats",
+        aliases: &[],
+        description: "Summarize the run: messages, response length, energies, and throughput",
+        local: false,
+    },
+    Command {
+        usage: "snapshot",
+        aliases: &[],
+        description: "Show a JSON snapshot of the simulation state",
+        local: false,
+    },
+    Command {
+        usage: "topic <subject>",
+        aliases: &[],
+        description: "Set a new discussion topic",
+        local: false,
+    },
+    Command {
+        usage: "event <text>",
+        aliases: &[],
+        description: "Make something happen that every agent notices",
+        local: false,
+    },
+    Command {
+        usage: "msg <agent> <message>",
+        aliases: &[],
+        description: "Send a message to a specific agent",
+        local: false,
+    },
+    Command {
+        usage: "export <path>.md",
+        aliases: &[],
+        description: "Export the conversation transcript as Markdown",
+        local: false,
+    },
+    Command {
+        usage: "graph <path>.dot|.json",
+        aliases: &[],
+        description: "Export who talked to whom, for Graphviz (DOT) or other tools (JSON)",
+        local: false,
+    },
+    Command {
+        usage: "profiles save|load <path>",
+        aliases: &[],
+        description: "Save the agents' personalities to a file, or apply them from one",
+        local: false,
+    },
+    Command {
+        usage: "scene save|load <path>",
+        aliases: &[],
+        description: "Save the agents, world settings, and topic as a scene, or switch to one",
+        local: false,
+    },
+    Command {
+        usage: "seek <tick>",
+        aliases: &[],
+        description: "Jump to a tick while replaying a transcript",
+        local: false,
+    },
+    Command {
+        usage: "focus <agent>",
+        aliases: &[],
+        description: "Talk 1:1 with an agent while the others pause",
+        local: false,
+    },
+    Command {
+        usage: "unfocus",
+        aliases: &[],
+        description: "Leave focus mode and return to the group conversation",
+        local: false,
+    },
+    Command {
+        usage: "set <agent> <trait> <value>",
+        aliases: &[],
+        description: "Change a Big Five trait of a live agent; the value must be between 0 and 1",
+        local: false,
+    },
+    Command {
+        usage: "room [<name>]",
+        aliases: &[],
+        description: "Show one room's messages and direct topic to it; alone, show every room",
+        local: false,
+    },
+    Command {
+        usage: "find <query>",
+        aliases: &[],
+        description: "Highlight messages containing the query; repeat to cycle through matches",
+        local: true,
+    },
+    Command {
+        usage: "remove <agent>",
+        aliases: &[],
+        description: "Take an agent out of the simulation, keeping what it said",
+        local: false,
+    },
+    Command {
+        usage: "mute <agent>",
+        aliases: &[],
+        description: "Silence an agent; it keeps hearing the discussion",
+        local: false,
+    },
+    Command {
+        usage: "unmute <agent>",
+        aliases: &[],
+        description: "Let a muted agent speak again, answering what it heard meanwhile",
+        local: false,
+    },
+    Command {
+        usage: "inspect <agent>",
+        aliases: &[],
+        description: "Show the last prompt sent for an agent (requires debug)",
+        local: false,
+    },
+    Command {
+        usage: "reload",
+        aliases: &[],
+        description: "Re-read the ui section of the configuration file",
+        local: true,
+    },
+    Command {
+        usage: "help",
+        aliases: &[],
+        description: "Show this list (or press ? with an empty input)",
+        local: true,
+    },
+    Command {
+        usage: "exit",
+        aliases: &[],
+        description: "Exit the application",
+        local: true,
+    },
+];
+
+/// Colors used to render participants in the UI
+struct Palette {
+    agents: Vec<Color>,
+    user: Color,
+    system: Color,
+    everyone: Color,
+    moderator: Color,
+    observer: Color,
+}
+
+impl Palette {
+    /// Builds the active palette from the UI configuration, ignoring unparsable colors
+    fn from_config(config: &UiConfig) -> Self {
+        let custom: Vec<Color> = config
+            .colors
+            .iter()
+            .filter_map(|name| name.parse().ok())
+            .collect();
+
+        let agents = if !custom.is_empty() {
+            custom
+        } else if config.palette == "colorblind" {
+            COLORBLIND_COLORS.to_vec()
+        } else {
+            COLORS.to_vec()
+        };
+
+        Self {
+            agents,
+            user: config.user_color.parse().unwrap_or(Color::White),
+            system: config.system_color.parse().unwrap_or(Color::Blue),
+            everyone: config.everyone_color.parse().unwrap_or(Color::Gray),
+            moderator: config.moderator_color.parse().unwrap_or(Color::Magenta),
+            observer: config.observer_color.parse().unwrap_or(Color::Cyan),
+        }
+    }
+}
+
+/// Widths of the main panels
+struct PanelLayout {
+    messages_pct: u16,
+    show_agents_panel: bool,
+}
+
+impl PanelLayout {
+    /// Builds the panel layout from the UI configuration, falling back to 70/30 when out of range
+    fn from_config(config: &UiConfig) -> Self {
+        let messages_pct = match config.messages_pct {
+            pct @ 1..=100 => pct,
+            _ => UiConfig::default().messages_pct,
+        };
+        Self {
+            messages_pct,
+            show_agents_panel: config.show_agents_panel && messages_pct < 100,
+        }
+    }
+}
+
 /// UI struct for managing the TUI interface
 pub struct UI {
     ui_tx: Sender<UIToSimulation>,
     ui_rx: Receiver<SimulationToUI>,
+    palette: Palette,
+    layout: PanelLayout,
     agent_colors: HashMap<String, Color>,
+    agent_avatars: HashMap<String, String>,
     input: String,
     messages: VecDeque<FormattedMessage>,
-    agent_states: HashMap<String, (AgentState, f32)>,
+    agent_states: HashMap<String, (AgentState, f32, f32)>,
+    typing: BTreeSet<String>,
+    muted: HashSet<String>,
+    agent_models: HashMap<String, String>,
+    moderator_name: Option<String>,
+    observer_name: Option<String>,
     simulation_status: String,
+    topic: Option<String>,
     current_tick: u64,
+    spinner_frame: usize,
     should_quit: bool,
     message_scroll: usize,
     message_scroll_state: ScrollbarState,
+    // Whether the messages panel sticks to the latest message; off once scrolled back
+    follow_tail: bool,
+    // Furthest scroll offset of the last rendered frame, where the tail is
+    max_message_scroll: Cell<usize>,
+    messages_width: Cell<u16>,
+    relative_times: bool,
+    rooms: Vec<String>,
+    room: Option<String>,
+    split_view: bool,
+    split_focus: usize,
+    split_scroll: HashMap<(String, String), usize>,
+    max_split_columns: usize,
+    // Recent generation time of each agent, in seconds, once it has generated
+    wait_estimates: HashMap<String, f64>,
+    show_wait_estimates: bool,
+    // Smallest terminal (width, height) the layout is drawn in
+    min_size: (u16, u16),
+    energy_cap: f32,
+    skip_splash: bool,
+    strings: Strings,
+    welcome_messages: Vec<String>,
+    simulation_stopped: bool,
+    focused_agent: Option<String>,
+    search_query: Option<String>,
+    search_match: Option<usize>,
+    overlay: Option<(String, String)>,
+    overlay_scroll: usize,
+    config_path: PathBuf,
 }
 
 /// A formatted message with sender/recipient information
 struct FormattedMessage {
     sender: String,
     sender_color: Color,
+    sender_avatar: Option<String>,
     recipient: String,
     recipient_color: Color,
+    recipient_avatar: Option<String>,
+    timestamp: DateTime<Utc>,
+    room: Option<String>,
     content: String,
+    parts: Vec<(&'static str, String)>,
+    sentiment: Option<Sentiment>,
+    from_moderator: bool,
+    from_observer: bool,
+    from_world: bool,
 }
 
 impl UI {
     /// Creates a new UI instance
-    pub fn new(ui_tx: Sender<UIToSimulation>, ui_rx: Receiver<SimulationToUI>) -> Self {
+    pub fn new(
+        ui_tx: Sender<UIToSimulation>,
+        ui_rx: Receiver<SimulationToUI>,
+        config: &Config,
+        config_path: &Path,
+    ) -> Self {
+        // A language that cannot be loaded is reported, and the UI stays in English
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        let (strings, simulation_status) = match Strings::load(&config.language, config_dir) {
+            Ok(strings) => {
+                let status = strings.waiting_to_start.clone();
+                (strings, status)
+            }
+            Err(e) => (
+                Strings::default(),
+                format!("Failed to load language '{}': {}", config.language, e),
+            ),
+        };
+
         Self {
             ui_tx,
             ui_rx,
-            agent_colors: HashMap::new(),
+            palette: Palette::from_config(&config.ui),
+            layout: PanelLayout::from_config(&config.ui),
+            agent_colors: config
+                .agents
+                .iter()
+                .filter_map(|a| Some((a.name.clone(), a.color.as_deref()?.parse().ok()?)))
+                .collect(),
+            agent_avatars: config
+                .agents
+                .iter()
+                .filter_map(|a| Some((a.name.clone(), a.avatar.clone()?)))
+                .collect(),
             input: String::new(),
             messages: VecDeque::with_capacity(100),
             agent_states: HashMap::new(),
-            simulation_status: "Waiting to start".to_string(),
+            typing: BTreeSet::new(),
+            muted: HashSet::new(),
+            agent_models: HashMap::new(),
+            moderator_name: config.moderator.as_ref().map(|m| m.name.clone()),
+            observer_name: config.observer.as_ref().map(|o| o.name.clone()),
+            simulation_status,
+            topic: None,
             current_tick: 0,
+            spinner_frame: 0,
             should_quit: false,
             message_scroll: 0,
             message_scroll_state: ScrollbarState::default(),
+            follow_tail: true,
+            max_message_scroll: Cell::new(0),
+            messages_width: Cell::new(80),
+            relative_times: false,
+            rooms: config.rooms.iter().map(|r| r.name.clone()).collect(),
+            room: None,
+            split_view: false,
+            split_focus: 0,
+            split_scroll: HashMap::new(),
+            max_split_columns: config.ui.max_split_columns,
+            wait_estimates: HashMap::new(),
+            show_wait_estimates: config.ui.show_wait_estimates,
+            min_size: (config.ui.min_width, config.ui.min_height),
+            energy_cap: config.world.energy_cap,
+            skip_splash: config.skip_splash,
+            welcome_messages: config
+                .ui
+                .welcome_messages
+                .clone()
+                .unwrap_or_else(|| strings.welcome_messages.clone()),
+            strings,
+            simulation_stopped: false,
+            focused_agent: None,
+            search_query: None,
+            search_match: None,
+            overlay: None,
+            overlay_scroll: 0,
+            config_path: config_path.to_path_buf(),
         }
     }
 
     /// Get the color for an agent
     fn get_agent_color(&mut self, agent_name: &str) -> Color {
         if !self.agent_colors.contains_key(agent_name) {
-            let color_index = self.agent_colors.len() % COLORS.len();
-            self.agent_colors
-                .insert(agent_name.to_string(), COLORS[color_index]);
+            // Agents without a configured color get one from their name, so it stays the same
+            // across runs whatever order they speak in
+            let color_index = name_hash(agent_name) as usize % self.palette.agents.len();
+            let color = self.palette.agents[color_index];
+            self.agent_colors.insert(agent_name.to_string(), color);
         }
         *self.agent_colors.get(agent_name).unwrap()
     }
 
+    /// Get the avatar prefix shown before an agent's name
+    fn agent_avatar(&self, agent_name: &str) -> String {
+        let avatar = self
+            .agent_avatars
+            .get(agent_name)
+            .map_or_else(|| default_avatar(agent_name), String::as_str);
+        avatar_prefix(avatar)
+    }
+
+    /// Add a system notice addressed to the user to the message history
+    fn add_system_message(&mut self, text: String) {
+        self.add_message(&Message::new("System", "User", text));
+    }
+
     /// Add a message to the message history
     fn add_message(&mut self, message: &Message) {
+        let from_moderator = self.moderator_name.as_deref() == Some(message.sender.as_str());
+        let from_observer = self.observer_name.as_deref() == Some(message.sender.as_str());
         let sender_color = match message.sender.as_str() {
-            "User" => Color::White,
-            "System" => Color::Blue,
+            "User" => self.palette.user,
+            "System" => self.palette.system,
+            WORLD_SENDER => Color::Yellow,
+            _ if from_moderator => self.palette.moderator,
+            _ if from_observer => self.palette.observer,
             _ => self.get_agent_color(&message.sender),
         };
+        let sender_avatar = match message.sender.as_str() {
+            "User" | "System" | WORLD_SENDER => None,
+            _ if from_moderator || from_observer => None,
+            sender => Some(self.agent_avatar(sender)),
+        };
 
         let recipient_color = match message.recipient.as_str() {
-            "User" => Color::White,
-            "System" => Color::Blue,
-            "everyone" => Color::Gray,
+            "User" => self.palette.user,
+            "System" => self.palette.system,
+            "everyone" => self.palette.everyone,
             _ => self.get_agent_color(&message.recipient),
         };
+        let recipient_avatar = match message.recipient.as_str() {
+            "User" | "System" | "everyone" => None,
+            recipient => Some(self.agent_avatar(recipient)),
+        };
 
         self.messages.push_back(FormattedMessage {
             sender: message.sender.clone(),
             sender_color,
+            sender_avatar,
             recipient: message.recipient.clone(),
             recipient_color,
-            content: message.content.to_string().trim_matches('"').to_string(),
+            recipient_avatar,
+            timestamp: message.timestamp,
+            room: message.room.clone(),
+            content: message.content.text.clone(),
+            parts: message
+                .content
+                .parts()
+                .into_iter()
+                .map(|(label, value)| (label, value.to_string()))
+                .collect(),
+            sentiment: message.sentiment,
+            from_moderator,
+            from_observer,
+            from_world: message.sender == WORLD_SENDER,
         });
 
-        self.message_scroll = self.messages.len();
+        // Only a reader already at the bottom is carried along by new messages
+        if self.follow_tail {
+            self.message_scroll = usize::MAX;
+        }
         self.message_scroll_state = self
             .message_scroll_state
             .content_length(self.messages.len())
@@ -116,7 +618,163 @@ impl UI {
 
         // Keep message history limited
         if self.messages.len() > 100 {
-            self.messages.pop_front();
+            if let Some(dropped) = self.messages.pop_front() {
+                // Scrolled back, the view stays on the same lines as the oldest one goes
+                if !self.follow_tail && self.is_visible(&dropped) {
+                    let width = self.messages_width.get();
+                    let lines = message_lines(&dropped, None, width, Clock::Absolute).len();
+                    self.message_scroll = self.message_scroll.saturating_sub(lines);
+                }
+            }
+            self.search_match = self.search_match.and_then(|i| i.checked_sub(1));
+        }
+    }
+
+    /// Indices of the messages containing the search query, oldest first
+    fn search_matches(&self) -> Vec<usize> {
+        let Some(query) = &self.search_query else {
+            return Vec::new();
+        };
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                self.is_visible(m) && m.content.to_lowercase().contains(query.as_str())
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Jump to the most recent message matching a query, or to the previous match on repeat
+    fn find(&mut self, query: &str) {
+        let query = query.to_lowercase();
+        let repeat = query.is_empty() || self.search_query.as_ref() == Some(&query);
+        if !query.is_empty() {
+            self.search_query = Some(query);
+        }
+
+        let matches = self.search_matches();
+        let query = self.search_query.clone().unwrap_or_default();
+        if query.is_empty() {
+            self.simulation_status = "Incorrect format. Use: find <query>".to_string();
+            return;
+        }
+
+        // Cycle to earlier matches, wrapping back to the most recent one
+        let current = if repeat { self.search_match } else { None };
+        let next = current
+            .and_then(|current| matches.iter().rev().find(|&&i| i < current))
+            .or(matches.last())
+            .copied();
+        self.search_match = next;
+
+        match next {
+            Some(index) => {
+                self.follow_tail = false;
+                self.message_scroll = self
+                    .messages
+                    .iter()
+                    .take(index)
+                    .filter(|m| self.is_visible(m))
+                    .map(|m| {
+                        message_lines(m, None, self.messages_width.get(), Clock::Absolute).len()
+                    })
+                    .sum();
+                self.message_scroll_state = self.message_scroll_state.position(self.message_scroll);
+                let position = matches.iter().position(|&i| i == index).unwrap_or(0);
+                self.simulation_status =
+                    format!("Match {}/{} for '{}'", position + 1, matches.len(), query);
+            }
+            None => {
+                self.simulation_status = format!("No messages match '{}'", query);
+            }
+        }
+    }
+
+    /// Resynchronize the agent panel from a snapshot and show it in the message log
+    fn apply_snapshot(&mut self, snapshot: SimulationSnapshot) {
+        self.current_tick = snapshot.tick;
+        self.topic = snapshot.topic.clone();
+        for agent in &snapshot.agents {
+            self.set_typing(&agent.name, &agent.state);
+            self.agent_states.insert(
+                agent.name.clone(),
+                (agent.state.clone(), agent.energy, agent.mood),
+            );
+            self.agent_models
+                .insert(agent.name.clone(), agent.model.clone());
+            self.set_muted(&agent.name, agent.muted);
+        }
+
+        let content = serde_json::to_string(&snapshot).unwrap_or_default();
+        self.add_message(&Message::new("System", "User", content));
+        self.simulation_status = format!("Snapshot taken at tick {}", snapshot.tick);
+    }
+
+    /// View a single room's messages, or every room's, and direct topic changes to it
+    fn select_room(&mut self, room: Option<&str>) {
+        if let Some(room) = room {
+            if !self.rooms.iter().any(|r| r == room) {
+                self.simulation_status = if self.rooms.is_empty() {
+                    "No rooms are configured".to_string()
+                } else {
+                    format!("Unknown room '{}'. Rooms: {}", room, self.rooms.join(", "))
+                };
+                return;
+            }
+        }
+        self.room = room.map(str::to_string);
+        self.send(UIToSimulation::SelectRoom(self.room.clone()));
+        self.follow_tail = true;
+    }
+
+    /// Open the overlay listing every command
+    fn show_help(&mut self) {
+        let width = COMMANDS
+            .iter()
+            .map(|c| c.usage_with_aliases().len())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<String> = COMMANDS
+            .iter()
+            .map(|c| {
+                format!(
+                    "{:<width$}  {}",
+                    c.usage_with_aliases(),
+                    c.description,
+                    width = width
+                )
+            })
+            .collect();
+        lines.push(String::new());
+        lines.push(self.strings.commands_footer.clone());
+        self.overlay = Some((self.strings.commands_title.clone(), lines.join("\n")));
+        self.overlay_scroll = 0;
+    }
+
+    /// An agent's name followed by its estimated wait, e.g. "Alice (~4s)", once known
+    fn wait_label(&self, agent: &str) -> String {
+        match self.wait_estimates.get(agent) {
+            Some(seconds) => format!("{} (~{}s)", agent, seconds.round().max(1.0)),
+            None => agent.to_string(),
+        }
+    }
+
+    /// Record whether an agent is muted, for the agents panel
+    fn set_muted(&mut self, agent: &str, muted: bool) {
+        if muted {
+            self.muted.insert(agent.to_string());
+        } else {
+            self.muted.remove(agent);
+        }
+    }
+
+    /// Show or hide an agent's typing indicator as it starts or stops thinking
+    fn set_typing(&mut self, agent: &str, state: &AgentState) {
+        if *state == AgentState::Thinking {
+            self.typing.insert(agent.to_string());
+        } else {
+            self.typing.remove(agent);
         }
     }
 
@@ -124,40 +782,258 @@ impl UI {
     fn process_command(&mut self, command: &str) {
         let command = command.trim();
 
+        // Aliases and unambiguous prefixes stand for the full name. In focus mode plain lines
+        // are meant for the agent, so only a command typed in full, with its arguments, runs
+        let (word, rest) = command.split_once(' ').unwrap_or((command, ""));
+        let chat = self.focused_agent.is_some()
+            && !COMMANDS.iter().any(|c| {
+                (c.name() == word || c.aliases.contains(&word))
+                    && c.takes_arguments() != rest.is_empty()
+            });
+        let command = match (word, rest) {
+            _ if chat => command.to_string(),
+            (word, rest) => match resolve_command(word) {
+                Ok(name) if rest.is_empty() => name.to_string(),
+                Ok(name) => format!("{} {}", name, rest),
+                Err(candidates) => {
+                    self.simulation_status = prompt::render(
+                        &self.strings.ambiguous_command,
+                        &[("command", word), ("candidates", &candidates.join(", "))],
+                    );
+                    return;
+                }
+            },
+        };
+        let command = command.as_str();
+
+        // Only local commands still work once the simulation is gone
+        let name = command.split_whitespace().next().unwrap_or_default();
+        let local = COMMANDS.iter().any(|c| c.local && c.name() == name);
+        if self.simulation_stopped && !local {
+            self.simulation_status = self.strings.simulation_stopped.clone();
+            return;
+        }
+
+        // In focus mode, anything but a command is a line for the focused agent
+        if let Some(agent) = self.focused_agent.clone().filter(|_| chat) {
+            if !command.is_empty() {
+                self.send(UIToSimulation::UserMessage(
+                    agent.clone(),
+                    command.to_string(),
+                ));
+                self.simulation_status = format!("Talking 1:1 with {} (unfocus to leave)", agent);
+            }
+            return;
+        }
+
         match command {
             "start" => {
-                let _ = self.ui_tx.send(UIToSimulation::Start);
+                self.send(UIToSimulation::Start);
                 self.simulation_status = "Starting simulation...".to_string();
             }
             "pause" => {
-                let _ = self.ui_tx.send(UIToSimulation::Pause);
+                self.send(UIToSimulation::Pause);
                 self.simulation_status = "Pausing simulation...".to_string();
             }
             "resume" => {
-                let _ = self.ui_tx.send(UIToSimulation::Resume);
+                self.send(UIToSimulation::Resume);
                 self.simulation_status = "Resuming simulation...".to_string();
             }
+            "step" => {
+                self.send(UIToSimulation::Step);
+                self.simulation_status = "Stepping one tick...".to_string();
+            }
             "stop" => {
-                let _ = self.ui_tx.send(UIToSimulation::Stop);
+                self.send(UIToSimulation::Stop);
                 self.simulation_status = "Stopping simulation...".to_string();
             }
+            "interrupt" => {
+                self.send(UIToSimulation::Interrupt);
+                self.simulation_status = "Interrupting current generation...".to_string();
+            }
+            "list" => {
+                self.send(UIToSimulation::ListAgents);
+                self.simulation_status = "Listing agents...".to_string();
+            }
+            "split" => {
+                self.split_view = !self.split_view;
+                self.simulation_status = if self.split_view {
+                    "Split view on (←/→ to switch column)".to_string()
+                } else {
+                    "Split view off".to_string()
+                };
+            }
+            "time" => {
+                self.relative_times = !self.relative_times;
+                self.simulation_status = if self.relative_times {
+                    "Showing message times as elapsed time".to_string()
+                } else {
+                    "Showing message times as clock time".to_string()
+                };
+            }
+            "stats" => {
+                self.send(UIToSimulation::RequestStats);
+                self.simulation_status = "Computing statistics...".to_string();
+            }
+            "snapshot" => {
+                self.send(UIToSimulation::RequestSnapshot);
+                self.simulation_status = "Requesting snapshot...".to_string();
+            }
             "exit" => {
-                let _ = self.ui_tx.send(UIToSimulation::Stop);
+                self.send(UIToSimulation::Stop);
                 self.should_quit = true;
+                return;
+            }
+            "unfocus" => {
+                self.send(UIToSimulation::Unfocus);
+                self.focused_agent = None;
+                self.simulation_status = "Back to the group conversation".to_string();
+            }
+            "reload" => self.reload_ui_config(),
+            "help" => self.show_help(),
+            "room" => self.select_room(None),
+            _ if command.starts_with("room ") => {
+                self.select_room(Some(command.trim_start_matches("room ").trim()));
+            }
+            "find" => self.find(""),
+            _ if command.starts_with("find ") => {
+                self.find(command.trim_start_matches("find ").trim());
             }
             _ if command.starts_with("topic ") => {
                 let topic = command.trim_start_matches("topic ").to_string();
-                let _ = self
-                    .ui_tx
-                    .send(UIToSimulation::SetDiscussionTopic(topic.clone()));
+                self.send(UIToSimulation::SetDiscussionTopic(topic.clone()));
                 self.simulation_status = format!("Discussion topic set: {}", topic);
             }
+            _ if command.starts_with("export ") => {
+                let path = command.trim_start_matches("export ").trim().to_string();
+                if path.ends_with(".md") {
+                    self.send(UIToSimulation::ExportTranscript(path.clone()));
+                    self.simulation_status = format!("Exporting transcript to {}...", path);
+                } else {
+                    self.simulation_status = "Incorrect format. Use: export <path>.md".to_string();
+                }
+            }
+            _ if command.starts_with("graph ") => {
+                let path = command.trim_start_matches("graph ").trim().to_string();
+                if path.ends_with(".dot") || path.ends_with(".json") {
+                    self.send(UIToSimulation::ExportGraph(path.clone()));
+                    self.simulation_status = format!("Exporting conversation graph to {}...", path);
+                } else {
+                    self.simulation_status =
+                        "Incorrect format. Use: graph <path>.dot or graph <path>.json".to_string();
+                }
+            }
+            _ if command.starts_with("scene ") => {
+                let parts: Vec<&str> = command.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_, "save", path] => {
+                        self.send(UIToSimulation::SaveScene(path.to_string()));
+                        self.simulation_status = format!("Saving scene to {}...", path);
+                    }
+                    [_, "load", path] => {
+                        self.send(UIToSimulation::LoadScene(path.to_string()));
+                        self.simulation_status = format!("Loading scene from {}...", path);
+                    }
+                    _ => {
+                        self.simulation_status =
+                            "Incorrect format. Use: scene save|load <path>".to_string();
+                    }
+                }
+            }
+            _ if command.starts_with("profiles ") => {
+                let parts: Vec<&str> = command.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_, "save", path] => {
+                        self.send(UIToSimulation::SaveProfiles(path.to_string()));
+                        self.simulation_status = format!("Saving profiles to {}...", path);
+                    }
+                    [_, "load", path] => {
+                        self.send(UIToSimulation::LoadProfiles(path.to_string()));
+                        self.simulation_status = format!("Loading profiles from {}...", path);
+                    }
+                    _ => {
+                        self.simulation_status =
+                            "Incorrect format. Use: profiles save|load <path>".to_string();
+                    }
+                }
+            }
+            _ if command.starts_with("focus ") => {
+                let agent = command.trim_start_matches("focus ").trim().to_string();
+                if self.agent_states.contains_key(&agent) || self.agent_models.contains_key(&agent)
+                {
+                    self.send(UIToSimulation::Focus(agent.clone()));
+                    self.simulation_status =
+                        format!("Talking 1:1 with {} (unfocus to leave)", agent);
+                    self.focused_agent = Some(agent);
+                } else {
+                    self.simulation_status = format!("Agent '{}' not found.", agent);
+                }
+            }
+            _ if command.starts_with("seek ") => {
+                match parse_number(command.trim_start_matches("seek "), 0..) {
+                    Ok(tick) => {
+                        self.send(UIToSimulation::Seek(tick));
+                        self.simulation_status = format!("Seeking to tick {}...", tick);
+                    }
+                    Err(e) => {
+                        self.simulation_status = format!("Invalid tick: {}", e);
+                    }
+                }
+            }
+            _ if command.starts_with("event ") => {
+                let event = command.trim_start_matches("event ").trim().to_string();
+                self.send(UIToSimulation::WorldEvent(event.clone()));
+                self.simulation_status = format!("Event: {}", event);
+            }
+            _ if command.starts_with("remove ") => {
+                let agent = command.trim_start_matches("remove ").trim().to_string();
+                self.send(UIToSimulation::RemoveAgent(agent.clone()));
+                self.simulation_status = format!("Removing {}...", agent);
+            }
+            _ if command.starts_with("mute ") => {
+                let agent = command.trim_start_matches("mute ").trim().to_string();
+                self.send(UIToSimulation::Mute(agent.clone(), true));
+                self.simulation_status = format!("Muting {}...", agent);
+            }
+            _ if command.starts_with("unmute ") => {
+                let agent = command.trim_start_matches("unmute ").trim().to_string();
+                self.send(UIToSimulation::Mute(agent.clone(), false));
+                self.simulation_status = format!("Unmuting {}...", agent);
+            }
+            _ if command.starts_with("inspect ") => {
+                let agent = command.trim_start_matches("inspect ").trim().to_string();
+                self.send(UIToSimulation::Inspect(agent.clone()));
+                self.simulation_status = format!("Fetching the last prompt sent for {}...", agent);
+            }
+            _ if command.starts_with("set ") => {
+                let parts: Vec<&str> = command.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_, agent, trait_name, value] => match parse_number(value, 0.0..=1.0) {
+                        Ok(value) => {
+                            self.send(UIToSimulation::SetTrait(
+                                agent.to_string(),
+                                trait_name.to_lowercase(),
+                                value,
+                            ));
+                            self.simulation_status =
+                                format!("Setting {}'s {}...", agent, trait_name);
+                        }
+                        Err(e) => {
+                            self.simulation_status = format!("Invalid {} value: {}", trait_name, e);
+                        }
+                    },
+                    _ => {
+                        self.simulation_status =
+                            "Incorrect format. Use: set <agent> <trait> <value>".to_string();
+                    }
+                }
+            }
             _ if command.starts_with("msg ") => {
                 let parts: Vec<&str> = command.splitn(3, ' ').collect();
                 if parts.len() == 3 {
                     let agent_name = parts[1];
                     let message = parts[2];
-                    let _ = self.ui_tx.send(UIToSimulation::UserMessage(
+                    self.send(UIToSimulation::UserMessage(
                         agent_name.to_string(),
                         message.to_string(),
                     ));
@@ -168,13 +1044,44 @@ impl UI {
                 }
             }
             _ => {
+                self.simulation_status = self.strings.unrecognized_command.clone();
+            }
+        }
+
+        // A failed send means the simulation thread is gone
+        if self.simulation_stopped && !local {
+            self.simulation_status = self.strings.simulation_stopped.clone();
+        }
+    }
+
+    /// Re-read the appearance settings from the configuration file and apply them live
+    fn reload_ui_config(&mut self) {
+        match Config::load(&self.config_path) {
+            Ok((config, warnings)) => {
+                self.palette = Palette::from_config(&config.ui);
+                self.layout = PanelLayout::from_config(&config.ui);
+                self.max_split_columns = config.ui.max_split_columns;
+                self.show_wait_estimates = config.ui.show_wait_estimates;
+                self.min_size = (config.ui.min_width, config.ui.min_height);
+                self.simulation_status = match warnings.first() {
+                    Some(warning) => format!("UI settings reloaded ({})", warning),
+                    None => "UI settings reloaded".to_string(),
+                };
+            }
+            Err(e) => {
                 self.simulation_status =
-                    "Unrecognized command. Try 'start', 'pause', 'resume', 'stop', 'topic <subject>', 'msg <agent> <message>' or 'exit'."
-                        .to_string();
+                    format!("Could not reload {}: {}", self.config_path.display(), e);
             }
         }
     }
 
+    /// Send a command to the simulation, noting when its channel has closed
+    fn send(&mut self, command: UIToSimulation) {
+        if self.ui_tx.send(command).is_err() {
+            self.simulation_stopped = true;
+        }
+    }
+
     /// Main UI loop
     pub fn run(&mut self) -> Result<(), io::Error> {
         // Terminal setup
@@ -185,24 +1092,20 @@ impl UI {
         let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
 
         // Render splash screen
-        self.render_splash_screen(&mut terminal)?;
-
-        // Show welcome message
-        self.messages.push_back(FormattedMessage {
-            sender: "System".to_string(),
-            sender_color: Color::Blue,
-            recipient: "User".to_string(),
-            recipient_color: Color::White,
-            content: "Welcome to Protopolis! Type commands below to interact.".to_string(),
-        });
+        if !self.skip_splash {
+            self.render_splash_screen(&mut terminal)?;
+        }
 
-        self.messages.push_back(FormattedMessage {
-            sender: "System".to_string(),
-            sender_color: Color::Blue,
-            recipient: "User".to_string(),
-            recipient_color: Color::White,
-            content: "Available commands: start, pause, resume, stop, topic <subject>, msg <agent> <message>, exit".to_string(),
-        });
+        // Show welcome messages
+        let commands = COMMANDS
+            .iter()
+            .map(|c| c.usage)
+            .collect::<Vec<_>>()
+            .join(", ");
+        for text in std::mem::take(&mut self.welcome_messages) {
+            let text = prompt::render(&text, &[("commands", &commands)]);
+            self.add_message(&Message::new("System", "User", text));
+        }
 
         let tick_rate = Duration::from_millis(100);
         let mut last_tick = Instant::now();
@@ -217,18 +1120,33 @@ impl UI {
 
             // Check for events
             if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
+                let event = event::read()?;
+                if let Event::Resize(width, height) = event {
+                    self.handle_resize(width, height);
+                    // Repaint everything rather than diffing against the old size
+                    terminal.clear()?;
+                }
+                if let Event::Key(key) = event {
                     if key.kind == KeyEventKind::Press {
                         match key.code {
+                            // An open overlay takes the scrolling keys
+                            KeyCode::Esc if self.overlay.is_some() => {
+                                self.overlay = None;
+                            }
+                            KeyCode::PageUp if self.overlay.is_some() => {
+                                self.overlay_scroll = self.overlay_scroll.saturating_sub(10);
+                            }
+                            KeyCode::PageDown if self.overlay.is_some() => {
+                                self.overlay_scroll = self.overlay_scroll.saturating_add(10);
+                            }
+                            KeyCode::Char('?') if self.input.is_empty() => self.show_help(),
                             KeyCode::Enter => {
                                 let input_clone = self.input.clone();
                                 self.process_command(&input_clone);
                                 self.input.clear();
                             }
-                            KeyCode::Char(c) => {
-                                if c.is_alphanumeric() || c.is_whitespace() {
-                                    self.input.push(c);
-                                }
+                            KeyCode::Char(c) if !c.is_control() => {
+                                self.input.push(c);
                             }
                             KeyCode::Backspace => {
                                 self.input.pop();
@@ -236,22 +1154,23 @@ impl UI {
                             KeyCode::Esc => {
                                 self.should_quit = true;
                             }
-                            KeyCode::PageUp => {
-                                self.message_scroll = self.message_scroll.saturating_sub(10);
-                                self.message_scroll_state =
-                                    self.message_scroll_state.position(self.message_scroll);
+                            KeyCode::Left if self.split_view => {
+                                self.split_focus = self.split_focus.saturating_sub(1);
                             }
-                            KeyCode::PageDown => {
-                                self.message_scroll = self.message_scroll.saturating_add(10);
-                                self.message_scroll_state =
-                                    self.message_scroll_state.position(self.message_scroll);
-                            }
-                            KeyCode::Home => {
-                                self.message_scroll = 0;
-                                self.message_scroll_state = self.message_scroll_state.position(0);
+                            KeyCode::Right if self.split_view => {
+                                let last = self.conversation_pairs().len().saturating_sub(1);
+                                self.split_focus = (self.split_focus + 1).min(last);
                             }
+                            KeyCode::PageUp if self.split_view => self.scroll_split(10),
+                            KeyCode::PageDown if self.split_view => self.scroll_split(-10),
+                            KeyCode::Home if self.split_view => self.scroll_split(isize::MAX),
+                            KeyCode::End if self.split_view => self.scroll_split(isize::MIN),
+                            KeyCode::PageUp => self.scroll_messages(-10),
+                            KeyCode::PageDown => self.scroll_messages(10),
+                            KeyCode::Home => self.scroll_messages(isize::MIN),
                             KeyCode::End => {
-                                self.message_scroll = self.messages.len();
+                                self.follow_tail = true;
+                                self.message_scroll = self.max_message_scroll.get();
                                 self.message_scroll_state =
                                     self.message_scroll_state.position(self.message_scroll);
                             }
@@ -262,26 +1181,82 @@ impl UI {
             }
 
             // Check for simulation updates
-            while let Ok(update) = self.ui_rx.try_recv() {
+            loop {
+                let update = match self.ui_rx.try_recv() {
+                    Ok(update) => update,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        // Keep the final status the simulation sent before exiting
+                        self.simulation_stopped = true;
+                        break;
+                    }
+                };
                 match update {
                     SimulationToUI::TickUpdate(tick) => {
                         self.current_tick = tick;
                     }
-                    SimulationToUI::AgentUpdate(name, state, energy) => {
-                        self.agent_states.insert(name, (state, energy));
+                    SimulationToUI::AgentUpdate(name, state, energy, mood) => {
+                        self.set_typing(&name, &state);
+                        self.agent_states.insert(name, (state, energy, mood));
                     }
                     SimulationToUI::MessageUpdate(message) => {
+                        self.typing.remove(&message.sender);
                         self.add_message(&message);
                     }
                     SimulationToUI::StateUpdate(state) => {
                         self.simulation_status = state;
                     }
+                    SimulationToUI::AgentJoined(name, model) => {
+                        // The starting roster is shown in the agents panel already
+                        if self.current_tick > 0 {
+                            self.add_system_message(format!("{} joined ({})", name, model));
+                        }
+                        self.agent_models.insert(name, model);
+                    }
+                    SimulationToUI::AgentLeft(name) => {
+                        self.agent_states.remove(&name);
+                        self.agent_models.remove(&name);
+                        self.agent_colors.remove(&name);
+                        self.typing.remove(&name);
+                        self.wait_estimates.remove(&name);
+                        self.muted.remove(&name);
+                        if self.focused_agent.as_ref() == Some(&name) {
+                            self.focused_agent = None;
+                        }
+                        self.add_system_message(format!("{} left the simulation", name));
+                    }
+                    SimulationToUI::Snapshot(snapshot) => {
+                        self.apply_snapshot(snapshot);
+                    }
+                    SimulationToUI::WaitEstimate(name, seconds) => {
+                        self.wait_estimates.insert(name, seconds);
+                    }
+                    SimulationToUI::AgentMuted(name, muted) => {
+                        self.set_muted(&name, muted);
+                    }
+                    SimulationToUI::TopicChanged(topic) => {
+                        self.topic = Some(topic);
+                    }
+                    SimulationToUI::Prompt(agent, prompt) => {
+                        self.overlay = Some((format!("Prompt sent for {}", agent), prompt));
+                        self.overlay_scroll = 0;
+                        self.simulation_status =
+                            "PageUp/PageDown to scroll the prompt, Esc to close".to_string();
+                    }
+                    SimulationToUI::ClearMessages => {
+                        self.messages.clear();
+                        self.message_scroll = 0;
+                        self.follow_tail = true;
+                        self.search_match = None;
+                        self.split_scroll.clear();
+                    }
                 }
             }
 
             // Check if we should tick
             if last_tick.elapsed() >= tick_rate {
                 last_tick = Instant::now();
+                self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
             }
         }
 
@@ -300,120 +1275,340 @@ impl UI {
 
     /// Draw the UI
     fn ui(&self, f: &mut Frame) {
+        // Below the minimum size the panels would get zero-height areas; drawing resumes once
+        // the terminal is resized
+        let (min_width, min_height) = self.min_size;
+        if f.area().width < min_width || f.area().height < min_height {
+            self.render_too_small(f);
+            return;
+        }
+
         // Create the layout
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3), // Title
-                Constraint::Min(5),    // Main content
-                Constraint::Length(3), // Input
-            ])
-            .split(f.area());
+        let chunks = screen_chunks(f.area());
 
         // Title bar with status
-        let title = Paragraph::new(vec![Line::from(vec![
+        let mut status = vec![
             Span::styled("Protopolis", Style::default().fg(Color::Cyan)),
             Span::raw(" | "),
-            Span::raw(format!("Tick: {}", self.current_tick)),
+            Span::raw(format!(
+                "{}: {}",
+                self.strings.tick_label, self.current_tick
+            )),
+            Span::raw(" | "),
+            Span::raw(format!("{}: ", self.strings.topic_label)),
+            Span::styled(
+                self.topic.as_deref().unwrap_or(&self.strings.no_topic),
+                Style::default().fg(Color::LightMagenta),
+            ),
             Span::raw(" | "),
             Span::raw(&self.simulation_status),
-        ])])
-        .block(Block::default().borders(Borders::ALL).title("Status"));
-        f.render_widget(title, chunks[0]);
+        ];
 
-        // Split the main content area
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(70), // Messages
-                Constraint::Percentage(30), // Agent states
-            ])
-            .split(chunks[1]);
+        // Match count of the active search
+        if self.search_query.is_some() {
+            let matches = self.search_matches();
+            let current = self
+                .search_match
+                .and_then(|m| matches.iter().position(|&i| i == m))
+                .map_or(0, |p| p + 1);
+            status.push(Span::raw(" | "));
+            status.push(Span::styled(
+                format!("Find: {}/{}", current, matches.len()),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        // Show activity while agents are waiting on the model, with how long each usually takes
+        let mut thinking: Vec<&String> = self
+            .agent_states
+            .iter()
+            .filter(|(_, (state, _, _))| *state == AgentState::Thinking)
+            .map(|(name, _)| name)
+            .collect();
+        thinking.sort();
+        if !thinking.is_empty() {
+            let waiting = if self.show_wait_estimates {
+                thinking
+                    .iter()
+                    .map(|name| self.wait_label(name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                format!(
+                    "{} agent{}",
+                    thinking.len(),
+                    if thinking.len() == 1 { "" } else { "s" }
+                )
+            };
+            status.push(Span::raw(" | "));
+            status.push(Span::styled(
+                format!(
+                    "{} Thinking... ({})",
+                    SPINNER_FRAMES[self.spinner_frame], waiting
+                ),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        let title = Paragraph::new(vec![Line::from(status)]).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.strings.status_title.as_str()),
+        );
+        f.render_widget(title, chunks[0]);
 
         // Messages area
-        self.render_messages_panel(f, main_chunks[0]);
+        let (messages_area, agents_area) = self.main_columns(chunks[1]);
+        self.render_messages_panel(f, messages_area);
 
         // Agent states panel
-        self.render_agent_states_panel(f, main_chunks[1]);
+        if let Some(agents_area) = agents_area {
+            self.render_agent_states_panel(f, agents_area);
+        }
 
         // Input field
         let input = Paragraph::new(self.input.as_str())
             .style(Style::default())
-            .block(Block::default().borders(Borders::ALL).title("Input"));
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.strings.input_title.as_str()),
+            );
         f.render_widget(input, chunks[2]);
 
-        // Set cursor position
+        // Prompt overlay on top of the messages and agents panels
+        if let Some((title, text)) = &self.overlay {
+            render_overlay(f, chunks[1], title, text, self.overlay_scroll);
+        }
+
+        // Set cursor position (by display width, not bytes, so multi-byte input lines up)
+        let input_width = Span::raw(self.input.as_str()).width() as u16;
         f.set_cursor_position(Position::new(
-            chunks[2].x + self.input.len() as u16 + 1,
+            chunks[2].x + input_width + 1,
             chunks[2].y + 1,
         ));
     }
 
-    /// Render the messages panel
+    /// Render the notice asking for a larger terminal in place of the layout
+    fn render_too_small(&self, f: &mut Frame) {
+        let (min_width, min_height) = self.min_size;
+        let notice = prompt::render(
+            &self.strings.terminal_too_small,
+            &[
+                ("width", &min_width.to_string()),
+                ("height", &min_height.to_string()),
+            ],
+        );
+        let paragraph = Paragraph::new(notice)
+            .style(Style::default().fg(Color::LightYellow))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, f.area());
+    }
+
+    /// Split the main content area into the messages and the agents panel, if shown
+    fn main_columns(&self, area: Rect) -> (Rect, Option<Rect>) {
+        // Messages take the whole width when the agents panel is hidden
+        if !self.layout.show_agents_panel {
+            return (area, None);
+        }
+
+        let main_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(self.layout.messages_pct), // Messages
+                Constraint::Percentage(100 - self.layout.messages_pct), // Agent states
+            ])
+            .split(area);
+        (main_chunks[0], Some(main_chunks[1]))
+    }
+
+    /// Re-clamp the message scroll against the messages viewport of a resized terminal
+    ///
+    /// # Arguments
+    /// * `width` - The new terminal width.
+    /// * `height` - The new terminal height.
+    fn handle_resize(&mut self, width: u16, height: u16) {
+        let chunks = screen_chunks(Rect::new(0, 0, width, height));
+        let (area, _) = self.main_columns(chunks[1]);
+        self.messages_width.set(area.width);
+
+        // Same line count as the single-column view, typing indicators included
+        let content_height = self
+            .messages
+            .iter()
+            .filter(|m| self.is_visible(m))
+            .map(|m| message_lines(m, None, area.width, Clock::Absolute).len())
+            .sum::<usize>()
+            + self.typing.len();
+        let viewport_height = area.height.saturating_sub(2) as usize; // -2 for borders
+        let max_scroll = content_height.saturating_sub(viewport_height);
+        self.max_message_scroll.set(max_scroll);
+
+        self.message_scroll = if self.follow_tail {
+            max_scroll
+        } else {
+            self.message_scroll.min(max_scroll)
+        };
+        self.message_scroll_state = self
+            .message_scroll_state
+            .content_length(content_height)
+            .position(self.message_scroll);
+    }
+
+    /// Render the messages panel, split by conversation pair when enabled
     fn render_messages_panel(&self, f: &mut Frame, area: Rect) {
-        // Create message content with proper text wrapping
-        let mut text = Vec::new();
-        for m in &self.messages {
-            // Header line with sender and recipient
-            text.push(Line::from(vec![
-                Span::styled(
-                    format!("[{}]", m.sender),
-                    Style::default().fg(m.sender_color),
-                ),
-                Span::raw(" to "),
-                Span::styled(
-                    format!("[{}]:", m.recipient),
-                    Style::default().fg(m.recipient_color),
-                ),
-            ]));
+        if self.split_view {
+            let pairs = self.conversation_pairs();
+            if !pairs.is_empty() && pairs.len() <= self.max_split_columns {
+                self.render_split_messages(f, area, &pairs);
+                return;
+            }
+        }
+
+        // Remember the width so searches can compute scroll offsets between frames
+        self.messages_width.set(area.width);
 
-            // Content line with automatic wrapping
-            text.push(Line::from(Span::raw(&m.content)));
+        // Create message content with proper text wrapping
+        let highlight = self.search_query.as_deref();
+        let clock = self.clock();
+        let mut text: Vec<Line> = self
+            .messages
+            .iter()
+            .filter(|m| self.is_visible(m))
+            .flat_map(|m| message_lines(m, highlight, area.width, clock))
+            .collect();
 
-            // Empty line as separator
-            text.push(Line::from(""));
+        // Typing indicators follow the real messages without being part of the history
+        for agent in &self.typing {
+            let color = self.agent_colors.get(agent).copied().unwrap_or(Color::Gray);
+            let agent = if self.show_wait_estimates {
+                self.wait_label(agent)
+            } else {
+                agent.clone()
+            };
+            text.push(Line::from(Span::styled(
+                format!("{} is typing…", agent),
+                Style::default().fg(color).add_modifier(Modifier::ITALIC),
+            )));
         }
 
         // Calculate appropriate scroll position
-        let content_height = text.len();
         let viewport_height = area.height.saturating_sub(2) as usize; // -2 for borders
-        let max_scroll = content_height.saturating_sub(viewport_height);
-        let scroll = self.message_scroll.min(max_scroll);
-
-        // Render the message content with scroll applied
-        let messages_widget = Paragraph::new(text)
-            .block(Block::default().borders(Borders::ALL).title("Messages"))
-            .wrap(ratatui::widgets::Wrap { trim: true })
-            .scroll((scroll as u16, 0));
-
-        f.render_widget(messages_widget, area);
-
-        // Render the scrollbar if content exceeds viewport
-        if content_height > viewport_height {
-            f.render_stateful_widget(
-                Scrollbar::default()
-                    .orientation(ScrollbarOrientation::VerticalRight)
-                    .begin_symbol(Some("↑"))
-                    .end_symbol(Some("↓")),
-                area.inner(Margin {
-                    vertical: 1,
-                    horizontal: 0,
-                }),
-                &mut self
-                    .message_scroll_state
-                    .clone()
-                    .content_length(content_height)
-                    .position(scroll),
-            );
+        let max_scroll = text.len().saturating_sub(viewport_height);
+        self.max_message_scroll.set(max_scroll);
+        let scroll = if self.follow_tail {
+            max_scroll
+        } else {
+            self.message_scroll.min(max_scroll)
+        };
+
+        let title = if self.split_view {
+            self.strings.messages_unsplit_title.as_str()
+        } else {
+            self.strings.messages_title.as_str()
+        };
+        render_message_column(f, area, title.to_string(), text, scroll, Style::default());
+    }
+
+    /// How message timestamps are shown in this frame
+    fn clock(&self) -> Clock {
+        if self.relative_times {
+            Clock::Relative(Utc::now())
+        } else {
+            Clock::Absolute
+        }
+    }
+
+    /// Render one scrollable column per conversation pair
+    fn render_split_messages(&self, f: &mut Frame, area: Rect, pairs: &[(String, String)]) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, pairs.len() as u32); pairs.len()])
+            .split(area);
+        let focus = self.split_focus.min(pairs.len() - 1);
+        let clock = self.clock();
+
+        for (i, (pair, column)) in pairs.iter().zip(columns.iter()).enumerate() {
+            let text: Vec<Line> = self
+                .messages
+                .iter()
+                .filter(|m| self.is_visible(m) && pair_key(&m.sender, &m.recipient) == *pair)
+                .flat_map(|m| message_lines(m, None, column.width, clock))
+                .collect();
+
+            // Columns follow the latest message unless scrolled back
+            let viewport_height = column.height.saturating_sub(2) as usize;
+            let max_scroll = text.len().saturating_sub(viewport_height);
+            let offset = self.split_scroll.get(pair).copied().unwrap_or(0);
+            let scroll = max_scroll.saturating_sub(offset);
+
+            let border_style = if i == focus {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            };
+            let title = format!("{} ↔ {}", pair.0, pair.1);
+            render_message_column(f, *column, title, text, scroll, border_style);
+        }
+    }
+
+    /// Whether a message belongs to the room being viewed; messages outside rooms always do
+    fn is_visible(&self, m: &FormattedMessage) -> bool {
+        match (&self.room, &m.room) {
+            (Some(viewed), Some(room)) => viewed == room,
+            _ => true,
+        }
+    }
+
+    /// Lists the conversation pairs in the message history, in order of first message
+    fn conversation_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        for m in self.messages.iter().filter(|m| self.is_visible(m)) {
+            // System notices are not part of any conversation
+            if m.sender == "System" {
+                continue;
+            }
+            let pair = pair_key(&m.sender, &m.recipient);
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+        pairs
+    }
+
+    /// Scroll the messages panel forward by `lines` (negative scrolls back), following new
+    /// messages again once the bottom is reached
+    fn scroll_messages(&mut self, lines: isize) {
+        let max_scroll = self.max_message_scroll.get();
+        self.message_scroll = self
+            .message_scroll
+            .min(max_scroll)
+            .saturating_add_signed(lines)
+            .min(max_scroll);
+        self.follow_tail = self.message_scroll == max_scroll;
+        self.message_scroll_state = self.message_scroll_state.position(self.message_scroll);
+    }
+
+    /// Scroll the focused column of the split view back by `lines` (negative scrolls forward)
+    fn scroll_split(&mut self, lines: isize) {
+        let pairs = self.conversation_pairs();
+        if let Some(pair) = pairs.get(self.split_focus.min(pairs.len().saturating_sub(1))) {
+            let offset = self.split_scroll.entry(pair.clone()).or_insert(0);
+            *offset = offset.saturating_add_signed(lines);
         }
     }
 
     /// Render the agent states panel
     fn render_agent_states_panel(&self, f: &mut Frame, area: Rect) {
+        // The gauge fills the panel's inner width, after its indent and numeric label
+        let bar_width =
+            (area.width as usize).saturating_sub(2 + ENERGY_INDENT + ENERGY_LABEL_WIDTH);
+
         let agents: Vec<ListItem> = self
             .agent_states
             .iter()
-            .map(|(name, (state, energy))| {
+            .map(|(name, (state, energy, mood))| {
                 let state_color = match state {
                     AgentState::Idle => Color::DarkGray,
                     AgentState::Thinking => Color::Yellow,
@@ -429,22 +1624,55 @@ impl UI {
                     Color::Green
                 };
 
+                let mood = Mood::from_value(*mood);
+                let mood_color = match mood {
+                    Mood::Happy => Color::LightGreen,
+                    Mood::Neutral => Color::DarkGray,
+                    Mood::Tense => Color::LightRed,
+                };
+
                 let agent_color = self.agent_colors.get(name).unwrap_or(&Color::White);
+                let model = self.agent_models.get(name).map_or("?", String::as_str);
 
-                let content = Line::from(vec![
+                let mut header = Line::from(vec![
+                    Span::raw(self.agent_avatar(name)),
                     Span::styled(name, Style::default().fg(*agent_color)),
+                    Span::styled(
+                        format!(" ({})", model),
+                        Style::default().fg(Color::DarkGray),
+                    ),
                     Span::raw(" - "),
                     Span::styled(format!("{}", state), Style::default().fg(state_color)),
-                    Span::raw(" - "),
-                    Span::styled(format!("{:.1}", energy), Style::default().fg(energy_color)),
+                    Span::raw(" "),
+                    Span::styled(mood.glyph(), Style::default().fg(mood_color)),
                 ]);
+                if self.muted.contains(name) {
+                    header.push_span(Span::styled(
+                        " 🔇 muted",
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
 
-                ListItem::new(content)
+                let (filled, empty) = energy_bar(*energy, self.energy_cap, bar_width);
+                let gauge = Line::from(vec![
+                    Span::raw(" ".repeat(ENERGY_INDENT)),
+                    Span::styled(filled, Style::default().fg(energy_color)),
+                    Span::styled(empty, Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        format!("{:>width$.1}", energy, width = ENERGY_LABEL_WIDTH),
+                        Style::default().fg(energy_color),
+                    ),
+                ]);
+
+                ListItem::new(vec![header, gauge])
             })
             .collect();
 
-        let agents_list =
-            List::new(agents).block(Block::default().borders(Borders::ALL).title("Agents"));
+        let agents_list = List::new(agents).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.strings.agents_title.as_str()),
+        );
 
         f.render_widget(agents_list, area);
     }
@@ -472,7 +1700,7 @@ Yb,  88      `8b                      I8
                                                          I8                                             
 
 
-<Press SPACE to continue>
+<Press any key to continue>
         "#;
         loop {
             terminal.draw(|f| {
@@ -490,16 +1718,15 @@ Yb,  88      `8b                      I8
                 f.render_widget(paragraph, size);
             })?;
 
-            // Wait for the space key press to continue
-
+            // Wait for any key press to continue
             if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char(' ') {
+                if key.kind == KeyEventKind::Press {
                     break;
                 }
             }
         }
 
-        // Clear the input buffer
+        // Clear the input buffer so the key that dismissed the splash isn't typed
         while event::poll(Duration::from_millis(0))? {
             event::read()?;
         }
@@ -507,3 +1734,508 @@ Yb,  88      `8b                      I8
         Ok(())
     }
 }
+
+/// How message timestamps are shown
+#[derive(Clone, Copy)]
+enum Clock {
+    /// Local time of day, as HH:MM:SS
+    Absolute,
+    /// Time elapsed until the given instant, as "12s ago"
+    Relative(DateTime<Utc>),
+}
+
+impl Clock {
+    /// Format a timestamp compactly for a message header
+    fn format(self, timestamp: DateTime<Utc>) -> String {
+        match self {
+            Clock::Absolute => timestamp
+                .with_timezone(&Local)
+                .format("%H:%M:%S")
+                .to_string(),
+            Clock::Relative(now) => {
+                let secs = (now - timestamp).num_seconds().max(0);
+                match secs {
+                    0..60 => format!("{}s ago", secs),
+                    60..3600 => format!("{}m ago", secs / 60),
+                    3600..86400 => format!("{}h ago", secs / 3600),
+                    _ => format!("{}d ago", secs / 86400),
+                }
+            }
+        }
+    }
+}
+
+/// Build the header, content, and separator lines of a message
+///
+/// Content is wrapped to the column and indented under the header, behind a gutter in the
+/// sender's color, so one line here is one row on screen.
+///
+/// # Arguments
+/// * `m` - The message to render.
+/// * `highlight` - A lowercase search query whose occurrences are highlighted in the content.
+/// * `width` - Width of the bordered column the message is shown in.
+/// * `clock` - How the message's timestamp is shown in its header.
+fn message_lines<'a>(
+    m: &'a FormattedMessage,
+    highlight: Option<&str>,
+    width: u16,
+    clock: Clock,
+) -> Vec<Line<'a>> {
+    let gutter = Span::styled(MESSAGE_GUTTER, Style::default().fg(m.sender_color));
+    let content_width = (width as usize).saturating_sub(2 + gutter.width() + MESSAGE_INDENT);
+
+    // Content tinted by sentiment
+    let mut content_style = match m.sentiment {
+        Some(Sentiment::Positive) => Style::default().fg(Color::LightGreen),
+        Some(Sentiment::Negative) => Style::default().fg(Color::LightRed),
+        _ => Style::default(),
+    };
+    // Moderator interventions stand out from the discussion itself
+    if m.from_moderator {
+        content_style = content_style
+            .fg(m.sender_color)
+            .add_modifier(Modifier::ITALIC);
+    }
+    // Observer summaries are asides for the user, dimmed to set them apart from the discussion
+    if m.from_observer {
+        content_style = content_style
+            .fg(m.sender_color)
+            .add_modifier(Modifier::ITALIC | Modifier::DIM);
+    }
+    // World events interrupt the discussion, so they are shown in bold
+    if m.from_world {
+        content_style = content_style
+            .fg(m.sender_color)
+            .add_modifier(Modifier::BOLD);
+    }
+
+    // Header line with sender and recipient
+    let mut lines = vec![Line::from(vec![
+        gutter.clone(),
+        Span::styled(
+            format!("{} ", clock.format(m.timestamp)),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(
+            m.room
+                .as_ref()
+                .map_or_else(String::new, |room| format!("#{} ", room)),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::raw(m.sender_avatar.as_deref().unwrap_or_default()),
+        Span::styled(
+            format!("[{}]", m.sender),
+            Style::default().fg(m.sender_color),
+        ),
+        Span::raw(" to "),
+        Span::raw(m.recipient_avatar.as_deref().unwrap_or_default()),
+        Span::styled(
+            format!("[{}]:", m.recipient),
+            Style::default().fg(m.recipient_color),
+        ),
+    ])];
+
+    let indented = |spans: Vec<Span<'a>>| {
+        let mut line = vec![gutter.clone(), Span::raw(" ".repeat(MESSAGE_INDENT))];
+        line.extend(spans);
+        Line::from(line)
+    };
+
+    for row in wrap_text(&m.content, content_width) {
+        lines.push(indented(highlight_spans(row, highlight, content_style)));
+    }
+
+    // Structured parts (citations, actions, tool calls) on their own dimmed lines
+    for (label, value) in &m.parts {
+        let part = format!("↳ {}: {}", label, value);
+        for row in wrap_text(&part, content_width) {
+            lines.push(indented(vec![Span::styled(
+                row.to_string(),
+                Style::default().fg(Color::DarkGray),
+            )]));
+        }
+    }
+
+    // Empty line as separator
+    lines.push(Line::from(""));
+    lines
+}
+
+/// Render text, such as a prompt sent for an agent or the help, in a scrollable overlay
+///
+/// # Arguments
+/// * `area` - The area the overlay is centered in.
+/// * `title` - What the text is.
+/// * `text` - The text to show.
+/// * `scroll` - Requested scroll offset, clamped to the text length.
+fn render_overlay(f: &mut Frame, area: Rect, title: &str, text: &str, scroll: usize) {
+    let overlay = area.inner(Margin {
+        vertical: area.height / 10,
+        horizontal: area.width / 10,
+    });
+    let text: Vec<Line> = wrap_text(text, overlay.width.saturating_sub(2) as usize)
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    let viewport_height = overlay.height.saturating_sub(2) as usize;
+    let max_scroll = text.len().saturating_sub(viewport_height);
+    let scroll = scroll.min(max_scroll);
+
+    let title = if max_scroll > 0 {
+        format!(
+            "{} (lines {}-{} of {}, Esc to close)",
+            title,
+            scroll + 1,
+            (scroll + viewport_height).min(text.len()),
+            text.len()
+        )
+    } else {
+        format!("{} (Esc to close)", title)
+    };
+
+    f.render_widget(Clear, overlay);
+    render_message_column(
+        f,
+        overlay,
+        title,
+        text,
+        scroll,
+        Style::default().fg(Color::Yellow),
+    );
+}
+
+/// Word-wrap text to a display width, keeping its line breaks
+///
+/// Words wider than the width are split across rows.
+///
+/// # Arguments
+/// * `text` - The text to wrap.
+/// * `width` - The maximum display width of a row.
+///
+/// # Returns
+/// * The rows, as slices of `text` without their surrounding spaces.
+fn wrap_text(text: &str, width: usize) -> Vec<&str> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut row: Option<(usize, usize)> = None; // (start, end)
+
+        for (start, word) in words(paragraph) {
+            if let Some((row_start, row_end)) = row {
+                // Measure the slice itself, since the text may have several spaces between words
+                let extended_width = Span::raw(&paragraph[row_start..start + word.len()]).width();
+                if extended_width <= width {
+                    row = Some((row_start, start + word.len()));
+                    continue;
+                }
+                rows.push(&paragraph[row_start..row_end]);
+            }
+
+            // Start a new row with this word, splitting it while it is too wide
+            let mut chunk_start = start;
+            let mut chunk_width = 0;
+            for (offset, c) in word.char_indices() {
+                let c_width = Span::raw(&word[offset..offset + c.len_utf8()]).width();
+                if chunk_width + c_width > width && chunk_width > 0 {
+                    rows.push(&paragraph[chunk_start..start + offset]);
+                    chunk_start = start + offset;
+                    chunk_width = 0;
+                }
+                chunk_width += c_width;
+            }
+            row = Some((chunk_start, start + word.len()));
+        }
+
+        match row {
+            Some((row_start, row_end)) => rows.push(&paragraph[row_start..row_end]),
+            None => rows.push(""),
+        }
+    }
+    rows
+}
+
+/// Split text into words, with the byte offset each word starts at
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                words.push((start, &text[start..i]));
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(start) = word_start {
+        words.push((start, &text[start..]));
+    }
+    words
+}
+
+/// Avatars assigned to agents without one, all a single two-column emoji
+const DEFAULT_AVATARS: [&str; 16] = [
+    "🦊", "🐻", "🐼", "🦉", "🐙", "🦁", "🐸", "🐧", "🦄", "🐢", "🐝", "🦋", "🐳", "🦔", "🐯", "🐨",
+];
+
+/// Columns reserved for an avatar, so names line up whatever glyph precedes them
+const AVATAR_WIDTH: usize = 2;
+
+/// Pick a default avatar from an agent's name, stable across runs
+///
+/// # Arguments
+/// * `name` - The agent's name.
+///
+/// # Returns
+/// * One of the default avatars.
+fn default_avatar(name: &str) -> &'static str {
+    DEFAULT_AVATARS[name_hash(name) as usize % DEFAULT_AVATARS.len()]
+}
+
+/// Hash an agent's name into a number stable across runs
+///
+/// # Arguments
+/// * `name` - The agent's name.
+///
+/// # Returns
+/// * The FNV-1a hash of the name.
+fn name_hash(name: &str) -> u32 {
+    // FNV-1a, since the standard hasher is not guaranteed to be stable across releases
+    name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Pad an avatar to its reserved columns, measured by display width rather than bytes
+///
+/// # Arguments
+/// * `avatar` - The emoji or short glyph.
+///
+/// # Returns
+/// * The avatar followed by enough spaces to fill its columns, plus a separating space.
+fn avatar_prefix(avatar: &str) -> String {
+    let padding = AVATAR_WIDTH.saturating_sub(Span::raw(avatar).width());
+    format!("{}{} ", avatar, " ".repeat(padding))
+}
+
+/// Parse a numeric command argument, checking it lies within the accepted range
+///
+/// # Arguments
+/// * `arg` - The argument as typed.
+/// * `range` - The accepted values.
+///
+/// # Returns
+/// * The number, or a message describing what was expected for the status bar.
+fn parse_number<T, R>(arg: &str, range: R) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + Display,
+    R: RangeBounds<T>,
+{
+    let expected = match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(min), Bound::Included(max)) => {
+            format!("expected a number between {} and {}", min, max)
+        }
+        (Bound::Included(min), _) => format!("expected a number of at least {}", min),
+        (_, Bound::Included(max)) => format!("expected a number of at most {}", max),
+        _ => "expected a number".to_string(),
+    };
+    match arg.trim().parse::<T>() {
+        Ok(value) if range.contains(&value) => Ok(value),
+        _ => Err(expected),
+    }
+}
+
+/// Columns before an agent's energy gauge, lining it up under the name
+const ENERGY_INDENT: usize = AVATAR_WIDTH + 1;
+
+/// Columns of the numeric energy label after the gauge, e.g. " 100.0"
+const ENERGY_LABEL_WIDTH: usize = 6;
+
+/// Draw an energy level as the filled and empty parts of a bar
+///
+/// # Arguments
+/// * `energy` - The agent's energy.
+/// * `cap` - The energy filling the whole bar.
+/// * `width` - The bar's width in columns.
+///
+/// # Returns
+/// * The filled and empty parts, together exactly `width` columns wide whatever the energy.
+fn energy_bar(energy: f32, cap: f32, width: usize) -> (String, String) {
+    let ratio = if cap > 0.0 {
+        (energy / cap).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let filled = ((ratio * width as f32).round() as usize).min(width);
+    ("█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Split the screen into the title bar, the main content, and the input field
+fn screen_chunks(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Title
+            Constraint::Min(5),    // Main content
+            Constraint::Length(3), // Input
+        ])
+        .split(area)
+}
+
+/// Render a bordered column of pre-wrapped message lines with a scrollbar when needed
+fn render_message_column(
+    f: &mut Frame,
+    area: Rect,
+    title: String,
+    text: Vec<Line>,
+    scroll: usize,
+    border_style: Style,
+) {
+    let content_height = text.len();
+    let viewport_height = area.height.saturating_sub(2) as usize; // -2 for borders
+
+    // Render the message content with scroll applied
+    let messages_widget = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .title(title),
+        )
+        .scroll((scroll as u16, 0));
+
+    f.render_widget(messages_widget, area);
+
+    // Render the scrollbar if content exceeds viewport
+    if content_height > viewport_height {
+        f.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("↑"))
+                .end_symbol(Some("↓")),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut ScrollbarState::new(content_height).position(scroll),
+        );
+    }
+}
+
+/// Split text into spans, highlighting case-insensitive occurrences of a query
+///
+/// # Arguments
+/// * `text` - The text to split.
+/// * `query` - The lowercase query to highlight, if any.
+/// * `style` - The style of the text outside matches.
+fn highlight_spans<'a>(text: &'a str, query: Option<&str>, style: Style) -> Vec<Span<'a>> {
+    let lowered = text.to_lowercase();
+    // Match offsets are only valid when lowercasing kept every byte in place
+    let query = match query {
+        Some(q) if !q.is_empty() && lowered.len() == text.len() => q,
+        _ => return vec![Span::styled(text, style)],
+    };
+
+    let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, _) in lowered.match_indices(query) {
+        let end = start + query.len();
+        if start < last || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            continue;
+        }
+        if start > last {
+            spans.push(Span::styled(&text[last..start], style));
+        }
+        spans.push(Span::styled(&text[start..end], match_style));
+        last = end;
+    }
+    if last < text.len() {
+        spans.push(Span::styled(&text[last..], style));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Returns a UI on the default configuration, with the ends of its channels.
+    fn setup_ui() -> (UI, Receiver<UIToSimulation>, Sender<SimulationToUI>) {
+        let (ui_tx, sim_rx) = mpsc::channel();
+        let (sim_tx, ui_rx) = mpsc::channel();
+        let ui = UI::new(ui_tx, ui_rx, &Config::default(), Path::new("config.json"));
+        (ui, sim_rx, sim_tx)
+    }
+
+    #[test]
+    fn test_resize_recomputes_layout_and_clamps_scroll() {
+        let (mut ui, _sim_rx, _sim_tx) = setup_ui();
+        for i in 0..20 {
+            ui.add_system_message(format!("Notice {} about the state of the simulation", i));
+        }
+
+        ui.handle_resize(160, 60);
+        let wide = ui.messages_width.get();
+        let tall_max = ui.max_message_scroll.get();
+        assert_eq!(ui.message_scroll, tall_max);
+
+        // Following the tail keeps the latest message in view as the terminal shrinks
+        ui.handle_resize(60, 20);
+        let (area, _) = ui.main_columns(screen_chunks(Rect::new(0, 0, 60, 20))[1]);
+        assert_eq!(ui.messages_width.get(), area.width);
+        assert!(ui.messages_width.get() < wide);
+        let short_max = ui.max_message_scroll.get();
+        assert!(short_max > tall_max);
+        assert_eq!(ui.message_scroll, short_max);
+
+        // A position scrolled back past the end of the new content is clamped to it
+        ui.follow_tail = false;
+        ui.message_scroll = usize::MAX;
+        ui.handle_resize(160, 60);
+        assert_eq!(ui.message_scroll, tall_max);
+    }
+
+    #[test]
+    fn test_resolve_command_expands_aliases_and_prefixes() {
+        // Exact names and aliases win, even where the alias is also a prefix of other commands
+        assert_eq!(resolve_command("stop"), Ok("stop"));
+        assert_eq!(resolve_command("s"), Ok("start"));
+        assert_eq!(resolve_command("p"), Ok("pause"));
+
+        assert_eq!(resolve_command("snap"), Ok("snapshot"));
+        assert_eq!(resolve_command("inter"), Ok("interrupt"));
+
+        let Err(candidates) = resolve_command("st") else {
+            panic!("'st' should be ambiguous");
+        };
+        assert_eq!(candidates, vec!["start", "step", "stop", "stats"]);
+
+        // Words matching no command are left for the caller to report
+        assert_eq!(resolve_command("dance"), Ok("dance"));
+        assert_eq!(resolve_command(""), Ok(""));
+    }
+
+    #[test]
+    fn test_parse_number_checks_range_and_format() {
+        assert_eq!(parse_number::<f32, _>("0.25", 0.0..=1.0), Ok(0.25));
+        assert_eq!(parse_number::<f32, _>(" 1 ", 0.0..=1.0), Ok(1.0));
+        assert_eq!(parse_number::<u64, _>("42", 0..), Ok(42));
+
+        assert_eq!(
+            parse_number::<f32, _>("1.5", 0.0..=1.0),
+            Err("expected a number between 0 and 1".to_string())
+        );
+        assert_eq!(
+            parse_number::<u64, _>("-3", 0..),
+            Err("expected a number of at least 0".to_string())
+        );
+        assert_eq!(
+            parse_number::<f32, _>("high", 0.0..=1.0),
+            Err("expected a number between 0 and 1".to_string())
+        );
+        assert!(parse_number::<u64, _>("", 0..).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+// utils.rs
+
+use crate::agent::Agent;
+use crate::error::Result;
+use crate::message::Message;
+use crate::personality::Personality;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Persisted state of a single agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedAgent {
+    /// Agent's display name.
+    pub name: String,
+
+    /// Energy level at the time of saving.
+    pub energy: f32,
+
+    /// Rolling mood at the time of saving.
+    #[serde(default)]
+    pub mood: f32,
+
+    /// Personality traits at the time of saving.
+    pub personality: Personality,
+
+    /// Conversation history remembered by the agent.
+    pub conversation_history: Vec<String>,
+}
+
+/// A saved simulation session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSession {
+    /// When the session was saved (in UTC).
+    pub saved_at: DateTime<Utc>,
+
+    /// Saved state of every agent.
+    pub agents: Vec<SavedAgent>,
+
+    /// Facts of the shared memory at the time of saving.
+    #[serde(default)]
+    pub global_memory: Vec<String>,
+
+    /// Delayed replies not delivered yet, with the number of ticks left before delivery.
+    #[serde(default)]
+    pub scheduled_messages: Vec<(u64, Message)>,
+}
+
+impl SavedSession {
+    /// Whether the session was saved less than `max_age_hours` ago.
+    pub fn is_recent(&self, max_age_hours: u64) -> bool {
+        let age = Utc::now() - self.saved_at;
+        i64::try_from(max_age_hours).is_ok_and(|hours| age.num_hours() < hours)
+    }
+}
+
+impl From<&Agent> for SavedAgent {
+    fn from(agent: &Agent) -> Self {
+        Self {
+            name: agent.name.clone(),
+            energy: agent.energy,
+            mood: agent.mood,
+            personality: agent.personality.clone(),
+            conversation_history: agent.conversation_history.clone(),
+        }
+    }
+}
+
+/// A shareable agent profile: its name and personality, without any conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentProfile {
+    /// Agent's display name.
+    pub name: String,
+
+    /// Personality traits, including any drift since the agent was configured.
+    pub personality: Personality,
+}
+
+impl From<&Agent> for AgentProfile {
+    fn from(agent: &Agent) -> Self {
+        Self {
+            name: agent.name.clone(),
+            personality: agent.personality.clone(),
+        }
+    }
+}
+
+/// Saves the agents' names and personalities to a JSON profile file.
+///
+/// # Arguments
+/// * `agents` - The agents to save.
+/// * `path` - The profile file (its directory is created if missing).
+///
+/// # Returns
+/// * `Ok(())` once written.
+/// * `Err(Error)` if an error occurs.
+pub fn save_profiles(agents: &[Agent], path: &Path) -> Result<()> {
+    let profiles: Vec<AgentProfile> = agents.iter().map(AgentProfile::from).collect();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&profiles)?)?;
+    Ok(())
+}
+
+/// Loads the profiles previously written by `save_profiles`.
+///
+/// # Arguments
+/// * `path` - The profile file to read.
+///
+/// # Returns
+/// * `Ok(Vec<AgentProfile>)` with one profile per agent.
+/// * `Err(Error)` if an error occurs.
+pub fn load_profiles(path: &Path) -> Result<Vec<AgentProfile>> {
+    let profiles: Vec<AgentProfile> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    Ok(profiles)
+}
+
+/// Saves the agents' conversation histories and the shared memory to a timestamped JSON file.
+///
+/// # Arguments
+/// * `agents` - The agents to save.
+/// * `global_memory` - The facts shared by the agents.
+/// * `dir` - The directory to write the file into (created if missing).
+///
+/// # Returns
+/// * `Ok(PathBuf)` with the path of the written file.
+/// * `Err(Error)` if an error occurs.
+pub fn save_conversations(
+    agents: &[Agent],
+    global_memory: &[String],
+    dir: &Path,
+) -> Result<PathBuf> {
+    let session = SavedSession {
+        saved_at: Utc::now(),
+        agents: agents.iter().map(SavedAgent::from).collect(),
+        global_memory: global_memory.to_vec(),
+        scheduled_messages: Vec::new(),
+    };
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "session-{}.json",
+        session.saved_at.format("%Y%m%d-%H%M%S%3f")
+    ));
+
+    let json = serde_json::to_string_pretty(&session)?;
+    let mut file = File::create(&path)?;
+    file.write_all(json.as_bytes())?;
+    Ok(path)
+}
+
+/// Overwrites the snapshot file with the agents, the shared memory, and the delayed replies.
+///
+/// Unlike `save_conversations`, the file keeps the same name, so it always holds the latest
+/// state of the session.
+///
+/// # Arguments
+/// * `agents` - The agents to save.
+/// * `global_memory` - The facts shared by the agents.
+/// * `scheduled_messages` - Delayed replies, with the number of ticks left before delivery.
+/// * `path` - The snapshot file (its directory is created if missing).
+///
+/// # Returns
+/// * `Ok(())` once written.
+/// * `Err(Error)` if an error occurs.
+pub fn save_snapshot(
+    agents: &[Agent],
+    global_memory: &[String],
+    scheduled_messages: &[(u64, Message)],
+    path: &Path,
+) -> Result<()> {
+    let session = SavedSession {
+        saved_at: Utc::now(),
+        agents: agents.iter().map(SavedAgent::from).collect(),
+        global_memory: global_memory.to_vec(),
+        scheduled_messages: scheduled_messages.to_vec(),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&session)?)?;
+    Ok(())
+}
+
+/// Saves a transcript of messages to a timestamped JSON file.
+///
+/// # Arguments
+/// * `messages` - The messages to save, in chronological order.
+/// * `dir` - The directory to write the file into (created if missing).
+///
+/// # Returns
+/// * `Ok(PathBuf)` with the path of the written file.
+/// * `Err(Error)` if an error occurs.
+pub fn save_transcript(messages: &[&Message], dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "transcript-{}.json",
+        Utc::now().format("%Y%m%d-%H%M%S%3f")
+    ));
+
+    let json = serde_json::to_string_pretty(messages)?;
+    let mut file = File::create(&path)?;
+    file.write_all(json.as_bytes())?;
+    Ok(path)
+}
+
+/// Loads a transcript previously written by `save_transcript`.
+///
+/// # Arguments
+/// * `path` - The transcript file to read.
+///
+/// # Returns
+/// * `Ok(Vec<Message>)` with the messages in chronological order.
+/// * `Err(Error)` if an error occurs.
+pub fn load_transcript(path: &Path) -> Result<Vec<Message>> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let messages: Vec<Message> = serde_json::from_str(&contents)?;
+    Ok(messages)
+}
+
+/// Loads a session previously written by `save_conversations` or `save_snapshot`.
+///
+/// # Arguments
+/// * `path` - The session file to read.
+///
+/// # Returns
+/// * `Ok(SavedSession)` if the file is successfully read and parsed.
+/// * `Err(Error)` if an error occurs.
+pub fn load_conversations(path: &Path) -> Result<SavedSession> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let session: SavedSession = serde_json::from_str(&contents)?;
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::personality::get_personality_template;
+
+    #[test]
+    fn test_save_conversations_round_trip() {
+        let mut agent = Agent::new(
+            "Alice".to_string(),
+            get_personality_template("friendly"),
+            42.5,
+            (0, 0),
+            "llama3.2:latest".to_string(),
+            std::sync::Arc::new(crate::backend::MockBackend::default()),
+        );
+        agent.conversation_history = vec!["[Bob→Alice]: Hello".to_string()];
+
+        let dir = std::env::temp_dir().join(format!("protopolis-test-{}", uuid::Uuid::new_v4()));
+        let facts = vec!["Bob lives downtown".to_string()];
+        let path = save_conversations(&[agent.clone()], &facts, &dir).unwrap();
+        let session = load_conversations(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(session.agents, vec![SavedAgent::from(&agent)]);
+        assert_eq!(session.global_memory, facts);
+    }
+}